@@ -8,7 +8,10 @@ use std::{
 use crate::common::DisplayOsStr;
 use crate::system::escape_os_str_lossy;
 
-use super::resolve::{canonicalize, resolve_path};
+use super::resolve::{
+    canonicalize, canonicalize_in, is_valid_executable, is_valid_executable_in, resolve_path,
+    resolve_path_in,
+};
 
 #[derive(Debug, Default)]
 #[cfg_attr(test, derive(PartialEq))]
@@ -63,7 +66,23 @@ fn is_qualified(path: impl AsRef<Path>) -> bool {
 }
 
 impl CommandAndArguments {
-    pub fn build_from_args(
+    pub fn build_from_args(shell: Option<PathBuf>, arguments: Vec<OsString>, path: &str) -> Self {
+        Self::build(None, shell, arguments, path)
+    }
+
+    /// Like [Self::build_from_args], but the command is looked up inside the directory `root`,
+    /// since that is where it will be executed after a `chroot`.
+    pub fn build_from_args_in_root(
+        root: &Path,
+        shell: Option<PathBuf>,
+        arguments: Vec<OsString>,
+        path: &str,
+    ) -> Self {
+        Self::build(Some(root), shell, arguments, path)
+    }
+
+    fn build(
+        root: Option<&Path>,
         shell: Option<PathBuf>,
         mut arguments: Vec<OsString>,
         path: &str,
@@ -86,7 +105,11 @@ impl CommandAndArguments {
 
             // resolve the command, remembering errors (but not propagating them)
             if !is_qualified(&command) {
-                match resolve_path(&command, path) {
+                let qualified_path = match root {
+                    None => resolve_path(&command, path),
+                    Some(root) => resolve_path_in(root, &command, path),
+                };
+                match qualified_path {
                     Some(qualified_path) => command = qualified_path,
                     None => resolved = false,
                 }
@@ -95,11 +118,28 @@ impl CommandAndArguments {
 
         // resolve symlinks, even if the command was obtained through a PATH or SHELL
         // once again, failure to canonicalize should not stop the pipeline
-        match canonicalize(&command) {
+        // this also normalizes the path: duplicate slashes, `.` and `..` are removed, so that
+        // equivalent spellings of a path match the same sudoers rules; a path that continues
+        // after a file name (such as `/bin/ls/`) fails to canonicalize and is left unresolved
+        let canon_path = match root {
+            None => canonicalize(&command),
+            Some(root) => canonicalize_in(root, &command),
+        };
+        match canon_path {
             Ok(canon_path) => command = canon_path,
             Err(_) => resolved = false,
         }
 
+        // like a command found via PATH, the final command must be an executable file; otherwise
+        // it is reported as not found (instead of failing later on, when it is executed)
+        let executable = match root {
+            None => is_valid_executable(&command),
+            Some(root) => is_valid_executable_in(root, &command),
+        };
+        if resolved && !executable {
+            resolved = false;
+        }
+
         CommandAndArguments {
             command,
             arguments,
@@ -107,6 +147,15 @@ impl CommandAndArguments {
             arg0,
         }
     }
+
+    /// The length in bytes of the resolved command and its arguments, separated by single spaces.
+    pub(crate) fn command_line_len(&self) -> usize {
+        self.arguments
+            .iter()
+            .map(|arg| 1 + arg.len())
+            .sum::<usize>()
+            + self.command.as_os_str().len()
+    }
 }
 
 #[cfg(test)]
@@ -187,6 +236,77 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_build_command_normalizes_path() {
+        let build = |command: &str| {
+            CommandAndArguments::build_from_args(None, vec![command.into()], "/usr/bin")
+        };
+
+        for equivalent in ["//usr/bin///fmt", "/usr/./bin/fmt", "/usr/lib/../bin/fmt"] {
+            let cmd = build(equivalent);
+            assert_eq!(cmd.command, std::path::Path::new("/usr/bin/fmt"));
+            assert!(cmd.resolved);
+            assert_eq!(cmd.arg0, Some(equivalent.into()));
+        }
+
+        for not_a_file in ["/usr/bin/fmt/", "/usr/bin/fmt/.", "/usr/bin/fmt/.."] {
+            assert!(!build(not_a_file).resolved);
+        }
+    }
+
+    #[test]
+    fn test_build_command_requires_executable_file() {
+        for not_executable in ["/etc/passwd", "/tmp", "/usr/bin"] {
+            let cmd = CommandAndArguments::build_from_args(None, vec![not_executable.into()], "");
+            assert!(!cmd.resolved);
+        }
+    }
+
+    #[test]
+    fn test_build_command_in_root() {
+        use std::os::unix::fs::{PermissionsExt, symlink};
+        use std::path::Path;
+
+        let root = std::env::temp_dir().join(format!("sudo-chroot-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("usr/bin")).unwrap();
+        let tool = root.join("usr/bin/tool");
+        std::fs::write(&tool, "").unwrap();
+        std::fs::set_permissions(&tool, std::fs::Permissions::from_mode(0o755)).unwrap();
+        // an absolute symlink is interpreted inside the root
+        symlink("/usr/bin", root.join("bin")).unwrap();
+        symlink("/etc", root.join("usr/bin/escape")).unwrap();
+
+        let build = |command: &str, path: &str| {
+            CommandAndArguments::build_from_args_in_root(&root, None, vec![command.into()], path)
+        };
+
+        let cmd = build("tool", "/bin");
+        assert!(cmd.resolved);
+        assert_eq!(cmd.command, Path::new("/usr/bin/tool"));
+
+        let cmd = build("/bin/../bin/tool", "");
+        assert!(cmd.resolved);
+        assert_eq!(cmd.command, Path::new("/usr/bin/tool"));
+
+        // this only exists outside of the root
+        assert!(!build("/usr/bin/env", "").resolved);
+        assert!(!build("env", "/usr/bin").resolved);
+        assert!(!build("/usr/bin/escape/passwd", "").resolved);
+        assert!(!build("/usr/bin/tool/", "").resolved);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_command_line_len() {
+        let cmd = CommandAndArguments {
+            command: "/usr/bin/fmt".into(),
+            arguments: vec!["hello".into(), "".into()],
+            ..Default::default()
+        };
+        assert_eq!(cmd.command_line_len(), "/usr/bin/fmt hello ".len());
+    }
+
     #[test]
     fn qualified_paths() {
         use super::is_qualified;
@@ -1,17 +1,19 @@
 use std::env;
 use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use crate::common::{Error, HARDENED_ENUM_VALUE_0, HARDENED_ENUM_VALUE_1, HARDENED_ENUM_VALUE_2};
-use crate::exec::RunOptions;
+use crate::exec::{HomeFallback, RunOptions};
 use crate::sudo::{SudoEditOptions, SudoListOptions, SudoRunOptions, SudoValidateOptions};
 use crate::sudoers::Sudoers;
 use crate::sudoers::{DirChange, Restrictions};
-use crate::system::{Group, Hostname, User, audit::sudo_call};
+use crate::system::{Group, Hostname, User, audit::sudo_call, inherited_fds};
 
 use super::{
-    SudoPath,
+    SudoPath, SudoString,
     command::CommandAndArguments,
-    resolve::{CurrentUser, resolve_shell, resolve_target_user_and_group},
+    resolve::{CurrentUser, canonicalize_newfile, resolve_shell, resolve_target_user_and_group},
 };
 
 #[derive(Debug)]
@@ -19,6 +21,9 @@ pub struct Context {
     // cli options
     pub launch: LaunchType,
     pub chdir: Option<SudoPath>,
+    pub chroot: Option<SudoPath>,
+    pub close_from: Option<i32>,
+    pub command_timeout: Option<Duration>,
     pub command: CommandAndArguments,
     pub target_user: User,
     pub target_group: Group,
@@ -29,6 +34,9 @@ pub struct Context {
     pub prompt: Option<String>,
     pub non_interactive: bool,
     pub use_session_records: bool,
+    pub preserve_environment: bool,
+    pub set_home: bool,
+    pub preserve_env: Vec<String>,
     // system
     pub hostname: Hostname,
     pub current_user: CurrentUser,
@@ -53,8 +61,15 @@ impl Context {
         let hostname = Hostname::resolve();
         let current_user = CurrentUser::resolve()?;
 
+        let requested_user = requested_user(
+            &sudo_options.user,
+            &sudo_options.group,
+            policy,
+            &hostname,
+            &current_user,
+        )?;
         let (target_user, target_group) =
-            resolve_target_user_and_group(&sudo_options.user, &sudo_options.group, &current_user)?;
+            resolve_target_user_and_group(&requested_user, &sudo_options.group, &current_user)?;
 
         let launch = if sudo_options.login {
             LaunchType::Login
@@ -66,6 +81,28 @@ impl Context {
 
         let shell = resolve_shell(launch, &current_user, &target_user);
 
+        // an empty program name can never be executed; reject it before it gets resolved
+        if shell.is_none()
+            && sudo_options
+                .positional_args
+                .first()
+                .is_some_and(|command| command.is_empty())
+        {
+            return Err(Error::Options(
+                xlat!("the command to run must not be empty").into(),
+            ));
+        }
+
+        if shell.is_none()
+            && policy.require_absolute_command(&hostname, &current_user, &target_user)
+        {
+            if let Some(command) = sudo_options.positional_args.first() {
+                if !std::path::Path::new(command).is_absolute() {
+                    return Err(Error::CommandNotAbsolute(command.into()));
+                }
+            }
+        }
+
         let override_path = policy.search_path(&hostname, &current_user, &target_user);
 
         let command = {
@@ -78,8 +115,12 @@ impl Context {
                 system_path.as_ref()
             };
 
-            sudo_call(&target_user, &target_group, || {
-                CommandAndArguments::build_from_args(shell, sudo_options.positional_args, path)
+            let arguments = sudo_options.positional_args;
+            sudo_call(&target_user, &target_group, || match &sudo_options.chroot {
+                Some(root) => {
+                    CommandAndArguments::build_from_args_in_root(root, shell, arguments, path)
+                }
+                None => CommandAndArguments::build_from_args(shell, arguments, path),
             })?
         };
 
@@ -94,18 +135,23 @@ impl Context {
             use_session_records: !sudo_options.reset_timestamp,
             launch,
             chdir: sudo_options.chdir,
+            chroot: sudo_options.chroot,
+            close_from: sudo_options.close_from,
+            command_timeout: sudo_options.command_timeout.map(Duration::from_secs),
             askpass: sudo_options.askpass,
             stdin: sudo_options.stdin,
             bell: sudo_options.bell,
             background: sudo_options.background,
             prompt,
             non_interactive: sudo_options.non_interactive,
+            preserve_environment: sudo_options.preserve_environment,
+            set_home: sudo_options.set_home,
+            preserve_env: sudo_options.preserve_env,
             files_to_edit: vec![],
         })
     }
 
     pub fn from_edit_opts(sudo_options: SudoEditOptions) -> Result<Context, Error> {
-        use std::path::Path;
         let hostname = Hostname::resolve();
         let current_user = CurrentUser::resolve()?;
 
@@ -113,42 +159,25 @@ impl Context {
             resolve_target_user_and_group(&sudo_options.user, &sudo_options.group, &current_user)?;
 
         // resolve file arguments; if something can't be resolved, don't add it to the "edit" list
-        let resolved_args = sudo_call(&target_user, &target_group, || {
-            sudo_options.positional_args.iter().map(|arg| {
-                let path = Path::new(arg);
-                let absolute_path;
-                crate::common::resolve::canonicalize_newfile(if path.is_absolute() {
-                    path
-                } else {
-                    absolute_path = Path::new(".").join(path);
-                    &absolute_path
-                })
-                .map_err(|_| arg)
-                .and_then(|path| path.into_os_string().into_string().map_err(|_| arg))
-            })
-        })?;
+        let resolved_args =
+            resolve_edit_files(&sudo_options.positional_args, &target_user, &target_group)?;
 
         let files_to_edit = resolved_args
-            .clone()
-            .map(|path| path.ok().map(SudoPath::from_cli_string))
+            .iter()
+            .map(|path| path.clone().ok().map(SudoPath::from_cli_string))
             .collect();
 
         // if a path resolved to something that isn't in UTF-8, it means it isn't in the sudoers file
         // as well and so we treat it "as is" wrt. the policy lookup and fail if the user is allowed
         // by the policy to edit that file. this is to prevent leaking information.
-        let arguments = resolved_args
-            .map(|arg| match arg {
-                Ok(arg) => OsString::from(arg),
-                Err(arg) => OsString::from(arg),
-            })
-            .collect();
+        let arguments = edit_arguments(resolved_args);
 
         // TODO: the more Rust way of doing things would be to create an alternative for sudoedit instead;
         // but a stringly typed interface feels the most decent thing to do (if we can pull it off)
         // since "sudoedit" really is like a builtin command to sudo. We may want to be a bit 'better' than
         // ogsudo in the future.
         let command = CommandAndArguments {
-            command: std::path::PathBuf::from("sudoedit"),
+            command: PathBuf::from("sudoedit"),
             arguments,
             ..Default::default()
         };
@@ -162,12 +191,18 @@ impl Context {
             use_session_records: !sudo_options.reset_timestamp,
             launch: Default::default(),
             chdir: sudo_options.chdir,
+            chroot: None,
+            close_from: None,
+            command_timeout: None,
             askpass: sudo_options.askpass,
             stdin: sudo_options.stdin,
             bell: sudo_options.bell,
             background: false,
             prompt: sudo_options.prompt,
             non_interactive: sudo_options.non_interactive,
+            preserve_environment: false,
+            set_home: false,
+            preserve_env: Vec::new(),
             files_to_edit,
         })
     }
@@ -186,12 +221,18 @@ impl Context {
             use_session_records: !sudo_options.reset_timestamp,
             launch: Default::default(),
             chdir: None,
+            chroot: None,
+            close_from: None,
+            command_timeout: None,
             askpass: sudo_options.askpass,
             stdin: sudo_options.stdin,
             bell: sudo_options.bell,
             background: false,
             prompt: sudo_options.prompt,
             non_interactive: sudo_options.non_interactive,
+            preserve_environment: false,
+            set_home: false,
+            preserve_env: Vec::new(),
             files_to_edit: vec![],
         })
     }
@@ -200,15 +241,41 @@ impl Context {
         sudo_options: SudoListOptions,
         policy: &mut Sudoers,
     ) -> Result<Context, Error> {
-        let hostname = Hostname::resolve();
+        let hostname = match sudo_options.host {
+            Some(host) => Hostname::from_cli_string(host),
+            None => Hostname::resolve(),
+        };
         let current_user = CurrentUser::resolve()?;
+        let requested_user = requested_user(
+            &sudo_options.user,
+            &sudo_options.group,
+            policy,
+            &hostname,
+            &current_user,
+        )?;
         let (target_user, target_group) =
-            resolve_target_user_and_group(&sudo_options.user, &sudo_options.group, &current_user)?;
+            resolve_target_user_and_group(&requested_user, &sudo_options.group, &current_user)?;
 
         let override_path = policy.search_path(&hostname, &current_user, &target_user);
 
         let command = if sudo_options.positional_args.is_empty() {
             Default::default()
+        } else if sudo_options.positional_args[0] == "sudoedit" {
+            // `sudo -l sudoedit file ...` asks about the sudoedit pseudo-command; its arguments
+            // are files, which are looked up like sudoedit itself would
+            let resolved_args = resolve_edit_files(
+                &sudo_options.positional_args[1..],
+                &target_user,
+                &target_group,
+            )?;
+            let arguments = edit_arguments(resolved_args);
+
+            CommandAndArguments {
+                command: "sudoedit".into(),
+                arguments,
+                resolved: true,
+                arg0: None,
+            }
         } else {
             let system_path;
 
@@ -233,12 +300,18 @@ impl Context {
             use_session_records: !sudo_options.reset_timestamp,
             launch: Default::default(),
             chdir: None,
+            chroot: None,
+            close_from: None,
+            command_timeout: None,
             askpass: sudo_options.askpass,
             stdin: sudo_options.stdin,
             bell: sudo_options.bell,
             background: false,
             prompt: sudo_options.prompt,
             non_interactive: sudo_options.non_interactive,
+            preserve_environment: false,
+            set_home: false,
+            preserve_env: Vec::new(),
             files_to_edit: vec![],
         })
     }
@@ -255,6 +328,7 @@ impl Context {
                     return Err(Error::ChDirNotAllowed {
                         chdir: chdir.clone(),
                         command: self.command.command.clone(),
+                        allowed: optdir.clone(),
                     });
                 } else {
                     optdir.as_ref()
@@ -262,6 +336,86 @@ impl Context {
             }
         };
 
+        // see if the chroot flag is permitted; since the command was looked up inside the
+        // directory given with --chroot, a root directory imposed by the policy must be given too
+        let chroot = match (&controls.chroot, &self.chroot) {
+            (DirChange::Any, chroot) => chroot.as_ref(),
+            (DirChange::Strict(Some(allowed)), Some(chroot)) if **allowed == **chroot => {
+                Some(chroot)
+            }
+            (DirChange::Strict(None), None) => None,
+            (DirChange::Strict(allowed), chroot) => {
+                return Err(Error::ChRootNotAllowed {
+                    chroot: chroot.clone(),
+                    command: self.command.command.clone(),
+                    allowed: allowed.clone(),
+                });
+            }
+        };
+
+        // only tell whether the root directory exists once the user may use it
+        if let Some(root) = chroot {
+            if !root.is_dir() {
+                return Err(Error::ChRootNotFound(root.to_path_buf()));
+            }
+        }
+
+        // see if the close-from flag is permitted
+        if self.close_from.is_some() && !controls.closefrom_override {
+            return Err(Error::CloseFromNotAllowed);
+        }
+
+        // see if the user may choose how long the command can run
+        let command_timeout = match (self.command_timeout, controls.command_timeout) {
+            (None, policy) => policy,
+            (Some(_), _) if !controls.user_command_timeouts => {
+                return Err(Error::CommandTimeoutNotAllowed);
+            }
+            (Some(timeout), Some(max)) if timeout > max => {
+                return Err(Error::CommandTimeoutTooLarge(max));
+            }
+            (Some(timeout), _) => Some(timeout),
+        };
+
+        // see if the user may preserve their entire environment
+        if self.preserve_environment && !controls.trust_environment {
+            return Err(Error::PreserveEnvNotAllowed);
+        }
+
+        // see if the command line stays within the configured limit
+        if let Some(max) = controls.command_line_max {
+            if self.command.command_line_len() > max {
+                return Err(Error::CommandLineTooLong {
+                    command: self.command.command.clone(),
+                    max,
+                });
+            }
+        }
+
+        // a broken passwd entry of the target user is diagnosed here, instead of when the
+        // login shell is executed
+        if self.launch == LaunchType::Login {
+            if !self.command.resolved {
+                return Err(Error::InvalidLoginShell {
+                    user: self.target_user.name.clone(),
+                    shell: self.command.command.clone(),
+                });
+            }
+
+            // in a chroot, the home directory is not where it appears to be
+            let home = &self.target_user.home;
+            if chdir.is_none()
+                && chroot.is_none()
+                && matches!(controls.home_fallback, HomeFallback::Error)
+                && !home.is_dir()
+            {
+                return Err(Error::HomeNotFound {
+                    user: self.target_user.name.clone(),
+                    home: home.to_path_buf(),
+                });
+            }
+        }
+
         // expand tildes in the path with the users home directory
         let chdir = chdir
             .map(|dir| dir.expand_tilde_in_path(&self.target_user.name))
@@ -276,7 +430,9 @@ impl Context {
             arguments: &self.command.arguments,
             arg0: self.command.arg0.as_deref(),
             chdir: chdir.as_deref().map(ToOwned::to_owned),
+            chroot: chroot.map(|root| root.to_path_buf()),
             is_login: self.launch == LaunchType::Login,
+            home_fallback: controls.home_fallback,
             user: &self.target_user,
             group: &self.target_group,
             umask: controls.umask,
@@ -284,15 +440,102 @@ impl Context {
             background: self.background,
             use_pty: controls.use_pty,
             noexec: controls.noexec,
+            close_from: self.close_from,
+            command_timeout,
+            preserve_fds: inherited_fds(&controls.preserve_fds),
         })
     }
 }
 
+/// Resolve the files that sudoedit was asked to edit, with the privileges of the target user. A
+/// file that can't be resolved, or that resolves to a path which isn't in UTF-8, is returned as
+/// an error holding the argument as it was given.
+fn resolve_edit_files<'a>(
+    files: &'a [OsString],
+    target_user: &User,
+    target_group: &Group,
+) -> Result<Vec<Result<String, &'a OsString>>, Error> {
+    let resolved = sudo_call(target_user, target_group, || {
+        files
+            .iter()
+            .map(|arg| {
+                let path = Path::new(arg);
+                let absolute_path;
+                canonicalize_newfile(if path.is_absolute() {
+                    path
+                } else {
+                    absolute_path = Path::new(".").join(path);
+                    &absolute_path
+                })
+                .map_err(|_| arg)
+                .and_then(|path| path.into_os_string().into_string().map_err(|_| arg))
+            })
+            .collect()
+    })?;
+
+    Ok(resolved)
+}
+
+/// The arguments of the sudoedit pseudo-command that the policy is checked against: the resolved
+/// paths, and the arguments as given for files that could not be resolved.
+fn edit_arguments(resolved_args: Vec<Result<String, &OsString>>) -> Vec<OsString> {
+    resolved_args
+        .into_iter()
+        .map(|arg| match arg {
+            Ok(arg) => OsString::from(arg),
+            Err(arg) => arg.clone(),
+        })
+        .collect()
+}
+
+/// The target user given with -u; or if neither -u nor -g was used, the runas_default user of
+/// the policy.
+fn requested_user(
+    user: &Option<SudoString>,
+    group: &Option<SudoString>,
+    policy: &mut Sudoers,
+    hostname: &Hostname,
+    current_user: &CurrentUser,
+) -> Result<Option<SudoString>, Error> {
+    if user.is_some() || group.is_some() {
+        return Ok(user.clone());
+    }
+
+    SudoString::new(policy.runas_default(hostname, &**current_user)).map(Some)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{common::resolve::CurrentUser, sudo::SudoAction, system::Hostname};
 
-    use super::Context;
+    use super::{Context, DirChange, Error, Restrictions};
+
+    fn restrictions(settings: &crate::defaults::Settings) -> Restrictions<'_> {
+        Restrictions {
+            env_keep: settings.env_keep(),
+            env_check: settings.env_check(),
+            env_delete: settings.env_delete(),
+            env_file: None,
+            path: None,
+            use_pty: true,
+            chdir: DirChange::Strict(None),
+            chroot: DirChange::Strict(None),
+            trust_environment: false,
+            umask: crate::exec::Umask::Preserve,
+            closefrom_override: false,
+            preserve_fds: Vec::new(),
+            home_fallback: crate::exec::HomeFallback::Stay,
+            always_set_home: false,
+            command_line_max: None,
+            command_timeout: None,
+            user_command_timeouts: false,
+            #[cfg(feature = "apparmor")]
+            apparmor_profile: None,
+            noexec: false,
+            log: crate::sudoers::Logging::Auth,
+            rule: None,
+        }
+    }
 
     #[test]
     fn test_build_run_context() {
@@ -317,4 +560,206 @@ mod tests {
         assert_eq!(context.hostname, Hostname::resolve());
         assert_eq!(context.target_user.uid, current_user.uid);
     }
+
+    #[test]
+    fn test_empty_command_is_rejected() {
+        let mut options = SudoAction::try_parse_from(["sudo", "", "hello"])
+            .unwrap()
+            .try_into_run()
+            .ok()
+            .unwrap();
+        options.user = Some(CurrentUser::resolve().unwrap().name.clone());
+
+        assert!(matches!(
+            Context::from_run_opts(options, &mut Default::default()),
+            Err(Error::Options(_))
+        ));
+    }
+
+    #[test]
+    fn test_command_line_max() {
+        let mut options = SudoAction::try_parse_from(["sudo", "/usr/bin/env", "hello"])
+            .unwrap()
+            .try_into_run()
+            .ok()
+            .unwrap();
+        options.user = Some(CurrentUser::resolve().unwrap().name.clone());
+
+        let context = Context::from_run_opts(options, &mut Default::default()).unwrap();
+        let len = context.command.command_line_len();
+
+        let settings = crate::defaults::Settings::default();
+        let controls = |command_line_max| Restrictions {
+            command_line_max,
+            ..restrictions(&settings)
+        };
+
+        assert!(context.try_as_run_options(&controls(None)).is_ok());
+        assert!(context.try_as_run_options(&controls(Some(len))).is_ok());
+        assert!(matches!(
+            context.try_as_run_options(&controls(Some(len - 1))),
+            Err(Error::CommandLineTooLong { max, .. }) if max == len - 1
+        ));
+    }
+
+    #[test]
+    fn test_chdir_permission() {
+        let mut options = SudoAction::try_parse_from(["sudo", "-D", "/tmp", "/usr/bin/env"])
+            .unwrap()
+            .try_into_run()
+            .ok()
+            .unwrap();
+        options.user = Some(CurrentUser::resolve().unwrap().name.clone());
+
+        let context = Context::from_run_opts(options, &mut Default::default()).unwrap();
+
+        let settings = crate::defaults::Settings::default();
+        let controls = |chdir| Restrictions {
+            chdir,
+            ..restrictions(&settings)
+        };
+
+        let run_options = context.try_as_run_options(&controls(DirChange::Any));
+        assert_eq!(
+            run_options.unwrap().chdir.as_deref(),
+            Some(std::path::Path::new("/tmp"))
+        );
+
+        let err = context
+            .try_as_run_options(&controls(DirChange::Strict(None)))
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::ChDirNotAllowed { allowed: None, .. }));
+        assert!(err.to_string().contains("does not allow choosing"));
+
+        let err = context
+            .try_as_run_options(&controls(DirChange::Strict(Some("/var/www".into()))))
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            Error::ChDirNotAllowed {
+                allowed: Some(_),
+                ..
+            }
+        ));
+        assert!(err.to_string().contains("'--chdir /tmp'"));
+        assert!(err.to_string().contains("can only be run in '/var/www'"));
+    }
+
+    #[test]
+    fn test_command_timeout() {
+        use std::time::Duration;
+
+        let run_context = |args: &[&str]| {
+            let mut options = SudoAction::try_parse_from(args)
+                .unwrap()
+                .try_into_run()
+                .ok()
+                .unwrap();
+            options.user = Some(CurrentUser::resolve().unwrap().name.clone());
+            Context::from_run_opts(options, &mut Default::default()).unwrap()
+        };
+
+        let settings = crate::defaults::Settings::default();
+        let controls = |command_timeout, user_command_timeouts| Restrictions {
+            command_timeout,
+            user_command_timeouts,
+            ..restrictions(&settings)
+        };
+        let minute = Some(Duration::from_secs(60));
+
+        // without -T, the timeout of the policy is used
+        let context = run_context(&["sudo", "/usr/bin/env"]);
+        let run_options = context.try_as_run_options(&controls(minute, false));
+        assert_eq!(run_options.unwrap().command_timeout, minute);
+
+        let context = run_context(&["sudo", "-T", "30", "/usr/bin/env"]);
+        let err = context
+            .try_as_run_options(&controls(None, false))
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::CommandTimeoutNotAllowed));
+
+        let run_options = context.try_as_run_options(&controls(minute, true));
+        assert_eq!(
+            run_options.unwrap().command_timeout,
+            Some(Duration::from_secs(30))
+        );
+
+        // the user may shorten the timeout of the policy, but not extend it
+        let context = run_context(&["sudo", "-T", "90", "/usr/bin/env"]);
+        let err = context
+            .try_as_run_options(&controls(minute, true))
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::CommandTimeoutTooLarge(_)));
+        assert!(context.try_as_run_options(&controls(None, true)).is_ok());
+    }
+
+    #[test]
+    fn test_chroot_permission() {
+        let run_opts = |args: &[&str]| {
+            let mut options = SudoAction::try_parse_from(args)
+                .unwrap()
+                .try_into_run()
+                .ok()
+                .unwrap();
+            options.user = Some(CurrentUser::resolve().unwrap().name.clone());
+            Context::from_run_opts(options, &mut Default::default())
+        };
+
+        let settings = crate::defaults::Settings::default();
+        let controls = |chroot| Restrictions {
+            chroot,
+            ..restrictions(&settings)
+        };
+
+        let context = run_opts(&["sudo", "-R", "/", "/usr/bin/env"]).unwrap();
+        let run_options = context.try_as_run_options(&controls(DirChange::Any));
+        assert_eq!(
+            run_options.unwrap().chroot.as_deref(),
+            Some(std::path::Path::new("/"))
+        );
+
+        let run_options =
+            context.try_as_run_options(&controls(DirChange::Strict(Some("/".into()))));
+        assert!(run_options.is_ok());
+
+        let err = context
+            .try_as_run_options(&controls(DirChange::Strict(None)))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("'--chroot /'"));
+
+        let err = context
+            .try_as_run_options(&controls(DirChange::Strict(Some("/srv/jail".into()))))
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string()
+                .contains("only be run with '--chroot /srv/jail'")
+        );
+
+        // a root directory required by the policy must also be requested
+        let context = run_opts(&["sudo", "/usr/bin/env"]).unwrap();
+        let err = context
+            .try_as_run_options(&controls(DirChange::Strict(Some("/srv/jail".into()))))
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::ChRootNotAllowed { chroot: None, .. }));
+
+        // a missing root directory is only reported to a user that may choose it
+        let context = run_opts(&["sudo", "-R", "/nonexistent", "/usr/bin/env"]).unwrap();
+        let err = context
+            .try_as_run_options(&controls(DirChange::Strict(None)))
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::ChRootNotAllowed { .. }));
+        let err = context
+            .try_as_run_options(&controls(DirChange::Any))
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::ChRootNotFound(_)));
+    }
 }
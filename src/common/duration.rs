@@ -0,0 +1,134 @@
+use std::time::Duration;
+
+/// The unit of a duration that is written as a plain number, without a unit suffix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DurationUnit {
+    /// Used by command_timeout and the -T option
+    Seconds,
+    /// Used by passwd_timeout and timestamp_timeout
+    Minutes,
+}
+
+impl DurationUnit {
+    fn seconds(self) -> u128 {
+        match self {
+            DurationUnit::Seconds => 1,
+            DurationUnit::Minutes => 60,
+        }
+    }
+}
+
+/// Parse a duration, which is either a plain number in the `default_unit` (e.g. `90` or `2.5`),
+/// or a sequence of numbers that are each followed by one of the units `d`, `h`, `m` or `s`
+/// (e.g. `1h30m` or `1.5d`). Numbers may have a fractional component; the result is rounded
+/// down to whole seconds.
+pub fn parse_duration_spec(input: &str, default_unit: DurationUnit) -> Option<Duration> {
+    let seconds = if is_number(input) {
+        scaled_number(input, default_unit.seconds())?
+    } else if input.is_empty() {
+        return None;
+    } else {
+        let mut total = 0u128;
+        let mut rest = input;
+        while !rest.is_empty() {
+            let end = rest.find(|c: char| !c.is_ascii_digit() && c != '.')?;
+            let (number, suffix) = rest.split_at(end);
+            let unit = match suffix.as_bytes()[0].to_ascii_lowercase() {
+                b'd' => 24 * 60 * 60,
+                b'h' => 60 * 60,
+                b'm' => 60,
+                b's' => 1,
+                _ => return None,
+            };
+            if !is_number(number) {
+                return None;
+            }
+            total = total.checked_add(scaled_number(number, unit)?)?;
+            rest = &suffix[1..];
+        }
+
+        total
+    };
+
+    Some(Duration::from_secs(seconds.try_into().ok()?))
+}
+
+/// A non-empty sequence of digits, optionally followed by a `.` and a non-empty fraction.
+fn is_number(input: &str) -> bool {
+    let digits = |s: &str| !s.is_empty() && s.bytes().all(|c| c.is_ascii_digit());
+    match input.split_once('.') {
+        Some((integral, fractional)) => digits(integral) && digits(fractional),
+        None => digits(input),
+    }
+}
+
+/// The number of seconds in `input` units of `unit` seconds, rounded down.
+fn scaled_number(input: &str, unit: u128) -> Option<u128> {
+    let (integral, fractional) = input.split_once('.').unwrap_or((input, ""));
+    // digits beyond this precision can not affect the number of seconds
+    let fractional = &fractional[..fractional.len().min(18)];
+
+    let integral = integral.parse::<u128>().ok()?.checked_mul(unit)?;
+    let shift = 10u128.pow(fractional.len() as u32);
+    let fractional = fractional.parse::<u128>().unwrap_or(0) * unit / shift;
+
+    integral.checked_add(fractional)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DurationUnit, parse_duration_spec};
+
+    #[test]
+    fn durations_are_parsed() {
+        use DurationUnit::*;
+
+        // input, seconds with unit `Seconds`, seconds with unit `Minutes`
+        let cases = [
+            ("0", Some(0), Some(0)),
+            ("5", Some(5), Some(5 * 60)),
+            ("2.5", Some(2), Some(150)),
+            ("0.5", Some(0), Some(30)),
+            ("1.25", Some(1), Some(75)),
+            ("0.001", Some(0), Some(0)),
+            ("15.00", Some(15), Some(15 * 60)),
+            // with a suffix, the default unit does not matter
+            ("30s", Some(30), Some(30)),
+            ("5m", Some(300), Some(300)),
+            ("1h", Some(3600), Some(3600)),
+            ("1d", Some(86400), Some(86400)),
+            ("1h30m", Some(5400), Some(5400)),
+            ("1d2h3m4s", Some(93784), Some(93784)),
+            ("1.5h", Some(5400), Some(5400)),
+            ("0.5m", Some(30), Some(30)),
+            ("90S", Some(90), Some(90)),
+            ("2H", Some(7200), Some(7200)),
+            ("0s", Some(0), Some(0)),
+            // units may be repeated and are simply added up
+            ("1m1m", Some(120), Some(120)),
+            ("", None, None),
+            ("1.", None, None),
+            (".5", None, None),
+            ("1.2.3", None, None),
+            ("-5", None, None),
+            ("+5", None, None),
+            ("1e3", None, None),
+            (" 5", None, None),
+            ("5 m", None, None),
+            ("m", None, None),
+            ("5x", None, None),
+            ("1h30", None, None),
+            ("h1", None, None),
+            ("1.h", None, None),
+            ("18446744073709551615", Some(u64::MAX), None),
+            ("307445734561825861", Some(307445734561825861), None),
+            ("99999999999999999999999999d", None, None),
+        ];
+
+        for (input, seconds, minutes) in cases {
+            let parse = |unit| parse_duration_spec(input, unit).map(|d| d.as_secs());
+            assert_eq!(parse(Seconds), seconds, "{input} (seconds)");
+            assert_eq!(parse(Minutes), minutes, "{input} (minutes)");
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use crate::{pam::PamError, system::Hostname};
-use std::{borrow::Cow, fmt, path::PathBuf};
+use std::{borrow::Cow, fmt, path::PathBuf, time::Duration};
 
 use super::{SudoPath, SudoString};
 
@@ -14,12 +14,37 @@ pub enum Error {
     },
     SelfCheckSetuid,
     SelfCheckNoNewPrivs,
+    SelfCheckUserNamespace,
     CommandNotFound(PathBuf),
     InvalidCommand(PathBuf),
+    CommandNotAbsolute(PathBuf),
     ChDirNotAllowed {
         chdir: SudoPath,
         command: PathBuf,
+        allowed: Option<SudoPath>,
     },
+    ChRootNotAllowed {
+        chroot: Option<SudoPath>,
+        command: PathBuf,
+        allowed: Option<SudoPath>,
+    },
+    ChRootNotFound(PathBuf),
+    CommandLineTooLong {
+        command: PathBuf,
+        max: usize,
+    },
+    InvalidLoginShell {
+        user: SudoString,
+        shell: PathBuf,
+    },
+    HomeNotFound {
+        user: SudoString,
+        home: PathBuf,
+    },
+    CloseFromNotAllowed,
+    CommandTimeoutNotAllowed,
+    CommandTimeoutTooLarge(Duration),
+    PreserveEnvNotAllowed,
     UserNotFound(String),
     GroupNotFound(String),
     Authorization(String),
@@ -76,12 +101,43 @@ impl fmt::Display for Error {
                     configuration to disable the flag."
                 )
             }
+            Error::SelfCheckUserNamespace => {
+                xlat_write!(
+                    f,
+                    "sudo is running in a user namespace in which uid 0 is not mapped, \
+                    so the setuid bit cannot grant root privileges.\n\
+                    If sudo is running in a container, run it from a namespace that maps the root user."
+                )
+            }
             Error::CommandNotFound(p) => {
                 xlat_write!(f, "'{path}': command not found", path = p.display())
             }
             Error::InvalidCommand(p) => {
                 xlat_write!(f, "'{path}': invalid command", path = p.display())
             }
+            Error::CommandNotAbsolute(p) => {
+                xlat_write!(
+                    f,
+                    "'{path}': the command must be given as an absolute path",
+                    path = p.display()
+                )
+            }
+            Error::InvalidLoginShell { user, shell } => {
+                xlat_write!(
+                    f,
+                    "'{shell}': login shell of user '{user}' is not an executable file",
+                    shell = shell.display(),
+                    user = user
+                )
+            }
+            Error::HomeNotFound { user, home } => {
+                xlat_write!(
+                    f,
+                    "unable to change directory to {home}: home directory of user '{user}' does not exist",
+                    home = home.display(),
+                    user = user
+                )
+            }
             Error::UserNotFound(u) => xlat_write!(f, "user '{user}' not found", user = u),
             Error::GroupNotFound(g) => xlat_write!(f, "group '{group}' not found", group = g),
             Error::Authorization(u) => {
@@ -123,11 +179,87 @@ impl fmt::Display for Error {
                     num = num
                 )
             }
-            Error::ChDirNotAllowed { chdir, command } => xlat_write!(
+            Error::ChDirNotAllowed {
+                chdir,
+                command,
+                allowed,
+            } => {
+                xlat_write!(
+                    f,
+                    "you are not allowed to use '--chdir {path}' with '{command}'",
+                    path = chdir.display(),
+                    command = command.display()
+                )?;
+                match allowed {
+                    Some(dir) => {
+                        xlat_write!(f, "; it can only be run in '{dir}'", dir = dir.display())
+                    }
+                    None => xlat_write!(
+                        f,
+                        "; the sudoers policy does not allow choosing its working directory"
+                    ),
+                }
+            }
+            Error::ChRootNotAllowed {
+                chroot: Some(chroot),
+                command,
+                allowed,
+            } => {
+                xlat_write!(
+                    f,
+                    "you are not allowed to use '--chroot {path}' with '{command}'",
+                    path = chroot.display(),
+                    command = command.display()
+                )?;
+                if let Some(dir) = allowed {
+                    xlat_write!(
+                        f,
+                        "; it can only be run with '--chroot {dir}'",
+                        dir = dir.display()
+                    )?;
+                }
+                Ok(())
+            }
+            Error::ChRootNotAllowed {
+                chroot: None,
+                command,
+                allowed,
+            } => {
+                xlat_write!(
+                    f,
+                    "'{command}' must be run with '--chroot {dir}'",
+                    command = command.display(),
+                    dir = allowed.as_deref().unwrap_or("/".as_ref()).display()
+                )
+            }
+            Error::ChRootNotFound(path) => {
+                xlat_write!(
+                    f,
+                    "unable to change root directory to {path}: no such directory",
+                    path = path.display()
+                )
+            }
+            Error::CloseFromNotAllowed => {
+                xlat_write!(f, "you are not allowed to use the '--close-from' option")
+            }
+            Error::CommandTimeoutNotAllowed => {
+                xlat_write!(f, "you are not allowed to set a command timeout")
+            }
+            Error::CommandTimeoutTooLarge(max) => {
+                xlat_write!(
+                    f,
+                    "the command timeout may not exceed {max} seconds",
+                    max = max.as_secs()
+                )
+            }
+            Error::PreserveEnvNotAllowed => {
+                xlat_write!(f, "sorry, you are not allowed to preserve the environment")
+            }
+            Error::CommandLineTooLong { command, max } => xlat_write!(
                 f,
-                "you are not allowed to use '--chdir {path}' with '{command}'",
-                path = chdir.display(),
-                command = command.display()
+                "the command line for '{command}' is longer than the maximum of {max} bytes",
+                command = command.display(),
+                max = max
             ),
             Error::StringValidation(string) => {
                 write!(
@@ -158,7 +290,11 @@ impl fmt::Display for Error {
 
 impl From<PamError> for Error {
     fn from(err: PamError) -> Self {
-        Error::Pam(err)
+        match err {
+            // a PAM module tried to prompt while running with --non-interactive
+            PamError::InteractionRequired => Error::InteractionRequired,
+            err => Error::Pam(err),
+        }
     }
 }
 
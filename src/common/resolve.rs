@@ -3,10 +3,10 @@ use crate::system::{Group, User};
 use core::fmt;
 use std::{
     env,
-    ffi::CStr,
+    ffi::{CStr, OsString},
     fs, io, ops,
-    os::unix::prelude::MetadataExt,
-    path::{Path, PathBuf},
+    os::unix::prelude::{MetadataExt, OsStrExt},
+    path::{Component, Path, PathBuf},
     str::FromStr,
 };
 
@@ -306,10 +306,11 @@ mod tests {
 /// tools like busybox or pgrep (which is a symlink to pgrep on systems)
 /// This function will check for existence.
 pub fn canonicalize<P: AsRef<Path>>(path: P) -> io::Result<PathBuf> {
-    let reconstructed_path = canonicalize_newfile(path)?;
+    let reconstructed_path = canonicalize_newfile(&path)?;
 
-    // access the object to generate the regular error if it does not exist
-    let _ = fs::metadata(&reconstructed_path)?;
+    // access the object to generate the regular error if it does not exist; this uses the
+    // path as given, since `Path` ignores a trailing slash (or `/.`) that the kernel does not
+    let _ = fs::metadata(path)?;
 
     Ok(reconstructed_path)
 }
@@ -324,15 +325,98 @@ pub fn canonicalize_newfile<P: AsRef<Path>>(path: P) -> io::Result<PathBuf> {
         return Ok(path.to_path_buf());
     };
 
-    let canon_path = fs::canonicalize(parent)?;
+    let Some(file_name) = path.file_name() else {
+        // path ends in "..", so there is no final component to keep as is
+        return fs::canonicalize(path);
+    };
 
-    let reconstructed_path = if let Some(file_name) = path.file_name() {
-        canon_path.join(file_name)
-    } else {
-        canon_path
+    Ok(fs::canonicalize(parent)?.join(file_name))
+}
+
+/// The location of `path` in the directory `root`, i.e. where it can be found before
+/// a `chroot` to `root`.
+pub(crate) fn in_root(root: &Path, path: &Path) -> PathBuf {
+    root.join(path.strip_prefix("/").unwrap_or(path))
+}
+
+/// Resolve all symlinks in an absolute `path` as if `root` was the root directory, so that
+/// the result is also valid after a `chroot` to `root`; like the kernel does after a `chroot`,
+/// neither ".." nor a symlink can lead outside of `root`.
+fn resolve_in(root: &Path, path: &Path) -> io::Result<PathBuf> {
+    // the same limit as Linux uses
+    const MAX_SYMLINKS: usize = 40;
+
+    fn push_components(pending: &mut Vec<OsString>, path: &Path) {
+        for component in path.components().rev() {
+            match component {
+                Component::Normal(name) => pending.push(name.to_owned()),
+                Component::ParentDir => pending.push("..".into()),
+                Component::RootDir | Component::CurDir | Component::Prefix(_) => {}
+            }
+        }
+    }
+
+    let mut pending = Vec::new();
+    push_components(&mut pending, path);
+
+    let mut resolved = PathBuf::from("/");
+    let mut symlinks = 0;
+    while let Some(name) = pending.pop() {
+        if name == ".." {
+            resolved.pop();
+            continue;
+        }
+
+        let candidate = resolved.join(&name);
+        let location = in_root(root, &candidate);
+        if fs::symlink_metadata(&location)?.is_symlink() {
+            symlinks += 1;
+            if symlinks > MAX_SYMLINKS {
+                return Err(io::Error::from_raw_os_error(libc::ELOOP));
+            }
+
+            let target = fs::read_link(&location)?;
+            if target.is_absolute() {
+                resolved = PathBuf::from("/");
+            }
+            push_components(&mut pending, &target);
+        } else {
+            resolved = candidate;
+        }
+    }
+
+    Ok(resolved)
+}
+
+/// Like [canonicalize], but for an absolute path that is interpreted inside the directory
+/// `root`, as it will be after a `chroot` to that directory.
+pub(crate) fn canonicalize_in(root: &Path, path: &Path) -> io::Result<PathBuf> {
+    let bytes = path.as_os_str().as_bytes();
+    let (Some(parent), Some(file_name)) = (path.parent(), path.file_name()) else {
+        return Err(io::Error::from(io::ErrorKind::NotFound));
     };
+    if !path.is_absolute() || bytes.ends_with(b"/") || bytes.ends_with(b"/.") {
+        return Err(io::Error::from(io::ErrorKind::NotFound));
+    }
 
-    Ok(reconstructed_path)
+    // the file itself must exist, but is not resolved
+    resolve_in(root, path)?;
+
+    Ok(resolve_in(root, parent)?.join(file_name))
+}
+
+/// Like [is_valid_executable], for an absolute path that is interpreted inside `root`.
+pub(crate) fn is_valid_executable_in(root: &Path, path: &Path) -> bool {
+    resolve_in(root, path).is_ok_and(|path| is_valid_executable(&in_root(root, &path)))
+}
+
+/// Like [resolve_path], but the directories in PATH are looked up inside `root`.
+pub(crate) fn resolve_path_in(root: &Path, command: &Path, path: &str) -> Option<PathBuf> {
+    path.split(':')
+        .map(Path::new)
+        .filter(|path| path.is_absolute())
+        .map(|path| path.join(command))
+        .find(|arg| is_valid_executable_in(root, arg))
 }
 
 #[cfg(test)]
@@ -344,6 +428,11 @@ mod test {
     fn canonicalization() {
         assert_eq!(canonicalize("/").unwrap(), Path::new("/"));
         assert!(canonicalize("").is_err());
+        assert_eq!(canonicalize("//usr///bin").unwrap(), Path::new("/usr/bin"));
+        assert_eq!(canonicalize("/usr/./bin/..").unwrap(), Path::new("/usr"));
+        assert!(canonicalize("/usr/bin/env/").is_err());
+        assert!(canonicalize("/usr/bin/env/.").is_err());
+        assert!(canonicalize("/usr/bin/env/..").is_err());
         if cfg!(any(target_os = "linux")) {
             // this test REQUIRES /usr/bin/unxz to be a symlink for /usr/bin/xz or /usr/bin/busybox
             assert!(Path::new("/bin").is_symlink());
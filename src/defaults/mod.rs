@@ -1,5 +1,5 @@
 #![forbid(unsafe_code)]
-pub type SettingsModifier = Box<dyn FnOnce(&mut Settings)>;
+pub type SettingsModifier = Box<dyn Fn(&mut Settings)>;
 
 pub enum ListMode {
     Set,
@@ -21,6 +21,8 @@ use settings_dsl::{
     result_of, storage_of,
 };
 
+use crate::common::{DurationUnit, parse_duration_spec};
+
 pub const SYSTEM_EDITOR: &str = if cfg!(target_os = "linux") {
     "/usr/bin/editor:/usr/bin/nano:/usr/bin/vi"
 } else {
@@ -29,44 +31,65 @@ pub const SYSTEM_EDITOR: &str = if cfg!(target_os = "linux") {
 
 defaults! {
     always_query_group_plugin = false  #ignored
-    always_set_home           = false  #ignored
+    always_set_home           = false
     env_reset                 = true   #ignored
     fqdn                      = false  #ignored
     ignore_dot                = true   #ignored
-    lecture                   = never (!= never) [always, once, never] #ignored
+    lecture                   = never (!= never) [always, once, never]
     mailerpath                = None (!= None) #ignored
     mail_badpass              = true   #ignored
     match_group_by_gid        = false  #ignored
     use_pty                   = true
     visiblepw                 = false  #ignored
     pwfeedback                = true
+    passprompt_override       = false
     rootpw                    = false
     targetpw                  = false
     noexec                    = false
     noninteractive_auth       = false
+    authenticate              = true
+    sudoedit_authenticate     = false
+    require_absolute_command  = false
+    includedir_host_escape    = false
 
     log_allowed               = true
     log_denied                = true #ignored
+    log_year                  = false
+    log_host                  = false
 
-    insults                   = false  #ignored
+    insults                   = false
 
     setenv                    = false
+    set_sudo_rule             = false
     runcwd                    = None (!= None)
     apparmor_profile          = None (!= None)
     umask                     = 0o022 (!= 0o777) {octal_mode}
     umask_override            = false
+    closefrom_override        = false
+    user_command_timeouts     = false
+    inaccessible_home         = stay [stay, root, error]
 
     passwd_tries              = 3 [0..=1000]
+    includedir_file_limit     = 100 [1..=128]
+    command_line_max          = 0 (!= 0) [0..=4294967295]
+    command_timeout           = 0 (!= 0) {duration_seconds}
 
     secure_path               = None (!= None)
+    env_file                  = None (!= None)
+    lecture_file              = None (!= None)
+    lecture_status_dir        = None (!= None)
+    timestamp_dir             = None (!= None)
+    askpass                   = None (!= None)
+    logfile                   = None (!= None)
 
     verifypw                  = all (!= never) [all, always, any, never] #ignored
 
-    passwd_timeout            = (5*60) (!= 0) {fractional_minutes}
-    timestamp_timeout         = (15*60) (!= 0) {fractional_minutes}
-    timestamp_type            = tty [tty, ppid]
+    passwd_timeout            = (5*60) (!= 0) {duration_minutes}
+    timestamp_timeout         = (15*60) (!= 0) {timestamp_minutes}
+    timestamp_type            = tty [tty, ppid, global]
 
     editor                    = SYSTEM_EDITOR
+    runas_default             = "root"
     env_editor                = true
 
     env_keep                  = ["COLORS", "DISPLAY", "HOSTNAME", "KRB5CCNAME", "LS_COLORS", "PATH",
@@ -80,7 +103,14 @@ defaults! {
                                 "BASHOPTS", "SHELLOPTS", "JAVA_TOOL_OPTIONS", "PERLIO_DEBUG",
                                 "PERLLIB", "PERL5LIB", "PERL5OPT", "PERL5DB", "FPATH", "NULLCMD",
                                 "READNULLCMD", "ZDOTDIR", "TMPPREFIX", "PYTHONHOME", "PYTHONPATH",
-                                "PYTHONINSPECT", "PYTHONUSERBASE", "RUBYLIB", "RUBYOPT", "*=()*"] #ignored
+                                "PYTHONINSPECT", "PYTHONUSERBASE", "RUBYLIB", "RUBYOPT", "*=()*"]
+
+    preserve_fds              = []
+}
+
+/// Settings that name a directory; like in original sudo, their values must be absolute paths.
+pub(crate) fn is_directory(name: &str) -> bool {
+    matches!(name, "lecture_status_dir" | "timestamp_dir")
 }
 
 fn octal_mode(input: &str) -> Option<u64> {
@@ -89,21 +119,24 @@ fn octal_mode(input: &str) -> Option<u64> {
         .map(Into::into)
 }
 
-/// A custom parser to parse seconds as fractional "minutes", the format used by
-/// passwd_timeout and timestamp_timeout.
-fn fractional_minutes(input: &str) -> Option<u64> {
-    if let Some((integral, fractional)) = input.split_once('.') {
-        // - 'input' is maximally 18 characters, making fractional.len() at most 17;
-        //   1e17 < 2**63, so the definition of 'shift' will not overflow.
-        // - for the same reason, if both parses in the definition of 'seconds' succeed,
-        //   we will have constructed an integer < 1e17.
-        //-  1e17 * 60 = 6e18 < 9e18 < 2**63, so the final line also will not overflow
-        let shift = 10u64.pow(fractional.len().try_into().ok()?);
-        let seconds = integral.parse::<u64>().ok()? * shift + fractional.parse::<u64>().ok()?;
-
-        Some(seconds * 60 / shift)
+/// Durations in minutes, the default unit of passwd_timeout and timestamp_timeout; these are
+/// stored as seconds.
+fn duration_minutes(input: &str) -> Option<u64> {
+    parse_duration_spec(input, DurationUnit::Minutes).map(|duration| duration.as_secs())
+}
+
+/// Durations in seconds, the default unit of command_timeout.
+fn duration_seconds(input: &str) -> Option<u64> {
+    parse_duration_spec(input, DurationUnit::Seconds).map(|duration| duration.as_secs())
+}
+
+/// Like `duration_minutes`, but also accepts -1 for a timestamp that never expires, which is
+/// stored as `u64::MAX` seconds.
+fn timestamp_minutes(input: &str) -> Option<u64> {
+    if input == "-1" {
+        Some(u64::MAX)
     } else {
-        input.parse::<u64>().ok()?.checked_mul(60)
+        duration_minutes(input)
     }
 }
 
@@ -157,6 +190,17 @@ mod test {
         negate("lecture").unwrap()(&mut def);
         assert_eq! { def.lecture, enums::lecture::never };
 
+        assert_eq! { def.inaccessible_home, enums::inaccessible_home::stay };
+        let SettingKind::Text(f) = set("inaccessible_home").unwrap() else {
+            panic!()
+        };
+        f("root").unwrap()(&mut def);
+        assert_eq! { def.inaccessible_home, enums::inaccessible_home::root };
+        f("error").unwrap()(&mut def);
+        assert_eq! { def.inaccessible_home, enums::inaccessible_home::error };
+        assert!(f("/").is_none());
+        assert!(negate("inaccessible_home").is_none());
+
         let SettingKind::Flag(f) = set("env_reset").unwrap() else {
             panic!()
         };
@@ -193,5 +237,78 @@ mod test {
 
         assert!(set("notanoption").is_none());
         assert!(f("notanoption").is_none());
+        assert!(f("-2").is_none());
+        assert!(f("-0.5").is_none());
+
+        f("-1").unwrap()(&mut def);
+        assert_eq! { def.timestamp_timeout, u64::MAX };
+        f("2.5").unwrap()(&mut def);
+        assert_eq! { def.timestamp_timeout, 150 };
+    }
+
+    #[test]
+    fn durations_have_a_default_unit_per_setting() {
+        let parse = |name: &str, value: &str| {
+            let SettingKind::Integer(f) = set(name).unwrap() else {
+                panic!()
+            };
+            let mut def = Settings::default();
+            f(value).map(|modifier| {
+                modifier(&mut def);
+                (
+                    def.passwd_timeout,
+                    def.timestamp_timeout,
+                    def.command_timeout,
+                )
+            })
+        };
+
+        // a plain number is in minutes for passwd_timeout and timestamp_timeout...
+        assert_eq!(parse("passwd_timeout", "2"), Some((120, 15 * 60, 0)));
+        assert_eq!(parse("timestamp_timeout", "2.5"), Some((5 * 60, 150, 0)));
+        // ...but in seconds for command_timeout
+        assert_eq!(parse("command_timeout", "2"), Some((5 * 60, 15 * 60, 2)));
+
+        // unit suffixes are accepted by all of them
+        assert_eq!(parse("passwd_timeout", "30s"), Some((30, 15 * 60, 0)));
+        assert_eq!(parse("timestamp_timeout", "1h"), Some((5 * 60, 3600, 0)));
+        assert_eq!(
+            parse("command_timeout", "1h30m"),
+            Some((5 * 60, 15 * 60, 5400))
+        );
+
+        for name in ["passwd_timeout", "timestamp_timeout", "command_timeout"] {
+            assert_eq!(parse(name, "1x"), None, "{name}");
+            assert_eq!(parse(name, "-2"), None, "{name}");
+        }
+
+        // only the timestamp timeout can be disabled with exactly -1
+        assert_eq!(parse("passwd_timeout", "-1"), None);
+        assert_eq!(parse("command_timeout", "-1"), None);
+        assert_eq!(
+            parse("timestamp_timeout", "-1"),
+            Some((5 * 60, u64::MAX, 0))
+        );
+        assert_eq!(parse("timestamp_timeout", "-1.0"), None);
+    }
+
+    #[test]
+    fn negated_lists_are_empty() {
+        for name in ["env_keep", "env_check", "env_delete"] {
+            let mut def = Settings::default();
+            negate(name).unwrap()(&mut def);
+
+            let SettingKind::List(f) = set(name).unwrap() else {
+                panic!()
+            };
+            f(ListMode::Add, vec!["FOO".to_string()])(&mut def);
+
+            let list = match name {
+                "env_keep" => &def.env_keep,
+                "env_check" => &def.env_check,
+                _ => &def.env_delete,
+            };
+            assert_eq!(list, &["FOO".to_string()].into(), "{name}");
+        }
     }
 }
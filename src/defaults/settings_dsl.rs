@@ -60,11 +60,11 @@ macro_rules! modifier_of {
     ($id:ident, [ $($value: expr),* ]) => {
         $crate::defaults::SettingKind::List(|mode, list| {
             Box::new(move |obj: &mut Settings| match mode {
-                ListMode::Set => obj.$id = list.into_iter().collect(),
-                ListMode::Add => obj.$id.extend(list),
+                ListMode::Set => obj.$id = list.iter().cloned().collect(),
+                ListMode::Add => obj.$id.extend(list.iter().cloned()),
                 ListMode::Del => {
-                    for key in list {
-                        obj.$id.remove(&key);
+                    for key in &list {
+                        obj.$id.remove(key);
                     }
                 }
             })
@@ -101,14 +101,14 @@ macro_rules! modifier_of {
     };
     ($id:ident, None) => {
         $crate::defaults::SettingKind::Text(|text| {
-            let text = text.into();
-            Some(Box::new(move |obj: &mut Settings| obj.$id = Some(text)))
+            let text: Box<str> = text.into();
+            Some(Box::new(move |obj: &mut Settings| obj.$id = Some(text.clone())))
         })
     };
     ($id:ident, $value: expr) => {
         $crate::defaults::SettingKind::Text(|text| {
-            let text = text.into();
-            Some(Box::new(move |obj: &mut Settings| obj.$id = text))
+            let text: Box<str> = text.into();
+            Some(Box::new(move |obj: &mut Settings| obj.$id = text.clone()))
         })
     };
 }
@@ -189,7 +189,7 @@ macro_rules! defaults {
                         // for bool and sets, false/empty works (for other types this is dead code)
                         Default::default()
                     );
-                    Some(Box::new(move |obj: &mut Settings| obj.$name = value))
+                    Some(Box::new(move |obj: &mut Settings| obj.$name = Clone::clone(&value)))
                 },
                 )*
                 _ => None
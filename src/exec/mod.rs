@@ -9,7 +9,7 @@ use std::{
     borrow::Cow,
     convert::Infallible,
     env,
-    ffi::{OsStr, OsString, c_int},
+    ffi::{CString, OsStr, OsString, c_int},
     io,
     os::unix::{ffi::OsStrExt, process::CommandExt},
     path::{Path, PathBuf},
@@ -24,9 +24,9 @@ use crate::{
     exec::no_pty::exec_no_pty,
     log::{dev_info, dev_warn, user_error},
     system::{
-        _exit, ForkResult, Group, User, fork,
+        _exit, ForkResult, Group, User, chroot, fork,
         interface::ProcessId,
-        kill, killpg, mark_fds_as_cloexec, set_target_user, setpgid,
+        kill, killpg, mark_fds_as_cloexec_from, set_target_user, setpgid,
         signal::{SignalNumber, SignalSet, SignalsState, consts::*, exit_with_signal, signal_name},
         term::UserTerm,
         wait::{Wait, WaitError, WaitOptions},
@@ -60,12 +60,30 @@ pub enum Umask {
     Override(libc::mode_t) = HARDENED_ENUM_VALUE_2,
 }
 
+/// What to do when the working directory of a login shell, the home directory of the target
+/// user, can not be entered.
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(test, derive(PartialEq))]
+#[repr(u32)]
+pub enum HomeFallback {
+    /// Warn and keep the current working directory, like ogsudo does.
+    Stay = HARDENED_ENUM_VALUE_0,
+    /// Warn and use the root directory as the working directory instead.
+    Root = HARDENED_ENUM_VALUE_1,
+    /// Refuse to run the command.
+    Error = HARDENED_ENUM_VALUE_2,
+}
+
 pub struct RunOptions<'a> {
     pub command: &'a Path,
     pub arguments: &'a [OsString],
     pub arg0: Option<&'a Path>,
     pub chdir: Option<PathBuf>,
+    /// The directory that becomes the root directory of the command; `chdir` and the command
+    /// itself are relative to it.
+    pub chroot: Option<PathBuf>,
     pub is_login: bool,
+    pub home_fallback: HomeFallback,
     pub user: &'a User,
     pub group: &'a Group,
     pub umask: Umask,
@@ -73,10 +91,30 @@ pub struct RunOptions<'a> {
     pub background: bool,
     pub use_pty: bool,
     pub noexec: bool,
+    /// The lowest file descriptor that is closed for the command; by default, only the IO
+    /// streams are left open.
+    pub close_from: Option<c_int>,
+    /// How long the command may run before it is terminated.
+    pub command_timeout: Option<Duration>,
+    /// Inherited file descriptors that are passed to the command regardless of `close_from`,
+    /// in ascending order.
+    pub preserve_fds: Vec<c_int>,
+}
+
+/// The file descriptors that the command should not inherit.
+#[derive(Clone, Copy)]
+struct ClosedFds<'a> {
+    /// The lowest file descriptor that is closed
+    from: c_int,
+    /// The (sorted) file descriptors above `from` that are left open anyway
+    except: &'a [c_int],
 }
 
 /// Based on `ogsudo`s `exec_pty` function.
 ///
+/// The command only gets a pseudo-terminal and a separate monitor process if `use_pty` is set
+/// and the user's terminal can be opened; otherwise it is forked directly from this process.
+///
 /// Returns the [`ExitReason`] of the command and a function that restores the default handler for
 /// signals once its called.
 pub fn run_command(
@@ -138,12 +176,41 @@ pub fn run_command(
         .or_else(|| options.is_login.then(|| options.user.home.clone().into()))
         .clone();
 
+    // change the root directory; this requires privileges, so it is done before the target
+    // user is set, and before the working directory is changed
+    if let Some(root) = &options.chroot {
+        let root_c = CString::new(root.as_os_str().as_bytes())?;
+        let root = root.clone();
+
+        // SAFETY: Chroot and chdir are async-signal-safe. The logger we use is also
+        // async-signal-safe.
+        unsafe {
+            command.pre_exec(move || {
+                if let Err(err) = chroot(&root_c).and_then(|()| env::set_current_dir("/")) {
+                    user_error!(
+                        "unable to change root directory to {path}: {error}",
+                        path = root.display(),
+                        error = err
+                    );
+                    return Err(err);
+                }
+
+                Ok(())
+            });
+        }
+    }
+
     // set target user and groups
     set_target_user(&mut command, options.user.clone(), options.group.clone());
 
     // change current directory if necessary.
     if let Some(path) = path {
-        let is_chdir = options.chdir.is_some();
+        // an explicitly requested directory must always be entered
+        let fallback = if options.chdir.is_some() {
+            HomeFallback::Error
+        } else {
+            options.home_fallback
+        };
 
         // SAFETY: Chdir as used internally by set_current_dir is async-signal-safe. The logger we
         // use is also async-signal-safe.
@@ -155,8 +222,10 @@ pub fn run_command(
                         path = path.display(),
                         error = err
                     );
-                    if is_chdir {
-                        return Err(err);
+                    match fallback {
+                        HomeFallback::Stay => {}
+                        HomeFallback::Root => env::set_current_dir("/")?,
+                        HomeFallback::Error => return Err(err),
                     }
                 }
 
@@ -189,6 +258,10 @@ pub fn run_command(
     }
 
     let sudo_pid = ProcessId::new(std::process::id() as i32);
+    let closed_fds = ClosedFds {
+        from: options.close_from.unwrap_or(libc::STDERR_FILENO + 1),
+        except: &options.preserve_fds,
+    };
 
     if options.use_pty {
         match UserTerm::open() {
@@ -196,17 +269,31 @@ pub fn run_command(
                 sudo_pid,
                 spawn_noexec_handler,
                 command,
+                closed_fds,
                 user_tty,
                 options.user,
                 options.background,
+                options.command_timeout,
             ),
             Err(err) => {
                 dev_info!("Could not open user's terminal, not allocating a pty: {err}");
-                exec_no_pty(sudo_pid, spawn_noexec_handler, command)
+                exec_no_pty(
+                    sudo_pid,
+                    spawn_noexec_handler,
+                    command,
+                    closed_fds,
+                    options.command_timeout,
+                )
             }
         }
     } else {
-        exec_no_pty(sudo_pid, spawn_noexec_handler, command)
+        exec_no_pty(
+            sudo_pid,
+            spawn_noexec_handler,
+            command,
+            closed_fds,
+            options.command_timeout,
+        )
     }
 }
 
@@ -228,6 +315,7 @@ impl ExitReason {
 
 fn exec_command(
     mut command: Command,
+    closed_fds: ClosedFds<'_>,
     original_set: Option<SignalSet>,
     mut original_signal: SignalsState,
     mut errpipe_tx: BinPipe<i32>,
@@ -244,7 +332,7 @@ fn exec_command(
         }
     }
 
-    if let Err(err) = mark_fds_as_cloexec() {
+    if let Err(err) = mark_fds_as_cloexec_from(closed_fds.from, closed_fds.except) {
         dev_warn!("failed to close the universe: {err}");
         // Send the error to the monitor using the pipe.
         if let Some(error_code) = err.raw_os_error() {
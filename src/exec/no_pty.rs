@@ -1,7 +1,7 @@
-use std::{ffi::c_int, io, process::Command};
+use std::{ffi::c_int, io, os::unix::process::CommandExt, process::Command, time::Duration};
 
 use super::{
-    ExitReason, HandleSigchld,
+    ClosedFds, ExitReason, HandleSigchld,
     event::PollEvent,
     event::{EventRegistry, Process, StopReason},
     io_util::was_interrupted,
@@ -18,9 +18,9 @@ use crate::{
     exec::{SpawnNoexecHandler, exec_command, handle_sigchld, signal_fmt},
     log::{dev_error, dev_info, dev_warn},
     system::{
-        ForkResult, fork, getpgid, getpgrp,
+        ForkResult, alarm, fork, getpgid, getpgrp,
         interface::ProcessId,
-        kill, killpg,
+        kill, killpg, setpgid,
         term::{Terminal, UserTerm},
         wait::WaitOptions,
     },
@@ -29,7 +29,9 @@ use crate::{
 pub(super) fn exec_no_pty(
     sudo_pid: ProcessId,
     spawn_noexec_handler: Option<SpawnNoexecHandler>,
-    command: Command,
+    mut command: Command,
+    closed_fds: ClosedFds<'_>,
+    command_timeout: Option<Duration>,
 ) -> io::Result<ExitReason> {
     // FIXME (ogsudo): Initialize the policy plugin's session here.
 
@@ -50,13 +52,25 @@ pub(super) fn exec_no_pty(
     // Use a pipe to get the IO error if `exec` fails.
     let (errpipe_tx, errpipe_rx) = BinPipe::pair()?;
 
+    // When the command can time out, it gets its own process group so that any processes it
+    // spawns are terminated together with it, see `ExecClosure::on_signal`.
+    if command_timeout.is_some() {
+        command.process_group(0);
+    }
+
     // SAFETY: There should be no other threads at this point.
     let ForkResult::Parent(command_pid) = unsafe { fork() }.map_err(|err| {
         dev_warn!("unable to fork command process: {err}");
         err
     })?
     else {
-        exec_command(command, original_set, original_signals, errpipe_tx);
+        exec_command(
+            command,
+            closed_fds,
+            original_set,
+            original_signals,
+            errpipe_tx,
+        );
     };
 
     if let Some(spawner) = spawn_noexec_handler {
@@ -65,6 +79,22 @@ pub(super) fn exec_no_pty(
 
     dev_info!("executed command with pid {command_pid}");
 
+    // If sudo runs in the foreground, the command's process group takes its place there so it can
+    // still use the terminal. The foreground process group is restored once the command is done.
+    let mut foreground_tty = None;
+    if command_timeout.is_some() {
+        // Both the parent and the child set the process group to avoid a race.
+        setpgid(command_pid, command_pid).ok();
+
+        if let Ok(tty) = UserTerm::open() {
+            if tty.tcgetpgrp().is_ok_and(|pgrp| pgrp == getpgrp())
+                && tty.tcsetpgrp_nobg(command_pid).is_ok()
+            {
+                foreground_tty = Some(tty);
+            }
+        }
+    }
+
     let mut registry = EventRegistry::new();
 
     let mut closure = ExecClosure::new(
@@ -75,6 +105,11 @@ pub(super) fn exec_no_pty(
         original_signals,
     )?;
 
+    // The command is terminated when `SIGALRM` arrives, see `ExecClosure::on_signal`.
+    if let Some(timeout) = command_timeout {
+        alarm(timeout);
+    }
+
     // Restore the signal mask now that the handlers have been setup.
     if let Some(set) = original_set {
         if let Err(err) = set.set_mask() {
@@ -82,7 +117,13 @@ pub(super) fn exec_no_pty(
         }
     }
 
-    let command_exit_reason = match registry.event_loop(&mut closure) {
+    let stop_reason = registry.event_loop(&mut closure);
+
+    if let Some(tty) = foreground_tty {
+        tty.tcsetpgrp_nobg(closure.parent_pgrp).ok();
+    }
+
+    let command_exit_reason = match stop_reason {
         StopReason::Break(err) => return Err(err),
         StopReason::Exit(reason) => reason,
     };
@@ -104,6 +145,15 @@ struct ExecClosure {
 }
 
 impl ExecClosure {
+    /// The signals handled while the command runs without a pty:
+    ///
+    /// - `SIGCHLD` is used to reap the command and to suspend sudo when the command stops.
+    /// - `SIGALRM` terminates the command, and its process group if it has one of its own.
+    /// - Every other signal (including `SIGINT`, `SIGQUIT`, `SIGTSTP`, `SIGTERM`, `SIGHUP`,
+    ///   `SIGCONT` and `SIGWINCH`) is forwarded to the command, unless the command sent it to
+    ///   itself or to its own process group.
+    ///
+    /// Signals not in this list keep their original disposition.
     const SIGNALS: [SignalNumber; 12] = [
         SIGINT, SIGQUIT, SIGTSTP, SIGTERM, SIGHUP, SIGALRM, SIGPIPE, SIGUSR1, SIGUSR2, SIGCHLD,
         SIGCONT, SIGWINCH,
@@ -256,7 +306,10 @@ impl ExecClosure {
                 }
 
                 if signal == SIGALRM {
-                    terminate_process(command_pid, false);
+                    match getpgid(command_pid) {
+                        Ok(pgrp) if pgrp != self.parent_pgrp => terminate_process(pgrp, true),
+                        _ => terminate_process(command_pid, false),
+                    }
                 } else {
                     kill(command_pid, signal).ok();
                 }
@@ -310,3 +363,21 @@ impl HandleSigchld for ExecClosure {
         self.suspend_parent(signal);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_pty_signal_table() {
+        for signal in [
+            SIGINT, SIGQUIT, SIGTSTP, SIGTERM, SIGHUP, SIGCONT, SIGWINCH, SIGALRM, SIGCHLD,
+        ] {
+            assert!(
+                ExecClosure::SIGNALS.contains(&signal),
+                "{}",
+                signal_fmt(signal)
+            );
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use std::{convert::Infallible, ffi::c_int, io, process::Command};
 
-use crate::exec::{opt_fmt, signal_fmt};
+use crate::exec::{ClosedFds, opt_fmt, signal_fmt};
 use crate::system::signal::{
     SignalHandler, SignalHandlerBehavior, SignalNumber, SignalSet, SignalStream, SignalsState,
     consts::*, register_handlers,
@@ -37,6 +37,7 @@ use super::CommandStatus;
 pub(super) fn exec_monitor(
     pty_follower: PtyFollower,
     command: Command,
+    closed_fds: ClosedFds<'_>,
     foreground: bool,
     backchannel: &mut MonitorBackchannel,
     original_set: Option<SignalSet>,
@@ -112,7 +113,13 @@ pub(super) fn exec_monitor(
         // Done with the pty follower.
         drop(pty_follower);
 
-        exec_command(command, original_set, original_signals, errpipe_tx)
+        exec_command(
+            command,
+            closed_fds,
+            original_set,
+            original_signals,
+            errpipe_tx,
+        )
     };
 
     // Send the command's PID to the parent.
@@ -224,6 +231,16 @@ struct MonitorClosure<'a> {
 }
 
 impl<'a> MonitorClosure<'a> {
+    /// The signals handled by the monitor:
+    ///
+    /// - `SIGCHLD` is used to reap the command and to report when it stops.
+    /// - Every other signal (including `SIGINT`, `SIGQUIT`, `SIGTSTP`, `SIGTERM` and `SIGHUP`) is
+    ///   forwarded to the command, unless the command sent it to itself or to its own process
+    ///   group.
+    ///
+    /// `SIGALRM`, `SIGCONT` and `SIGWINCH` are not handled here: the parent receives them and
+    /// relays them over the backchannel or through the pty instead. `SIGTTIN` and `SIGTTOU` are
+    /// ignored, see [`exec_monitor`].
     const SIGNALS: [SignalNumber; 8] = [
         SIGINT, SIGQUIT, SIGTSTP, SIGTERM, SIGHUP, SIGUSR1, SIGUSR2, SIGCHLD,
     ];
@@ -322,7 +339,9 @@ impl<'a> MonitorClosure<'a> {
         );
         match signal {
             SIGALRM => {
-                terminate_process(command_pid, false);
+                // The command runs in its own process group, terminate all of it so that any
+                // background processes spawned by the command do not outlive it.
+                terminate_process(self.command_pgrp, true);
             }
             SIGCONT_FG => {
                 // Continue with the command as the foreground process group
@@ -455,3 +474,26 @@ impl HandleSigchld for MonitorClosure<'_> {
             .ok();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn monitor_signal_table() {
+        for signal in [SIGINT, SIGQUIT, SIGTSTP, SIGTERM, SIGHUP, SIGCHLD] {
+            assert!(
+                MonitorClosure::SIGNALS.contains(&signal),
+                "{}",
+                signal_fmt(signal)
+            );
+        }
+        for signal in [SIGALRM, SIGCONT, SIGWINCH, SIGTTIN, SIGTTOU] {
+            assert!(
+                !MonitorClosure::SIGNALS.contains(&signal),
+                "{}",
+                signal_fmt(signal)
+            );
+        }
+    }
+}
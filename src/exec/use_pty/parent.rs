@@ -3,6 +3,7 @@ use std::ffi::c_int;
 use std::io;
 use std::os::fd::{FromRawFd, OwnedFd};
 use std::process::{Command, Stdio};
+use std::time::Duration;
 
 use libc::{O_CLOEXEC, close};
 
@@ -11,7 +12,7 @@ use crate::exec::event::{EventHandle, EventRegistry, PollEvent, Process, StopRea
 use crate::exec::use_pty::SIGCONT_FG;
 use crate::exec::use_pty::monitor::exec_monitor;
 use crate::exec::{
-    ExitReason, SpawnNoexecHandler,
+    ClosedFds, ExitReason, SpawnNoexecHandler,
     io_util::retry_while_interrupted,
     use_pty::backchannel::{BackchannelPair, MonitorMessage, ParentBackchannel, ParentMessage},
 };
@@ -23,19 +24,22 @@ use crate::system::signal::{
 };
 use crate::system::term::{Pty, PtyFollower, PtyLeader, TermSize, Terminal, UserTerm};
 use crate::system::wait::WaitOptions;
-use crate::system::{_exit, ForkResult, Group, User, chown, fork, getpgrp, kill, killpg};
+use crate::system::{_exit, ForkResult, Group, User, alarm, chown, fork, getpgrp, kill, killpg};
 use crate::system::{getpgid, interface::ProcessId};
 
 use super::pipe::Pipe;
 use super::{CommandStatus, SIGCONT_BG};
 
+#[allow(clippy::too_many_arguments)]
 pub(in crate::exec) fn exec_pty(
     sudo_pid: ProcessId,
     spawn_noexec_handler: Option<SpawnNoexecHandler>,
     mut command: Command,
+    closed_fds: ClosedFds<'_>,
     user_tty: UserTerm,
     pty_owner: &User,
     background: bool,
+    command_timeout: Option<Duration>,
 ) -> io::Result<ExitReason> {
     // Allocate a pseudoterminal.
     let pty = get_pty(pty_owner)?;
@@ -227,6 +231,7 @@ pub(in crate::exec) fn exec_pty(
         match exec_monitor(
             pty.follower,
             command,
+            closed_fds,
             foreground && !exec_bg,
             &mut backchannels.monitor,
             original_set,
@@ -282,6 +287,11 @@ pub(in crate::exec) fn exec_pty(
         original_signals,
     )?;
 
+    // `SIGALRM` is passed on to the monitor, which terminates the process group of the command.
+    if let Some(timeout) = command_timeout {
+        alarm(timeout);
+    }
+
     // Restore the signal mask now that the handlers have been setup.
     if let Some(set) = original_set {
         if let Err(err) = set.set_mask() {
@@ -344,6 +354,8 @@ struct ParentClosure {
     command_pid: Option<ProcessId>,
     tty_pipe: Pipe<UserTerm, PtyLeader>,
     tty_size: TermSize,
+    /// Whether the user's terminal was revoked (e.g. because its emulator was closed).
+    tty_revoked: bool,
     foreground: bool,
     term_raw: bool,
     preserve_oflag: bool,
@@ -356,6 +368,17 @@ struct ParentClosure {
 }
 
 impl ParentClosure {
+    /// The signals handled by the parent while the command runs in a pty:
+    ///
+    /// - `SIGCHLD` is used to reap the monitor.
+    /// - `SIGCONT` restores the user's terminal after sudo is resumed.
+    /// - `SIGWINCH` copies the terminal size to the pty, which signals the command.
+    /// - Every other signal (including `SIGINT`, `SIGQUIT`, `SIGTSTP`, `SIGTERM`, `SIGHUP` and
+    ///   `SIGALRM`) is sent to the monitor over the backchannel, unless the command sent it to
+    ///   itself or to its own process group.
+    ///
+    /// `SIGTTIN` and `SIGTTOU` are ignored, see [`exec_pty`]. Signals not in this list keep their
+    /// original disposition.
     const SIGNALS: [SignalNumber; 11] = [
         SIGINT, SIGQUIT, SIGTSTP, SIGTERM, SIGHUP, SIGALRM, SIGUSR1, SIGUSR2, SIGCHLD, SIGCONT,
         SIGWINCH,
@@ -398,6 +421,7 @@ impl ParentClosure {
             command_pid: None,
             tty_pipe,
             tty_size,
+            tty_revoked: false,
             foreground,
             term_raw,
             preserve_oflag,
@@ -478,7 +502,7 @@ impl ParentClosure {
                                     self.schedule_signal(signal, registry);
                                 }
 
-                                self.tty_pipe.resume_events(registry);
+                                self.resume_tty_events(registry);
                             }
                         }
                     }
@@ -653,6 +677,13 @@ impl ParentClosure {
         Some(ret_signal)
     }
 
+    /// Resume polling the terminals, unless the user's terminal was revoked.
+    fn resume_tty_events(&mut self, registry: &mut EventRegistry<Self>) {
+        if !self.tty_revoked {
+            self.tty_pipe.resume_events(registry);
+        }
+    }
+
     /// Check whether we are part of the foreground process group and update the foreground flag.
     fn check_foreground(&mut self) -> io::Result<()> {
         let pgrp = self.tty_pipe.left().tcgetpgrp()?;
@@ -797,6 +828,12 @@ impl Process for ParentClosure {
                 if self.tty_pipe.left().tcgetsid().is_err() {
                     dev_warn!("tty gone (closed/detached), ignoring future events");
                     self.tty_pipe.ignore_events(registry);
+                    // Like a process running on the terminal itself, the command is told that
+                    // the terminal hung up.
+                    if !self.tty_revoked {
+                        self.tty_revoked = true;
+                        self.schedule_signal(SIGHUP, registry);
+                    }
                 } else {
                     self.tty_pipe.on_left_event(poll_event, registry).ok();
                 }
@@ -827,6 +864,31 @@ impl HandleSigchld for ParentClosure {
         if let Some(signal) = self.suspend_pty(signal, registry) {
             self.schedule_signal(signal, registry);
         }
-        self.tty_pipe.resume_events(registry);
+        self.resume_tty_events(registry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parent_signal_table() {
+        for signal in [
+            SIGINT, SIGQUIT, SIGTSTP, SIGTERM, SIGHUP, SIGCONT, SIGWINCH, SIGALRM, SIGCHLD,
+        ] {
+            assert!(
+                ParentClosure::SIGNALS.contains(&signal),
+                "{}",
+                signal_fmt(signal)
+            );
+        }
+        for signal in [SIGTTIN, SIGTTOU] {
+            assert!(
+                !ParentClosure::SIGNALS.contains(&signal),
+                "{}",
+                signal_fmt(signal)
+            );
+        }
     }
 }
@@ -0,0 +1,73 @@
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+
+use crate::log::{Level, Log};
+use crate::system::time::format_local_time;
+
+/// Writes log messages to a file, each line prefixed with the local time and optionally the
+/// name of the host.
+pub struct FileLogger {
+    target: File,
+    year: bool,
+    host: Option<String>,
+}
+
+impl FileLogger {
+    pub fn open(path: &Path, year: bool, host: Option<String>) -> io::Result<Self> {
+        let target = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .mode(0o600)
+            .custom_flags(libc::O_NOFOLLOW)
+            .open(path)?;
+
+        Ok(Self { target, year, host })
+    }
+
+    fn line(&self, time: &str, args: &dyn fmt::Display) -> String {
+        match &self.host {
+            Some(host) => format!("{time} : HOST={host} : {args}\n"),
+            None => format!("{time} : {args}\n"),
+        }
+    }
+}
+
+impl Log for FileLogger {
+    fn log(&self, _level: Level, args: &dyn fmt::Display) {
+        let format = if self.year {
+            c"%h %e %T %Y"
+        } else {
+            c"%h %e %T"
+        };
+        let time = format_local_time(format).unwrap_or_default();
+        let _ = (&self.target).write_all(self.line(&time, args).as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_format() {
+        let path = std::env::temp_dir().join(format!("sudo-test-log-{}", std::process::id()));
+        let mut logger = FileLogger::open(&path, false, None).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let time = "Oct 16 12:34:56";
+        let message = "ferris : PWD=/home/ferris ; USER=root ; COMMAND=/usr/bin/true";
+        assert_eq!(
+            logger.line(time, &message),
+            format!("Oct 16 12:34:56 : {message}\n")
+        );
+
+        logger.host = Some("crab".to_string());
+        assert_eq!(
+            logger.line(time, &message),
+            format!("Oct 16 12:34:56 : HOST=crab : {message}\n")
+        );
+    }
+}
@@ -1,8 +1,11 @@
+use self::file_logger::FileLogger;
 use self::simple_logger::SimpleLogger;
 use self::syslog::Syslog;
 use std::fmt;
+use std::path::Path;
 use std::sync::OnceLock;
 
+mod file_logger;
 mod simple_logger;
 mod syslog;
 
@@ -68,6 +71,21 @@ dev_logger_macro!(dev_debug is Debug);
 
 pub static LOGGER: OnceLock<SudoLogger> = OnceLock::new();
 
+/// The log file is only known once the sudoers file has been read, so it is kept apart
+/// from the other loggers, which are set up right at the start.
+static LOG_FILE: OnceLock<FileLogger> = OnceLock::new();
+
+/// Also write everything that is sent to the system log to the given file; if that file can
+/// not be opened, this is reported to the system log instead.
+pub fn add_log_file(path: &Path, year: bool, host: Option<&str>) {
+    match FileLogger::open(path, year, host.map(str::to_string)) {
+        Ok(logger) => {
+            let _ = LOG_FILE.set(logger);
+        }
+        Err(e) => auth_warn!("unable to open log file {}: {e}", path.display()),
+    }
+}
+
 #[derive(Default)]
 pub struct SudoLogger(Vec<(Sink, Box<dyn Log>)>);
 
@@ -111,6 +129,12 @@ impl SudoLogger {
                 l.log(level, &args);
             }
         }
+
+        if target == Sink::AuthLog {
+            if let Some(l) = LOG_FILE.get() {
+                l.log(level, &args);
+            }
+        }
     }
 }
 
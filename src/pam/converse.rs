@@ -1,5 +1,6 @@
 use std::cell::Cell;
 use std::ffi::{c_int, c_void};
+use std::path::PathBuf;
 use std::time::Duration;
 
 use crate::cutils::string_from_ptr;
@@ -77,6 +78,7 @@ fn handle_message<C: Converser>(
                     // Suppress password prompt entirely when -p '' is passed.
                     String::new()
                 }
+                Some(prompt) if app_data.auth_prompt_override => prompt.to_owned(),
                 Some(prompt) => {
                     format!("[{}: {prompt}] {msg}", app_data.converser_name)
                 }
@@ -97,6 +99,7 @@ fn handle_message<C: Converser>(
 pub struct CLIConverser {
     pub(super) name: String,
     pub(super) use_askpass: bool,
+    pub(super) askpass: Option<PathBuf>,
     pub(super) use_stdin: bool,
     pub(super) bell: Cell<bool>,
     pub(super) password_feedback: bool,
@@ -131,20 +134,42 @@ impl Drop for SignalGuard {
 impl CLIConverser {
     fn open(&self) -> PamResult<(Terminal<'_>, SignalGuard)> {
         let term = if self.use_askpass {
-            Terminal::open_askpass()?
+            Terminal::open_askpass(self.askpass.as_deref())?
         } else if self.use_stdin {
             Terminal::open_stdie()?
         } else {
-            let mut tty = Terminal::open_tty()?;
-            if self.bell.replace(false) {
-                tty.bell()?;
-            }
+            match Terminal::open_tty() {
+                Ok(mut tty) => {
+                    if self.bell.replace(false) {
+                        tty.bell()?;
+                    }
 
-            tty
+                    tty
+                }
+                // without a terminal, fall back to the askpass program if there is one
+                Err(PamError::TtyRequired) if self.askpass.is_some() => {
+                    Terminal::open_askpass(self.askpass.as_deref())?
+                }
+                Err(e) => return Err(e),
+            }
         };
 
         Ok((term, SignalGuard::unblock_interrupts()))
     }
+
+    /// Show text where the password prompt would be shown, without ringing the bell;
+    /// there is nowhere to show it when an askpass program is used.
+    pub(super) fn display(&self, text: &[u8]) -> PamResult<()> {
+        let mut term = if self.use_askpass {
+            return Ok(());
+        } else if self.use_stdin {
+            Terminal::open_stdie()?
+        } else {
+            Terminal::open_tty()?
+        };
+
+        Ok(term.prompt(text)?)
+    }
 }
 
 impl Converser for CLIConverser {
@@ -173,12 +198,12 @@ impl Converser for CLIConverser {
 
     fn handle_error(&self, msg: &str) -> PamResult<()> {
         let (mut tty, _) = self.open()?;
-        Ok(tty.prompt(&format!("[{} error] {msg}\n", self.name))?)
+        Ok(tty.prompt(format!("[{} error] {msg}\n", self.name).as_bytes())?)
     }
 
     fn handle_info(&self, msg: &str) -> PamResult<()> {
         let (mut tty, _) = self.open()?;
-        Ok(tty.prompt(&format!("[{}] {msg}\n", self.name))?)
+        Ok(tty.prompt(format!("[{}] {msg}\n", self.name).as_bytes())?)
     }
 }
 
@@ -188,6 +213,7 @@ pub(super) struct ConverserData<C> {
     pub(super) converser_name: String,
     pub(super) no_interact: bool,
     pub(super) auth_prompt: Option<String>,
+    pub(super) auth_prompt_override: bool,
     // pam_authenticate does not return error codes returned by the conversation
     // function; these are set by the conversation function instead of returning
     // multiple error codes.
@@ -429,6 +455,7 @@ mod test {
             converser_name: "tux".to_string(),
             no_interact: false,
             auth_prompt: Some("authenticate".to_owned()),
+            auth_prompt_override: false,
             error: None,
             panicked: false,
         });
@@ -473,4 +500,60 @@ mod test {
 
         assert!(hello.panicked); // allowed now
     }
+
+    #[test]
+    fn miri_pam_prompt_override() {
+        let mut hello = Box::pin(ConverserData {
+            converser: "tux".to_string(),
+            converser_name: "tux".to_string(),
+            no_interact: false,
+            auth_prompt: Some("secret word?".to_owned()),
+            auth_prompt_override: false,
+            error: None,
+            panicked: false,
+        });
+        let cookie = PamConvBorrow::new(hello.as_mut());
+        let pam_conv = cookie.borrow();
+
+        assert_eq!(
+            dummy_pam(&[msg(PromptEchoOff, "PIN:")], pam_conv),
+            vec![Some("[tux: secret word?] PIN:".to_string())]
+        );
+
+        let real_hello = unsafe { &mut *(pam_conv.appdata_ptr as *mut ConverserData<String>) };
+        real_hello.auth_prompt_override = true;
+
+        assert_eq!(
+            dummy_pam(&[msg(PromptEchoOff, "PIN:")], pam_conv),
+            vec![Some("secret word?".to_string())]
+        );
+    }
+
+    #[test]
+    fn miri_pam_no_interact() {
+        let mut hello = Box::pin(ConverserData {
+            converser: "tux".to_string(),
+            converser_name: "tux".to_string(),
+            no_interact: true,
+            auth_prompt: Some("authenticate".to_owned()),
+            auth_prompt_override: false,
+            error: None,
+            panicked: false,
+        });
+        let cookie = PamConvBorrow::new(hello.as_mut());
+        let pam_conv = cookie.borrow();
+
+        // messages that do not need input are still shown
+        assert_eq!(dummy_pam(&[msg(TextInfo, "mars")], pam_conv), vec![None]);
+
+        for style in [PromptEchoOn, PromptEchoOff] {
+            assert_eq!(dummy_pam(&[msg(style, "PIN:")], pam_conv), vec![]);
+
+            let real_hello = unsafe { &mut *(pam_conv.appdata_ptr as *mut ConverserData<String>) };
+            assert!(matches!(
+                real_hello.error.take(),
+                Some(PamError::InteractionRequired)
+            ));
+        }
+    }
 }
@@ -3,6 +3,7 @@ use std::{
     io,
     os::raw::c_char,
     os::unix::prelude::OsStrExt,
+    path::PathBuf,
     ptr::NonNull,
     time::Duration,
 };
@@ -55,6 +56,7 @@ impl PamContext {
         converser_name: &str,
         service_name: &str,
         use_askpass: bool,
+        askpass: Option<PathBuf>,
         use_stdin: bool,
         bell: bool,
         no_interact: bool,
@@ -66,6 +68,7 @@ impl PamContext {
             bell: bell.into(),
             name: converser_name.to_owned(),
             use_askpass,
+            askpass,
             use_stdin,
             password_feedback,
             password_timeout,
@@ -84,6 +87,7 @@ impl PamContext {
             converser_name: converser_name.to_owned(),
             no_interact,
             auth_prompt: Some(xlat!("authenticate").to_owned()),
+            auth_prompt_override: false,
             error: None,
             panicked: false,
         }));
@@ -124,6 +128,22 @@ impl PamContext {
         }
     }
 
+    /// Set whether the authentication prompt replaces the prompt supplied by PAM
+    /// instead of being shown alongside it, by default it does not.
+    pub fn mark_auth_prompt_override(&mut self, auth_prompt_override: bool) {
+        // SAFETY: self.data_ptr was created by Box::into_raw
+        unsafe {
+            (*self.data_ptr).auth_prompt_override = auth_prompt_override;
+        }
+    }
+
+    /// Show a message to the user on the channel that is also used for the password prompt.
+    pub fn display(&self, text: &[u8]) -> PamResult<()> {
+        // SAFETY: self.data_ptr was created by Box::into_raw
+        let converser = unsafe { &(*self.data_ptr).converser };
+        converser.display(text)
+    }
+
     /// Set whether output of pam calls should be silent or not, by default
     /// PAM calls are not silent.
     pub fn mark_silent(&mut self, silent: bool) {
@@ -16,7 +16,7 @@
 use std::ffi::c_void;
 use std::io::{self, ErrorKind};
 use std::os::fd::{AsFd, AsRawFd, BorrowedFd};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 use std::{fs, mem};
 
@@ -372,10 +372,10 @@ impl TimeoutRead<'_> {
     }
 }
 
-/// A data structure representing either /dev/tty or /dev/stdin+stderr
+/// A data structure representing either /dev/tty or /dev/stdin+(/dev/tty or stderr)
 pub enum Terminal<'a> {
     Tty(fs::File),
-    StdIE(io::StdinLock<'a>, io::StderrLock<'a>),
+    StdIE(io::StdinLock<'a>, Box<dyn io::Write + 'a>),
     Askpass(PathBuf, io::Sink),
 }
 
@@ -393,16 +393,24 @@ impl Terminal<'_> {
         ))
     }
 
-    /// Open standard input and standard error for user communication
+    /// Open standard input for reading the user's input; prompts are written to the
+    /// current TTY if there is one, so they are not mixed up with the output of a program
+    /// that is piping the password into standard input, and to standard error otherwise.
     pub fn open_stdie() -> io::Result<Self> {
-        Ok(Terminal::StdIE(io::stdin().lock(), io::stderr().lock()))
+        let sink: Box<dyn io::Write> = match fs::OpenOptions::new().write(true).open("/dev/tty") {
+            Ok(tty) => Box::new(tty),
+            Err(_) => Box::new(io::stderr().lock()),
+        };
+
+        Ok(Terminal::StdIE(io::stdin().lock(), sink))
     }
 
-    pub fn open_askpass() -> PamResult<Self> {
-        let Some(program) = std::env::var_os("SUDO_ASKPASS") else {
+    /// Use a helper program to ask for input; it prints the input on its standard output
+    pub fn open_askpass(program: Option<&Path>) -> PamResult<Self> {
+        let Some(program) = program else {
             return Err(PamError::NoAskpassProgram);
         };
-        let program = PathBuf::from(program);
+        let program = program.to_owned();
 
         if program.is_absolute() {
             Ok(Terminal::Askpass(program, io::sink()))
@@ -419,8 +427,8 @@ impl Terminal<'_> {
         hidden: Hidden<()>,
     ) -> PamResult<PamBuffer> {
         match self {
-            Terminal::StdIE(stdin, stdout) => {
-                prompt_password(stdin.as_fd(), stdout, prompt, timeout, hidden)
+            Terminal::StdIE(stdin, sink) => {
+                prompt_password(stdin.as_fd(), sink, prompt, timeout, hidden)
             }
             Terminal::Tty(file) => {
                 prompt_password(file.as_fd(), &mut &*file, prompt, timeout, hidden)
@@ -429,25 +437,31 @@ impl Terminal<'_> {
                 let (command_pid, askpass_stdout) = askpass::spawn_askpass(program, prompt)?;
 
                 let mut reader = TimeoutRead::new(askpass_stdout.as_fd(), None);
-                let password = read_unbuffered(&mut reader, sink, &Hidden::No)?;
+                let password = read_unbuffered(&mut reader, sink, &Hidden::No);
 
-                loop {
+                let status = loop {
                     match command_pid.wait(WaitOptions::new()) {
-                        Ok(_) => break,
+                        Ok((_, status)) => break status,
                         Err(WaitError::Io(err)) if err.kind() == io::ErrorKind::Interrupted => {}
                         Err(WaitError::Io(err)) => return Err(PamError::IoError(err)),
                         Err(WaitError::NotReady) => unreachable!(),
                     }
+                };
+
+                // a helper that fails (e.g. because its dialog was closed) cancels
+                // the authentication, regardless of what it printed
+                if status.exit_status() != Some(0) {
+                    return Err(PamError::NoPasswordProvided);
                 }
 
-                Ok(password)
+                password
             }
         }
     }
 
     /// Display information
-    pub fn prompt(&mut self, text: &str) -> io::Result<()> {
-        write_unbuffered(self.sink(), text.as_bytes())
+    pub fn prompt(&mut self, text: &[u8]) -> io::Result<()> {
+        write_unbuffered(self.sink(), text)
     }
 
     /// Ring the bell
@@ -459,7 +473,7 @@ impl Terminal<'_> {
     // boilerplate reduction functions
     fn sink(&mut self) -> &mut dyn io::Write {
         match self {
-            Terminal::StdIE(_, x) => x,
+            Terminal::StdIE(_, x) => &mut **x,
             Terminal::Tty(x) => x,
             Terminal::Askpass(_, x) => x,
         }
@@ -7,7 +7,7 @@ use std::{
 };
 
 use crate::common::{error::Error, resolve::CurrentUser};
-use crate::exec::{RunOptions, Umask};
+use crate::exec::{HomeFallback, RunOptions, Umask};
 use crate::log::user_warn;
 use crate::system::{Group, User};
 use crate::{common::resolve::is_valid_executable, system::interface::UserId};
@@ -202,7 +202,9 @@ impl SuContext {
             arguments: &self.arguments,
             arg0: None,
             chdir: None,
+            chroot: None,
             is_login: self.options.login,
+            home_fallback: HomeFallback::Stay,
             user: &self.user,
             group: &self.group,
             umask: Umask::Preserve,
@@ -210,6 +212,9 @@ impl SuContext {
             background: false,
             use_pty: true,
             noexec: false,
+            close_from: None,
+            command_timeout: None,
+            preserve_fds: Vec::new(),
         }
     }
 }
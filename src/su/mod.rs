@@ -32,6 +32,7 @@ fn authenticate(requesting_user: &str, user: &str, login: bool) -> Result<PamCon
         "su",
         context,
         false,
+        None,
         use_stdin,
         false,
         false,
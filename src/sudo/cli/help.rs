@@ -2,9 +2,9 @@ pub fn usage_msg() -> &'static str {
     xlat!(
         "\
 usage: sudo -h | -K | -k | -V
-usage: sudo [-ABbknS] [-p prompt] [-D directory] [-g group] [-u user] [-i | -s] [command [arg ...]]
+usage: sudo [-ABbEknS] [-p prompt] [-C num] [-D directory] [-g group] [-u user] [-i | -s] [command [arg ...]]
 usage: sudo -v [-ABknS] [-p prompt] [-g group] [-u user]
-usage: sudo -l [-ABknS] [-p prompt] [-U user] [-g group] [-u user] [command [arg ...]]
+usage: sudo -l [-ABknS] [-h host] [-p prompt] [-U user] [-g group] [-u user] [command [arg ...]]
 usage: sudo -e [-ABknS] [-p prompt] [-D directory] [-g group] [-u user] file ..."
     )
 }
@@ -18,10 +18,13 @@ fn help_msg() -> &'static str {
   -A, --askpass                 use a helper program for password prompting
   -b, --background              run command in the background
   -B, --bell                    ring bell when prompting
+  -C, --close-from=num          close all file descriptors >= num
   -D, --chdir=directory         change the working directory before running command
+  -E, --preserve-env            preserve user environment when running command
   -e, --edit                    edit files instead of running a command
   -g, --group=group             run command as the specified group name or ID
   -h, --help                    display help message and exit
+  -h, --host=host               in list mode, display privileges on the specified host
   -i, --login                   run login shell as the target user; a command may also be specified
   -K, --remove-timestamp        remove timestamp file completely
   -k, --reset-timestamp         invalidate timestamp file
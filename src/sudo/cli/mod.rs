@@ -4,7 +4,7 @@ use std::ffi::OsStr;
 use std::str;
 use std::{borrow::Cow, ffi::OsString, mem};
 
-use crate::common::{DisplayOsStr, SudoPath, SudoString};
+use crate::common::{DisplayOsStr, DurationUnit, SudoPath, SudoString, parse_duration_spec};
 use crate::log::user_warn;
 
 pub mod help;
@@ -261,6 +261,8 @@ pub struct SudoListOptions {
     pub prompt: Option<String>,
     // -g
     pub group: Option<SudoString>,
+    // -h
+    pub host: Option<SudoString>,
     // -U
     pub other_user: Option<SudoString>,
     // -u
@@ -281,6 +283,7 @@ impl TryFrom<SudoOptions> for SudoListOptions {
         let stdin = mem::take(&mut opts.stdin);
         let prompt = mem::take(&mut opts.prompt);
         let group = mem::take(&mut opts.group);
+        let host = mem::take(&mut opts.host);
         let other_user = mem::take(&mut opts.other_user);
         let user = mem::take(&mut opts.user);
         let positional_args = mem::take(&mut opts.positional_args);
@@ -315,6 +318,7 @@ impl TryFrom<SudoOptions> for SudoListOptions {
             stdin,
             prompt,
             group,
+            host,
             other_user,
             user,
             positional_args,
@@ -331,7 +335,9 @@ pub struct SudoRunOptions {
     // -b
     pub background: bool,
     // -E
-    /* ignored, part of env_var_list */
+    pub preserve_environment: bool,
+    // -H
+    pub set_home: bool,
     // -k
     pub reset_timestamp: bool,
     // -n
@@ -340,18 +346,28 @@ pub struct SudoRunOptions {
     pub stdin: bool,
     // -p
     pub prompt: Option<String>,
+    // -C
+    pub close_from: Option<i32>,
     // -D
     pub chdir: Option<SudoPath>,
+    // -R
+    pub chroot: Option<SudoPath>,
+    // -T
+    pub command_timeout: Option<u64>,
     // -g
     pub group: Option<SudoString>,
     // -u
     pub user: Option<SudoString>,
     // VAR=value
     pub env_var_list: Vec<(String, String)>,
+    // --preserve-env=list
+    pub preserve_env: Vec<String>,
     // -i
     pub login: bool,
     // -s
     pub shell: bool,
+    // --dry-run
+    pub dry_run: bool,
     pub positional_args: Vec<OsString>,
 }
 
@@ -362,16 +378,23 @@ impl TryFrom<SudoOptions> for SudoRunOptions {
         let askpass = mem::take(&mut opts.askpass);
         let bell = mem::take(&mut opts.bell);
         let background = mem::take(&mut opts.background);
+        let preserve_environment = mem::take(&mut opts.preserve_environment);
+        let set_home = mem::take(&mut opts.set_home);
         let reset_timestamp = mem::take(&mut opts.reset_timestamp);
         let non_interactive = mem::take(&mut opts.non_interactive);
         let stdin = mem::take(&mut opts.stdin);
         let prompt = mem::take(&mut opts.prompt);
+        let close_from = mem::take(&mut opts.close_from);
         let chdir = mem::take(&mut opts.chdir);
+        let chroot = mem::take(&mut opts.chroot);
+        let command_timeout = mem::take(&mut opts.command_timeout);
         let group = mem::take(&mut opts.group);
         let user = mem::take(&mut opts.user);
         let env_var_list = mem::take(&mut opts.env_var_list);
+        let preserve_env = mem::take(&mut opts.preserve_env);
         let login = mem::take(&mut opts.login);
         let shell = mem::take(&mut opts.shell);
+        let dry_run = mem::take(&mut opts.dry_run);
         let positional_args = mem::take(&mut opts.positional_args);
 
         if bell && stdin {
@@ -413,16 +436,23 @@ impl TryFrom<SudoOptions> for SudoRunOptions {
             askpass,
             bell,
             background,
+            preserve_environment,
+            set_home,
             reset_timestamp,
             non_interactive,
             stdin,
             prompt,
+            close_from,
             chdir,
+            chroot,
+            command_timeout,
             group,
             user,
             env_var_list,
+            preserve_env,
             login,
             shell,
+            dry_run,
             positional_args,
         })
     }
@@ -436,10 +466,18 @@ struct SudoOptions {
     bell: bool,
     // -b
     background: bool,
+    // -C
+    close_from: Option<i32>,
     // -D
     chdir: Option<SudoPath>,
+    // -R
+    chroot: Option<SudoPath>,
+    // -T
+    command_timeout: Option<u64>,
     // -g
     group: Option<SudoString>,
+    // -h
+    host: Option<SudoString>,
     // -i
     login: bool,
     // -n
@@ -447,7 +485,9 @@ struct SudoOptions {
     // -U
     other_user: Option<SudoString>,
     // -E
-    /* ignored, part of env_var_list */
+    preserve_environment: bool,
+    // -H
+    set_home: bool,
     // -s
     shell: bool,
     // -S
@@ -456,9 +496,13 @@ struct SudoOptions {
     prompt: Option<String>,
     // -u
     user: Option<SudoString>,
+    // --dry-run
+    dry_run: bool,
 
     // additional environment
     env_var_list: Vec<(String, String)>,
+    // --preserve-env=list
+    preserve_env: Vec<String>,
 
     /* actions */
     // -e
@@ -508,9 +552,11 @@ fn demand_utf8(arg: &OsStr) -> String {
 }
 
 impl SudoArg {
-    const TAKES_ARGUMENT_SHORT: &'static [char] = &['D', 'g', 'h', 'p', 'R', 'U', 'u'];
+    const TAKES_ARGUMENT_SHORT: &'static [char] = &['C', 'D', 'g', 'h', 'p', 'R', 'T', 'U', 'u'];
     const TAKES_ARGUMENT: &'static [&'static str] = &[
         "chdir",
+        "close-from",
+        "command-timeout",
         "group",
         "host",
         "chroot",
@@ -667,18 +713,17 @@ impl SudoOptions {
                     "-b" | "--background" => {
                         options.background = true;
                     }
+                    "--dry-run" => {
+                        options.dry_run = true;
+                    }
                     "-E" | "--preserve-env" => {
-                        user_warn!(
-                            "preserving the entire environment is not supported, '{flag}' is ignored",
-                            flag = flag
-                        )
+                        options.preserve_environment = true;
                     }
                     "-e" | "--edit" if !invoked_as_sudoedit => {
                         options.edit = true;
                     }
                     "-H" | "--set-home" => {
-                        // this option is ignored, since it is the default for sudo-rs; but accept
-                        // it for backwards compatibility reasons
+                        options.set_home = true;
                     }
                     "-h" | "--help" => {
                         options.help = true;
@@ -717,21 +762,54 @@ impl SudoOptions {
                     }
                 },
                 SudoArg::Argument(option, value) => match option.as_str() {
+                    "-C" | "--close-from" => {
+                        // the IO streams can not be closed
+                        match value.parse() {
+                            Ok(fd) if fd > 2 => options.close_from = Some(fd),
+                            _ => Err(xlat!(
+                                "the argument to '{option}' must be a number greater than or equal to 3",
+                                option = option
+                            ))?,
+                        }
+                    }
+                    "-T" | "--command-timeout" => {
+                        match parse_duration_spec(&value, DurationUnit::Seconds) {
+                            Some(timeout) if timeout.as_secs() > 0 => {
+                                options.command_timeout = Some(timeout.as_secs())
+                            }
+                            _ => Err(xlat!(
+                                "the argument to '{option}' must be a duration of at least one second, such as 30 or 1h30m",
+                                option = option
+                            ))?,
+                        }
+                    }
                     "-D" | "--chdir" => {
                         options.chdir = Some(SudoPath::from_cli_string(value));
                     }
+                    "-R" | "--chroot" => {
+                        let root = SudoPath::from_cli_string(value);
+                        if !root.is_absolute() {
+                            Err(xlat!(
+                                "the argument to '{option}' must be an absolute path",
+                                option = option
+                            ))?;
+                        }
+                        options.chroot = Some(root);
+                    }
                     "-E" | "--preserve-env" => {
-                        options
-                            .env_var_list
-                            .extend(value.split(',').filter_map(|var| {
-                                std::env::var(var)
-                                    .ok()
-                                    .map(|value| (var.to_string(), value))
-                            }));
+                        let names = value.split(',').filter(|name| !name.is_empty());
+                        for name in names {
+                            // an earlier VAR=value for the same variable is overridden
+                            options.env_var_list.retain(|(key, _)| key != name);
+                            options.preserve_env.push(name.to_string());
+                        }
                     }
                     "-g" | "--group" => {
                         options.group = Some(SudoString::from_cli_string(value));
                     }
+                    "-h" | "--host" => {
+                        options.host = Some(SudoString::from_cli_string(value));
+                    }
                     "-p" | "--prompt" => {
                         options.prompt = Some(value);
                     }
@@ -830,10 +908,13 @@ fn ensure_is_absent(context: &str, thing: &dyn IsAbsent, name: &str) -> Result<(
 fn reject_all(context: &str, mut opts: SudoOptions) -> Result<(), String> {
     // actions that don't need env var arguments need to ignore them for compatibility with og-sudo.
     opts.env_var_list.clear();
+    opts.preserve_env.clear();
+    opts.preserve_environment = false;
 
     macro_rules! check_options {
         ($($field:ident $(= $name:expr)?,)*) => {{
-            let SudoOptions { $($field),* } = opts;
+            // -H only affects the environment of the command, so it is ignored otherwise
+            let SudoOptions { $($field,)* set_home: _ } = opts;
 
             $(
                 let name = check_options!(@name $field $($name)?);
@@ -860,9 +941,14 @@ fn reject_all(context: &str, mut opts: SudoOptions) -> Result<(), String> {
         bell,
         background,
         chdir,
+        chroot,
+        close_from,
+        command_timeout,
+        dry_run,
         edit,
         group,
         help,
+        host,
         list,
         login,
         non_interactive,
@@ -877,5 +963,7 @@ fn reject_all(context: &str, mut opts: SudoOptions) -> Result<(), String> {
         version,
         positional_args = xlat!("command"),
         env_var_list = xlat!("environment variable"),
+        preserve_env = "--preserve-env",
+        preserve_environment = "--preserve-env",
     )
 }
@@ -53,6 +53,30 @@ impl SudoAction {
     }
 }
 
+/// Passing '-E' without a variable asks to preserve the entire environment
+#[test]
+fn short_preserve_env() {
+    for flag in ["-E", "--preserve-env"] {
+        let cmd = SudoOptions::try_parse_from(["sudo", flag]).unwrap();
+        assert!(cmd.preserve_environment);
+        assert!(cmd.preserve_env.is_empty());
+    }
+}
+
+/// '-H' is only recorded for running a command
+#[test]
+fn set_home() {
+    for flag in ["-H", "--set-home"] {
+        let cmd = SudoAction::try_parse_from(["sudo", flag, "true"]).unwrap();
+        assert!(cmd.try_into_run().ok().unwrap().set_home);
+    }
+
+    // and ignored otherwise
+    for action in ["-v", "-l", "-k", "-K"] {
+        assert!(SudoAction::try_parse_from(["sudo", "-H", action]).is_ok());
+    }
+}
+
 /// Passing '-E' with a variable fails
 #[test]
 fn short_preserve_env_with_var_fails() {
@@ -64,27 +88,19 @@ fn short_preserve_env_with_var_fails() {
     }
 }
 
-/// Passing '--preserve-env' with an argument fills 'preserve_env', 'short_preserve_env' stays 'false'
+/// Passing '--preserve-env' with an argument fills 'preserve_env'
 #[test]
 fn preserve_env_with_var() {
     let cmd = SudoOptions::try_parse_from(["sudo", "--preserve-env=HOME"]).unwrap();
-    assert_eq!(
-        [("HOME".to_string(), std::env::var("HOME").unwrap())],
-        cmd.env_var_list.as_slice()
-    );
+    assert_eq!(["HOME"], cmd.preserve_env.as_slice());
+    assert!(cmd.env_var_list.is_empty());
 }
 
-/// Passing '--preserve-env' with several arguments fills 'preserve_env', 'short_preserve_env' stays 'false'
+/// Passing '--preserve-env' with several arguments fills 'preserve_env'
 #[test]
 fn preserve_env_with_several_vars() {
     let cmd = SudoOptions::try_parse_from(["sudo", "--preserve-env=PATH,HOME"]).unwrap();
-    assert_eq!(
-        [
-            ("PATH".to_string(), std::env::var("PATH").unwrap()),
-            ("HOME".to_string(), std::env::var("HOME").unwrap()),
-        ],
-        cmd.env_var_list.as_slice()
-    );
+    assert_eq!(["PATH", "HOME"], cmd.preserve_env.as_slice());
 }
 
 #[test]
@@ -96,25 +112,27 @@ fn preserve_env_boolean_and_list() {
 
     for args in argss {
         let cmd = SudoOptions::try_parse_from(args).unwrap();
-        assert_eq!(
-            [("HOME".to_string(), std::env::var("HOME").unwrap())],
-            cmd.env_var_list.as_slice()
-        );
+        assert_eq!(["HOME"], cmd.preserve_env.as_slice());
     }
 }
 
+/// A variable that is listed after it was set on the command line is taken from the environment
+#[test]
+fn preserve_env_overrides_earlier_assignment() {
+    let cmd =
+        SudoOptions::try_parse_from(["sudo", "FOO=bar", "BAZ=qux", "--preserve-env=FOO"]).unwrap();
+    assert_eq!(["FOO"], cmd.preserve_env.as_slice());
+    assert_eq!(
+        [("BAZ".to_string(), "qux".to_string())],
+        cmd.env_var_list.as_slice()
+    );
+}
+
 #[test]
 fn preserve_env_repeated() {
     let cmd = SudoOptions::try_parse_from(["sudo", "--preserve-env=PATH", "--preserve-env=HOME"])
         .unwrap();
-    assert_eq!(
-        ["PATH", "HOME"],
-        cmd.env_var_list
-            .into_iter()
-            .map(|x| x.0)
-            .collect::<Vec<_>>()
-            .as_slice()
-    );
+    assert_eq!(["PATH", "HOME"], cmd.preserve_env.as_slice());
 }
 
 /// Catch env variable that is given without hyphens in 'VAR=value' form in env_var_list.
@@ -297,6 +315,19 @@ fn shell() {
     assert!(cmd.shell);
 }
 
+#[test]
+fn dry_run() {
+    let cmd = SudoAction::try_parse_from(["sudo", "--dry-run", "true"])
+        .unwrap()
+        .try_into_run()
+        .ok()
+        .unwrap();
+    assert!(cmd.dry_run);
+
+    assert!(SudoAction::try_parse_from(["sudo", "--dry-run", "-l"]).is_err());
+    assert!(SudoAction::try_parse_from(["sudo", "--dry-run", "-v"]).is_err());
+}
+
 #[test]
 fn directory() {
     let cmd = SudoOptions::try_parse_from(["sudo", "-D/some/path"]).unwrap();
@@ -309,6 +340,72 @@ fn directory() {
     assert_eq!(cmd.chdir, Some(SudoPath::from("/some/path")));
 }
 
+#[test]
+fn chroot() {
+    let cmd = SudoOptions::try_parse_from(["sudo", "-R/srv/jail"]).unwrap();
+    assert_eq!(cmd.chroot, Some(SudoPath::from("/srv/jail")));
+
+    let cmd = SudoOptions::try_parse_from(["sudo", "--chroot", "/srv/jail"]).unwrap();
+    assert_eq!(cmd.chroot, Some(SudoPath::from("/srv/jail")));
+
+    assert!(SudoOptions::try_parse_from(["sudo", "-R", "jail"]).is_err());
+    assert!(SudoAction::try_parse_from(["sudo", "-R", "/srv/jail", "-l"]).is_err());
+}
+
+#[test]
+fn command_timeout() {
+    let cmd = SudoOptions::try_parse_from(["sudo", "-T30"]).unwrap();
+    assert_eq!(cmd.command_timeout, Some(30));
+
+    let cmd = SudoOptions::try_parse_from(["sudo", "--command-timeout=30"]).unwrap();
+    assert_eq!(cmd.command_timeout, Some(30));
+
+    let cmd = SudoOptions::try_parse_from(["sudo", "-T", "1h30m"]).unwrap();
+    assert_eq!(cmd.command_timeout, Some(5400));
+
+    assert!(SudoOptions::try_parse_from(["sudo", "-T", "0"]).is_err());
+    assert!(SudoOptions::try_parse_from(["sudo", "-T", "0.5"]).is_err());
+    assert!(SudoOptions::try_parse_from(["sudo", "-T", "1x"]).is_err());
+    assert!(SudoAction::try_parse_from(["sudo", "-T", "30", "-v"]).is_err());
+}
+
+#[test]
+fn close_from() {
+    let cmd = SudoOptions::try_parse_from(["sudo", "-C5"]).unwrap();
+    assert_eq!(cmd.close_from, Some(5));
+
+    let cmd = SudoOptions::try_parse_from(["sudo", "--close-from", "3"]).unwrap();
+    assert_eq!(cmd.close_from, Some(3));
+
+    let cmd = SudoOptions::try_parse_from(["sudo", "--close-from=42"]).unwrap();
+    assert_eq!(cmd.close_from, Some(42));
+
+    // the IO streams are always left open
+    for fd in ["-1", "0", "2", "three", ""] {
+        assert!(SudoOptions::try_parse_from(["sudo", "-C", fd]).is_err());
+    }
+
+    // it only applies to running a command
+    assert!(SudoAction::try_parse_from(["sudo", "-C5", "-l"]).is_err());
+    assert!(SudoAction::try_parse_from(["sudo", "-C5", "true"]).is_ok_and(|cmd| cmd.is_run()));
+}
+
+#[test]
+fn prompt() {
+    let cmd = SudoOptions::try_parse_from(["sudo", "-p", "%p@%H: "]).unwrap();
+    assert_eq!(cmd.prompt.as_deref(), Some("%p@%H: "));
+
+    let cmd = SudoOptions::try_parse_from(["sudo", "--prompt=[sudo] %u"]).unwrap();
+    assert_eq!(cmd.prompt.as_deref(), Some("[sudo] %u"));
+
+    // an empty prompt is kept, since it disables the prompt
+    let cmd = SudoOptions::try_parse_from(["sudo", "--prompt", ""]).unwrap();
+    assert_eq!(cmd.prompt.as_deref(), Some(""));
+
+    let cmd = SudoOptions::try_parse_from(["sudo", "true"]).unwrap();
+    assert_eq!(cmd.prompt, None);
+}
+
 #[test]
 fn group() {
     let cmd = SudoOptions::try_parse_from(["sudo", "-grustaceans"]).unwrap();
@@ -321,6 +418,19 @@ fn group() {
     assert_eq!(cmd.group.as_deref(), Some("rustaceans"));
 }
 
+#[test]
+fn host() {
+    let cmd = SudoOptions::try_parse_from(["sudo", "-hserver"]).unwrap();
+    assert_eq!(cmd.host.as_deref(), Some("server"));
+
+    let cmd = SudoOptions::try_parse_from(["sudo", "-h", "server"]).unwrap();
+    assert_eq!(cmd.host.as_deref(), Some("server"));
+    assert!(!cmd.help);
+
+    let cmd = SudoOptions::try_parse_from(["sudo", "--host=server"]).unwrap();
+    assert_eq!(cmd.host.as_deref(), Some("server"));
+}
+
 #[test]
 fn other_user() {
     let cmd = SudoOptions::try_parse_from(["sudo", "-Uferris"]).unwrap();
@@ -415,6 +525,8 @@ fn list() {
         &["sudo", "-l", "-U", "ferris", "true"],
         &["sudo", "-l", "-u", "ferris", "true"],
         &["sudo", "-l", "-u", "ferris", "-U", "root", "true"],
+        &["sudo", "-l", "-h", "server"],
+        &["sudo", "-l", "-h", "server", "-U", "ferris", "true"],
     ];
 
     for args in valid {
@@ -425,6 +537,8 @@ fn list() {
     let invalid: &[&[_]] = &[
         &["sudo", "-l", "-u", "ferris"],
         &["sudo", "-l", "-u", "ferris", "-U", "root"],
+        &["sudo", "-h", "server", "true"],
+        &["sudo", "-v", "-h", "server"],
     ];
 
     for args in invalid {
@@ -1,18 +1,27 @@
 use std::{
     collections::{HashMap, HashSet},
     ffi::{OsStr, OsString},
+    io::Read,
     os::unix::prelude::OsStrExt,
+    path::Path,
 };
 
 use crate::common::{CommandAndArguments, Context, Error, context::LaunchType};
+use crate::log::auth_warn;
 use crate::sudoers::Restrictions;
-use crate::system::{PATH_MAX, audit::zoneinfo_path};
+use crate::system::{
+    PATH_MAX,
+    audit::{secure_open_sudoers, zoneinfo_path},
+};
 
 use super::wildcard_match::wildcard_match;
 
 // TODO: use _PATH_STDPATH from paths.h
 pub(crate) const PATH_DEFAULT: &str = "/usr/bin:/bin:/usr/sbin:/sbin";
 
+// TODO: use _PATH_MAILDIR from paths.h
+const MAIL_DIR: &str = "/var/mail";
+
 pub type Environment = HashMap<OsString, OsString>;
 
 /// obtain the system environment
@@ -62,16 +71,29 @@ fn add_extra_env(
     );
     environment.insert("SUDO_USER".into(), context.current_user.name.clone().into());
     environment.insert("SUDO_HOME".into(), context.current_user.home.clone().into());
+    if let Some(rule) = &cfg.rule {
+        environment.insert("SUDO_RULE".into(), rule.into());
+    }
     // The current SHELL variable should determine the shell to run when -s is passed, if none set use passwd entry
     environment
         .entry("SHELL".into())
         .or_insert_with(|| context.target_user.shell.clone().into());
     // HOME: Set to the home directory of the target user if -i or -H are specified, env_reset or always_set_home are
     // set in sudoers, or when the -s option is specified and set_home is set in sudoers.
-    // In sudo-rs env_reset is mandatory, so we always set HOME unless it's in the env_keep list.
+    // In sudo-rs env_reset is mandatory, so we always set HOME unless it's in the env_keep list
+    // or preserved with -E; -H and always_set_home take precedence over both.
+    if context.set_home || cfg.always_set_home {
+        environment.insert("HOME".into(), context.target_user.home.clone().into());
+    } else {
+        environment
+            .entry("HOME".into())
+            .or_insert_with(|| context.target_user.home.clone().into());
+    }
+    // MAIL: Set to the mail spool of the target user, unless it is in the env_keep list
+    // (which -i ignores, like it does for HOME).
     environment
-        .entry("HOME".into())
-        .or_insert_with(|| context.target_user.home.clone().into());
+        .entry("MAIL".into())
+        .or_insert_with(|| format!("{MAIL_DIR}/{user}", user = context.target_user.name).into());
 
     match (
         environment.get(OsStr::new("LOGNAME")),
@@ -175,27 +197,128 @@ fn should_keep(key: &OsStr, value: &OsStr, cfg: &Restrictions) -> bool {
         return false;
     }
 
+    // a variable in env_keep is preserved as-is, even if it is also in env_check
+    if in_table((key, value), cfg.env_keep) {
+        return true;
+    }
+
+    if !in_table((key, value), cfg.env_check) {
+        return false;
+    }
+
     if key == "TZ" {
-        return in_table((key, value), cfg.env_keep)
-            || (in_table((key, value), cfg.env_check) && is_safe_tz(value.as_bytes()));
+        is_safe_tz(value.as_bytes())
+    } else {
+        !value.as_bytes().iter().any(|c| *c == b'%' || *c == b'/')
+    }
+}
+
+fn is_listed(key: &OsStr, context: &Context) -> bool {
+    context.preserve_env.iter().any(|name| key == name.as_str())
+}
+
+/// Determine whether a variable that the user asked to preserve (using `--preserve-env=list`,
+/// or all of them using `--preserve-env`) may be kept; if the user is allowed to set
+/// environment variables, these are kept like variables in env_keep, unless they are in
+/// env_delete.
+fn should_preserve(key: &OsStr, value: &OsStr, context: &Context, cfg: &Restrictions) -> bool {
+    let listed = cfg.trust_environment && is_listed(key, context);
+    // -E keeps HOME, unless -H (or always_set_home) asks for the home directory of the target
+    // user; a login shell ignores -E altogether and starts from the environment of a fresh login
+    let all_preserved = context.preserve_environment
+        && context.launch != LaunchType::Login
+        && !((context.set_home || cfg.always_set_home) && key == "HOME");
+    if !listed && !all_preserved {
+        return false;
     }
 
-    if in_table((key, value), cfg.env_check) {
-        return !value.as_bytes().iter().any(|c| *c == b'%' || *c == b'/');
+    if value.as_bytes().starts_with("()".as_bytes()) {
+        return false;
+    }
+
+    if cfg.path.is_some() && key == "PATH" {
+        return false;
+    }
+
+    !in_table((key, value), cfg.env_delete)
+}
+
+/// Parse the contents of an env_file, which consists of lines of the form `VAR=value` or
+/// `export VAR=value`; the value may be enclosed in single or double quotes. Empty lines and
+/// lines starting with `#` are ignored; malformed lines are reported and skipped.
+fn parse_env_file(path: &Path, content: &[u8]) -> Vec<(OsString, OsString)> {
+    let mut result = Vec::new();
+    for (number, line) in content.split(|c| *c == b'\n').enumerate() {
+        let line = line.trim_ascii();
+        if line.is_empty() || line.starts_with(b"#") {
+            continue;
+        }
+
+        let line = line
+            .strip_prefix(b"export ")
+            .map_or(line, <[u8]>::trim_ascii_start);
+
+        let Some((key, value)) = line
+            .iter()
+            .position(|c| *c == b'=')
+            .map(|eq| (&line[..eq], &line[eq + 1..]))
+            .filter(|(key, _)| is_variable_name(key))
+        else {
+            auth_warn!(
+                "{}:{}: invalid environment variable assignment, ignoring it",
+                path.display(),
+                number + 1
+            );
+            continue;
+        };
+
+        let value = match value {
+            [b'"', inner @ .., b'"'] | [b'\'', inner @ .., b'\''] => inner,
+            _ => value,
+        };
+
+        result.push((
+            OsStr::from_bytes(key).to_owned(),
+            OsStr::from_bytes(value).to_owned(),
+        ));
     }
 
-    in_table((key, value), cfg.env_keep)
+    result
+}
+
+fn is_variable_name(name: &[u8]) -> bool {
+    matches!(name.first(), Some(c) if !c.is_ascii_digit())
+        && name.iter().all(|c| c.is_ascii_alphanumeric() || *c == b'_')
+}
+
+/// Read the variables in the env_file; it is part of the security policy, so it gets the
+/// same checks as the sudoers file itself.
+fn read_env_file(path: &Path) -> Vec<(OsString, OsString)> {
+    let mut content = Vec::new();
+    match secure_open_sudoers(path).and_then(|mut file| file.read_to_end(&mut content)) {
+        Ok(_) => parse_env_file(path, &content),
+        Err(err) => {
+            auth_warn!("unable to read {}: {err}", path.display());
+            Vec::new()
+        }
+    }
 }
 
 /// Construct the final environment from the current one and a sudo context
 /// see <https://github.com/sudo-project/sudo/blob/main/plugins/sudoers/env.c> for the original implementation
 /// see <https://www.sudo.ws/docs/man/sudoers.man/#Command_environment> for the original documentation
 ///
-/// The HOME, SHELL, LOGNAME and USER environment variables are initialized based on the target user
+/// The HOME, MAIL, SHELL, LOGNAME and USER environment variables are initialized based on the target user
 /// and the SUDO_* variables are set based on the invoking user.
 ///
 /// Additional variables, such as DISPLAY, PATH and TERM, are preserved from the invoking user's
-/// environment if permitted by the env_check, or env_keep options
+/// environment if permitted by the env_check, or env_keep options, or if the user asked to
+/// preserve them with `--preserve-env` and may set environment variables (unless they match the
+/// env_delete option)
+///
+/// Variables from the env_file are added unless they are already set by PAM or preserved from the
+/// invoking user's environment, and take precedence over the default values set by sudo;
+/// variables matching the env_delete list are not taken from it.
 ///
 /// If the PATH and TERM variables are not preserved from the user's environment, they will be set to default value
 ///
@@ -215,18 +338,40 @@ pub fn get_target_environment(
     let mut environment: HashMap<_, _> = additional_env.into_iter().collect();
 
     let login_vars: &[_] = if context.launch == LaunchType::Login {
-        &["HOME", "SHELL", "USER", "LOGNAME"].map(OsStr::new)
+        &["HOME", "MAIL", "SHELL", "USER", "LOGNAME"].map(OsStr::new)
     } else {
         &[]
     };
 
+    // a variable listed with `--preserve-env` that may not be kept is an error, just like a
+    // variable that is set on the command line
+    let mut rejected_vars = Vec::new();
     environment.extend(current_env.into_iter().filter(|(key, value)| {
-        !login_vars.contains(&key.as_os_str()) && should_keep(key, value, settings)
+        if login_vars.contains(&key.as_os_str()) {
+            false
+        } else if should_keep(key, value, settings)
+            || should_preserve(key, value, context, settings)
+        {
+            true
+        } else {
+            if is_listed(key, context) && !settings.trust_environment {
+                rejected_vars.push(key.to_string_lossy().into_owned());
+            }
+            false
+        }
     }));
+    rejected_vars.sort();
+
+    if let Some(path) = settings.env_file {
+        for (key, value) in read_env_file(Path::new(path)) {
+            if !in_table((&key, &value), settings.env_delete) {
+                environment.entry(key).or_insert(value);
+            }
+        }
+    }
 
     add_extra_env(context, settings, sudo_ps1, &mut environment);
 
-    let mut rejected_vars = Vec::new();
     for (key, value) in user_override {
         if should_keep(OsStr::new(&key), OsStr::new(&value), settings) {
             environment.insert(key.into(), value.into());
@@ -255,8 +400,8 @@ where
 
 #[cfg(test)]
 mod tests {
-    use super::{is_safe_tz, should_keep, zoneinfo_path};
-    use std::{collections::HashSet, ffi::OsStr};
+    use super::{is_safe_tz, parse_env_file, should_keep, zoneinfo_path};
+    use std::{collections::HashSet, ffi::OsStr, path::Path};
 
     struct TestConfiguration {
         keep: HashSet<String>,
@@ -273,15 +418,26 @@ mod tests {
                     &crate::sudoers::Restrictions {
                         env_keep: &self.keep,
                         env_check: &self.check,
+                        env_delete: &HashSet::new(),
+                        env_file: None,
                         path: self.path.as_deref(),
                         chdir: crate::sudoers::DirChange::Strict(None),
+                        chroot: crate::sudoers::DirChange::Strict(None),
                         trust_environment: false,
                         use_pty: true,
                         umask: crate::exec::Umask::Preserve,
+                        closefrom_override: false,
+                        preserve_fds: Vec::new(),
+                        home_fallback: crate::exec::HomeFallback::Stay,
+                        always_set_home: false,
+                        command_line_max: None,
+                        command_timeout: None,
+                        user_command_timeouts: false,
                         #[cfg(feature = "apparmor")]
                         apparmor_profile: None,
                         noexec: false,
                         log: crate::sudoers::Logging::Auth,
+                        rule: None,
                     }
                 ),
                 expected,
@@ -306,7 +462,15 @@ mod tests {
         config.check_should_keep("TZ", "Europe/Amsterdam", true);
         config.check_should_keep("TZ", "../Europe/Berlin", false);
         config.check_should_keep("MIES", "FOO/BAR", false);
-        config.check_should_keep("MIES", "FOO/BAR", false);
+        config.check_should_keep("MIES", "100%", false);
+        config.check_should_keep("AAP", "FOO/BAR", true);
+        config.check_should_keep("NOOT", "100%", true);
+
+        config.keep.insert("MIES".to_string());
+        config.check_should_keep("MIES", "FOO/BAR", true);
+        config.check_should_keep("MIES", "()=foo", false);
+        config.keep.insert("TZ".to_string());
+        config.check_should_keep("TZ", "../Europe/Berlin", true);
 
         config.keep.insert("PATH".to_string());
         config.check_should_keep("PATH", "FOO", false);
@@ -314,6 +478,27 @@ mod tests {
         config.check_should_keep("PATH", "FOO", true);
     }
 
+    #[test]
+    fn test_locale_filtering() {
+        let mut config = TestConfiguration {
+            keep: HashSet::new(),
+            check: HashSet::from(["LANG".to_string(), "LC_*".to_string()]),
+            path: None,
+        };
+
+        config.check_should_keep("LANG", "en_US.UTF-8", true);
+        config.check_should_keep("LC_CTYPE", "C.UTF-8", true);
+        config.check_should_keep("LC_CTYPE", "/tmp/evil", false);
+        config.check_should_keep("LC_MESSAGES", "../../tmp/evil", false);
+        config.check_should_keep("LANGUAGE", "en_US", false);
+
+        config.check.remove("LC_*");
+        config.check_should_keep("LC_CTYPE", "C.UTF-8", false);
+        config.keep.insert("LC_CTYPE".to_string());
+        config.check_should_keep("LC_CTYPE", "C.UTF-8", true);
+        config.check_should_keep("LC_NUMERIC", "C.UTF-8", false);
+    }
+
     #[allow(clippy::bool_assert_comparison)]
     #[test]
     fn test_tzinfo() {
@@ -338,4 +523,36 @@ mod tests {
             false
         );
     }
+
+    #[test]
+    fn test_parse_env_file() {
+        let content = b"\
+# a comment
+FOO=bar
+export QUOTED=\"hello world\"
+
+  SINGLE='it''s'
+EMPTY=
+no assignment here
+1INVALID=name
+SPACE IN_NAME=x
+EQUALS=a=b
+";
+        let parsed = parse_env_file(Path::new("/etc/sudo.env"), content)
+            .into_iter()
+            .map(|(key, value)| (key.into_string().unwrap(), value.into_string().unwrap()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            parsed,
+            [
+                ("FOO", "bar"),
+                ("QUOTED", "hello world"),
+                ("SINGLE", "it''s"),
+                ("EMPTY", ""),
+                ("EQUALS", "a=b"),
+            ]
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+        );
+    }
 }
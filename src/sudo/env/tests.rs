@@ -1,12 +1,15 @@
 use crate::common::resolve::CurrentUser;
-use crate::common::{CommandAndArguments, Context};
+use crate::common::{CommandAndArguments, Context, Error};
+use crate::defaults::{ListMode, SettingKind};
 use crate::sudo::{
     cli::{SudoAction, SudoRunOptions},
     env::environment::{Environment, get_target_environment},
 };
+use crate::sudoers::Restrictions;
 use crate::system::interface::{GroupId, UserId};
 use crate::system::{Group, Hostname, User};
 use std::collections::{HashMap, HashSet};
+use std::ffi::{OsStr, OsString};
 
 const TESTS: &str = "
 > env
@@ -16,6 +19,7 @@ const TESTS: &str = "
     LANG=en_US.UTF-8
     LANGUAGE=en_US.UTF-8
     LC_ALL=en_US.UTF-8
+    LD_LIBRARY_PATH=/tmp
     LS_COLORS=cd=40;33;01:*.jpg=01;35:*.mp3=00;36:
     PATH=/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin
     PWD=/home/test
@@ -36,6 +40,7 @@ const TESTS: &str = "
     SUDO_USER=test
     SUDO_HOME=/home/test
     HOME=/root
+    MAIL=/var/mail/root
     LOGNAME=root
     USER=root
     TERM=xterm
@@ -53,9 +58,108 @@ const TESTS: &str = "
     SUDO_USER=test
     SUDO_HOME=/home/test
     HOME=/home/test
+    MAIL=/var/mail/test
     LOGNAME=test
     USER=test
     TERM=xterm
+> sudo --preserve-env=FOO,LD_LIBRARY_PATH,UNSET env
+    FOO=BAR
+    HOSTNAME=test-ubuntu
+    LANG=en_US.UTF-8
+    LANGUAGE=en_US.UTF-8
+    LC_ALL=en_US.UTF-8
+    LS_COLORS=cd=40;33;01:*.jpg=01;35:*.mp3=00;36:
+    PATH=/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin
+    SHELL=/bin/bash
+    SUDO_COMMAND=/usr/bin/env
+    SUDO_GID=1000
+    SUDO_UID=1000
+    SUDO_USER=test
+    SUDO_HOME=/home/test
+    HOME=/root
+    MAIL=/var/mail/root
+    LOGNAME=root
+    USER=root
+    TERM=xterm
+> sudo -E env
+    FOO=BAR
+    HOSTNAME=test-ubuntu
+    LANG=en_US.UTF-8
+    LANGUAGE=en_US.UTF-8
+    LC_ALL=en_US.UTF-8
+    LS_COLORS=cd=40;33;01:*.jpg=01;35:*.mp3=00;36:
+    PATH=/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin
+    PWD=/home/test
+    SHLVL=0
+    _=/usr/bin/sudo
+    SHELL=/bin/bash
+    SUDO_COMMAND=/usr/bin/env
+    SUDO_GID=1000
+    SUDO_UID=1000
+    SUDO_USER=test
+    SUDO_HOME=/home/test
+    HOME=/home/test
+    MAIL=/var/mail/root
+    LOGNAME=root
+    USER=root
+    TERM=xterm
+> sudo -E -H env
+    FOO=BAR
+    HOSTNAME=test-ubuntu
+    LANG=en_US.UTF-8
+    LANGUAGE=en_US.UTF-8
+    LC_ALL=en_US.UTF-8
+    LS_COLORS=cd=40;33;01:*.jpg=01;35:*.mp3=00;36:
+    PATH=/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin
+    PWD=/home/test
+    SHLVL=0
+    _=/usr/bin/sudo
+    SHELL=/bin/bash
+    SUDO_COMMAND=/usr/bin/env
+    SUDO_GID=1000
+    SUDO_UID=1000
+    SUDO_USER=test
+    SUDO_HOME=/home/test
+    HOME=/root
+    MAIL=/var/mail/root
+    LOGNAME=root
+    USER=root
+    TERM=xterm
+";
+
+const ALWAYS_SET_HOME_TESTS: &str = "
+> env
+    FOO=BAR
+    HOME=/home/test
+    USER=test
+    TERM=xterm
+> sudo -E env
+    FOO=BAR
+    PATH=/usr/bin:/bin:/usr/sbin:/sbin
+    SHELL=/bin/bash
+    SUDO_COMMAND=/usr/bin/env
+    SUDO_GID=1000
+    SUDO_UID=1000
+    SUDO_USER=test
+    SUDO_HOME=/home/test
+    HOME=/root
+    MAIL=/var/mail/root
+    LOGNAME=test
+    USER=test
+    TERM=xterm
+> sudo env
+    PATH=/usr/bin:/bin:/usr/sbin:/sbin
+    SHELL=/bin/bash
+    SUDO_COMMAND=/usr/bin/env
+    SUDO_GID=1000
+    SUDO_UID=1000
+    SUDO_USER=test
+    SUDO_HOME=/home/test
+    HOME=/root
+    MAIL=/var/mail/root
+    LOGNAME=root
+    USER=root
+    TERM=xterm
 ";
 
 fn parse_env_commands(input: &str) -> Vec<(&str, Environment)> {
@@ -126,10 +230,16 @@ fn create_test_context(sudo_options: SudoRunOptions) -> Context {
         },
         launch: crate::common::context::LaunchType::Direct,
         chdir: sudo_options.chdir,
+        chroot: sudo_options.chroot,
+        command_timeout: None,
+        close_from: sudo_options.close_from,
         askpass: sudo_options.askpass,
         stdin: sudo_options.stdin,
         prompt: sudo_options.prompt,
         non_interactive: sudo_options.non_interactive,
+        preserve_environment: sudo_options.preserve_environment,
+        set_home: sudo_options.set_home,
+        preserve_env: sudo_options.preserve_env,
         use_session_records: false,
         bell: false,
         background: false,
@@ -145,39 +255,105 @@ fn environment_to_set(environment: Environment) -> HashSet<String> {
     )
 }
 
+fn test_restrictions(settings: &crate::defaults::Settings) -> Restrictions<'_> {
+    Restrictions {
+        env_keep: settings.env_keep(),
+        env_check: settings.env_check(),
+        env_delete: settings.env_delete(),
+        env_file: None,
+        path: settings.secure_path(),
+        use_pty: true,
+        chdir: crate::sudoers::DirChange::Strict(None),
+        chroot: crate::sudoers::DirChange::Strict(None),
+        trust_environment: false,
+        umask: crate::exec::Umask::Preserve,
+        closefrom_override: false,
+        preserve_fds: Vec::new(),
+        home_fallback: crate::exec::HomeFallback::Stay,
+        always_set_home: false,
+        command_line_max: None,
+        command_timeout: None,
+        user_command_timeouts: false,
+        #[cfg(feature = "apparmor")]
+        apparmor_profile: None,
+        noexec: false,
+        log: crate::sudoers::Logging::Auth,
+        rule: None,
+    }
+}
+
+fn get_test_environment(
+    cmd: &str,
+    initial_env: Environment,
+    cfg: &Restrictions,
+) -> Result<Environment, Error> {
+    let options = SudoAction::try_parse_from(cmd.split_whitespace())
+        .unwrap()
+        .try_into_run()
+        .ok()
+        .unwrap();
+    let context = create_test_context(options);
+    get_target_environment(initial_env, HashMap::new(), Vec::new(), &context, cfg)
+}
+
 #[test]
 fn test_environment_variable_filtering() {
-    let mut parts = parse_env_commands(TESTS);
+    let settings = crate::defaults::Settings::default();
+    // -E and --preserve-env=list require that the user may set environment variables
+    let cfg = Restrictions {
+        trust_environment: true,
+        ..test_restrictions(&settings)
+    };
+    check_environments(TESTS, &cfg);
+}
+
+#[test]
+fn test_always_set_home() {
+    let mut settings = crate::defaults::Settings::default();
+    // always_set_home also overrides a HOME that is in env_keep
+    let SettingKind::List(env_keep) = crate::defaults::set("env_keep").unwrap() else {
+        panic!()
+    };
+    env_keep(ListMode::Add, vec!["HOME".to_string()])(&mut settings);
+
+    let cfg = Restrictions {
+        trust_environment: true,
+        always_set_home: true,
+        ..test_restrictions(&settings)
+    };
+    check_environments(ALWAYS_SET_HOME_TESTS, &cfg);
+}
+
+#[test]
+fn test_preserve_env_list_without_setenv() {
+    let settings = crate::defaults::Settings::default();
+    let cfg = test_restrictions(&settings);
+    let initial_env = parse_env_commands(TESTS).remove(0).1;
+
+    // TERM is allowed by env_check, but FOO and LD_LIBRARY_PATH are not
+    let result = get_test_environment(
+        "sudo --preserve-env=TERM,FOO,LD_LIBRARY_PATH,UNSET env",
+        initial_env.clone(),
+        &cfg,
+    );
+    assert!(matches!(
+        result,
+        Err(Error::EnvironmentVar(vars)) if vars == ["FOO", "LD_LIBRARY_PATH"]
+    ));
+
+    let result = get_test_environment("sudo --preserve-env=TERM env", initial_env, &cfg);
+    assert_eq!(
+        result.unwrap().get(OsStr::new("TERM")),
+        Some(&OsString::from("xterm"))
+    );
+}
+
+fn check_environments(tests: &str, cfg: &Restrictions) {
+    let mut parts = parse_env_commands(tests);
     let initial_env = parts.remove(0).1;
 
     for (cmd, expected_env) in parts {
-        let options = SudoAction::try_parse_from(cmd.split_whitespace())
-            .unwrap()
-            .try_into_run()
-            .ok()
-            .unwrap();
-        let settings = crate::defaults::Settings::default();
-        let context = create_test_context(options);
-        let resulting_env = get_target_environment(
-            initial_env.clone(),
-            HashMap::new(),
-            Vec::new(),
-            &context,
-            &crate::sudoers::Restrictions {
-                env_keep: settings.env_keep(),
-                env_check: settings.env_check(),
-                path: settings.secure_path(),
-                use_pty: true,
-                chdir: crate::sudoers::DirChange::Strict(None),
-                trust_environment: false,
-                umask: crate::exec::Umask::Preserve,
-                #[cfg(feature = "apparmor")]
-                apparmor_profile: None,
-                noexec: false,
-                log: crate::sudoers::Logging::Auth,
-            },
-        )
-        .unwrap();
+        let resulting_env = get_test_environment(cmd, initial_env.clone(), cfg).unwrap();
 
         let resulting_env = environment_to_set(resulting_env);
         let expected_env = environment_to_set(expected_env);
@@ -0,0 +1,129 @@
+//! Insults that are shown instead of the usual message when a password was entered incorrectly
+//! and the `insults` flag is set. These are the insult groups that original sudo ships with.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const CLASSIC: &[&str] = &[
+    "Wrong!  You cheating scum!",
+    "And you call yourself a Rocket Scientist!",
+    "No soap, honkie-lips.",
+    "Where did you learn to type?",
+    "Are you on drugs?",
+    "My pet ferret can type better than you!",
+    "You type like i drive.",
+    "Do you think like you type?",
+    "Your mind just hasn't been the same since the electro-shock, has it?",
+];
+
+const CSOPS: &[&str] = &[
+    "Maybe if you used more than just two fingers...",
+    "BOB says:  You seem to have forgotten your passwd, enter another!",
+    "stty: unknown mode: doofus",
+    "I can't hear you -- I'm using the scrambler.",
+    "The more you drive -- the dumber you get.",
+    "Listen, broccoli brains, I don't have time to listen to this trash.",
+    "I've seen penguins that can type better than that.",
+    "Have you considered trying to match wits with a rutabaga?",
+    "You speak an infinite deal of nothing",
+];
+
+const HAL: &[&str] = &[
+    "Just what do you think you're doing Dave?",
+    "It can only be attributed to human error.",
+    "That's something I cannot allow to happen.",
+    "My mind is going. I can feel it.",
+    "Sorry about this, I know it's a bit silly.",
+    "Take a stress pill and think things over.",
+    "This mission is too important for me to allow you to jeopardize it.",
+    "I feel much better now.",
+];
+
+const GOONS: &[&str] = &[
+    "You silly, twisted boy you.",
+    "He has fallen in the water!",
+    "We'll all be murdered in our beds!",
+    "You can't come in. Our tiger has got flu",
+    "I don't wish to know that.",
+    "What, what, what, what, what, what, what, what, what, what?",
+    "You can't get the wood, you know.",
+    "You'll starve!",
+    "... and it used to be so popular...",
+    "Pauses for audience applause, not a sausage",
+    "Hold it up to the light --- not a brain in sight!",
+    "Have a gorilla...",
+    "There must be cure for it!",
+    "There's a lot of it about, you know.",
+    "You do that again and see what happens...",
+    "Ying Tong Iddle I Po",
+    "Harm can come to a young lad like that!",
+    "And with that remarks folks, the case of the Crown vs yourself was proven.",
+    "Speak English you fool --- there are no subtitles in this scene.",
+    "You gotta go owwwww!",
+    "I have been called worse.",
+    "It's only your word against mine.",
+    "I think ... err ... I think ... I think I'll go home",
+];
+
+const GROUPS: &[&[&str]] = &[CLASSIC, CSOPS, HAL, GOONS];
+
+/// Picks insults from the built-in set. The choice only needs to look random to the user, so a
+/// small xorshift generator is used; its seed can be fixed to make the sequence predictable.
+pub(super) struct Insults {
+    state: u64,
+}
+
+impl Insults {
+    /// Seed the generator from the clock and the process id.
+    pub(super) fn new() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64);
+
+        Self::with_seed(nanos ^ (u64::from(std::process::id()) << 32))
+    }
+
+    pub(super) fn with_seed(seed: u64) -> Self {
+        // xorshift gets stuck on an all-zero state
+        Self { state: seed.max(1) }
+    }
+
+    pub(super) fn pick(&mut self) -> &'static str {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+
+        let total = GROUPS.iter().map(|group| group.len()).sum::<usize>();
+        let mut index = (self.state % total as u64) as usize;
+        for group in GROUPS {
+            if let Some(insult) = group.get(index) {
+                return insult;
+            }
+            index -= group.len();
+        }
+
+        unreachable!()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn same_seed_gives_same_insults() {
+        let mut first = Insults::with_seed(42);
+        let mut second = Insults::with_seed(42);
+        for _ in 0..10 {
+            assert_eq!(first.pick(), second.pick());
+        }
+    }
+
+    #[test]
+    fn insults_come_from_every_group() {
+        let mut insults = Insults::with_seed(0);
+        let seen = (0..1000).map(|_| insults.pick()).collect::<Vec<_>>();
+        for group in GROUPS {
+            assert!(group.iter().any(|insult| seen.contains(insult)));
+        }
+    }
+}
@@ -0,0 +1,84 @@
+//! The lecture that is shown to a user before sudo asks for their password.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::common::resolve::CurrentUser;
+use crate::log::{auth_warn, dev_info};
+use crate::pam::PamContext;
+use crate::sudoers::Lecture;
+use crate::system::audit;
+
+/// Unlike session records, the fact that a user was lectured should survive a reboot.
+const LECTURED_PATH: &str = if cfg!(target_os = "freebsd") {
+    "/var/db/sudo-rs/lectured"
+} else {
+    "/var/lib/sudo-rs/lectured"
+};
+
+fn lectured_file(user: &CurrentUser, dir: Option<&Path>) -> PathBuf {
+    dir.unwrap_or(Path::new(LECTURED_PATH))
+        .join(user.uid.to_string())
+}
+
+/// Show the lecture if the policy asks for it; a lecture file that can not be read is
+/// replaced by the standard lecture.
+pub(super) fn lecture(
+    pam: &PamContext,
+    user: &CurrentUser,
+    lecture: &Lecture,
+    status_dir: Option<&Path>,
+) {
+    let lecture_file = match lecture {
+        Lecture::Never => return,
+        Lecture::Once(_) if lectured_file(user, status_dir).exists() => return,
+        Lecture::Once(file) | Lecture::Always(file) => file.as_deref(),
+    };
+
+    // the lecture file may be in a legacy encoding, so it is shown as-is
+    let custom = lecture_file.and_then(|path| match fs::read(path) {
+        Ok(text) => Some(text),
+        Err(e) => {
+            dev_info!("could not read lecture file {}: {e}", path.display());
+            None
+        }
+    });
+
+    let standard = xlat!(
+        "
+We trust you have received the usual lecture from the local System
+Administrator. It usually boils down to these three things:
+
+    #1) Respect the privacy of others.
+    #2) Think before you type.
+    #3) With great power comes great responsibility.
+
+"
+    );
+    let text = custom.as_deref().unwrap_or(standard.as_bytes());
+
+    if let Err(e) = pam.display(text) {
+        dev_info!("could not display the lecture: {e}");
+    }
+}
+
+/// Remember that the user has seen the lecture, if it should only be shown once; if the
+/// configured `status_dir` can not be used safely, the default directory is used instead.
+pub(super) fn mark_lectured(user: &CurrentUser, lecture: &Lecture, status_dir: Option<&Path>) {
+    if let Lecture::Once(_) = lecture {
+        let result = audit::secure_open_cookie_file(lectured_file(user, status_dir)).or_else(|e| {
+            let Some(dir) = status_dir else {
+                return Err(e);
+            };
+            auth_warn!(
+                "cannot use lecture_status_dir {}: {e}; using {LECTURED_PATH} instead",
+                dir.display()
+            );
+            audit::secure_open_cookie_file(lectured_file(user, None))
+        });
+
+        if let Err(e) = result {
+            dev_info!("could not record that the user was lectured: {e}");
+        }
+    }
+}
@@ -20,6 +20,8 @@ mod edit;
 pub(crate) mod diagnostic;
 mod env;
 pub(crate) use env::environment::PATH_DEFAULT;
+mod insults;
+mod lecture;
 mod pam;
 mod pipeline;
 
@@ -62,6 +64,8 @@ pub(crate) fn candidate_sudoers_file() -> PathBuf {
 }
 
 fn sudo_process() -> Result<(), Error> {
+    crate::system::record_inherited_fds();
+
     crate::log::SudoLogger::new("sudo: ").into_global_logger();
 
     dev_info!("development logs are enabled");
@@ -94,19 +98,28 @@ fn sudo_process() -> Result<(), Error> {
             }
             SudoAction::RemoveTimestamp(_) => {
                 let user = CurrentUser::resolve()?;
-                let mut record_file = SessionRecordFile::open_for_user(&user, Duration::default())?;
+                let dir = pipeline::timestamp_dir(&user);
+                let mut record_file =
+                    SessionRecordFile::open_for_user(&user, Duration::default(), dir.as_deref())?;
                 record_file.reset()?;
                 Ok(())
             }
             SudoAction::ResetTimestamp(_) => {
                 let user = CurrentUser::resolve()?;
+                let dir = pipeline::timestamp_dir(&user);
                 let process = Process::new();
-                for record_scope in [RecordScope::for_tty, RecordScope::for_ppid] {
-                    if let Some(scope) = record_scope(&process) {
-                        let mut record_file =
-                            SessionRecordFile::open_for_user(&user, Duration::default())?;
-                        record_file.disable(scope)?;
-                    }
+                let scopes = [
+                    RecordScope::for_tty(&process),
+                    RecordScope::for_ppid(&process),
+                    Some(RecordScope::Global),
+                ];
+                for scope in scopes.into_iter().flatten() {
+                    let mut record_file = SessionRecordFile::open_for_user(
+                        &user,
+                        Duration::default(),
+                        dir.as_deref(),
+                    )?;
+                    record_file.disable(scope)?;
                 }
                 Ok(())
             }
@@ -137,6 +150,11 @@ fn self_check() -> Result<(), Error> {
             return Err(Error::SelfCheckNoNewPrivs);
         }
 
+        #[cfg(target_os = "linux")]
+        if crate::system::audit::root_unmapped_in_user_namespace()? {
+            return Err(Error::SelfCheckUserNamespace);
+        }
+
         return Err(Error::SelfCheckSetuid);
     }
 
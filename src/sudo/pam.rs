@@ -1,6 +1,8 @@
 use std::ffi::OsString;
+use std::path::PathBuf;
 use std::time::Duration;
 
+use super::insults::Insults;
 use crate::common::context::LaunchType;
 use crate::common::error::Error;
 use crate::log::{dev_info, user_warn};
@@ -10,12 +12,14 @@ use crate::system::term::current_tty_name;
 pub(super) struct InitPamArgs<'a> {
     pub(super) launch: LaunchType,
     pub(super) use_askpass: bool,
+    pub(super) askpass: Option<PathBuf>,
     pub(super) use_stdin: bool,
     pub(super) bell: bool,
     pub(super) non_interactive: bool,
     pub(super) password_feedback: bool,
     pub(super) password_timeout: Option<Duration>,
     pub(super) auth_prompt: Option<String>,
+    pub(super) auth_prompt_override: bool,
     pub(super) auth_user: &'a str,
     pub(super) requesting_user: &'a str,
     pub(super) target_user: &'a str,
@@ -26,12 +30,14 @@ pub(super) fn init_pam(
     InitPamArgs {
         launch,
         use_askpass,
+        askpass,
         use_stdin,
         bell,
         non_interactive,
         password_feedback,
         password_timeout,
         auth_prompt,
+        auth_prompt_override,
         auth_user,
         requesting_user,
         target_user,
@@ -46,6 +52,7 @@ pub(super) fn init_pam(
         "sudo",
         service_name,
         use_askpass,
+        askpass,
         use_stdin,
         bell,
         non_interactive,
@@ -83,6 +90,7 @@ pub(super) fn init_pam(
                 }
             }
             pam.set_auth_prompt(Some(final_prompt));
+            pam.mark_auth_prompt_override(auth_prompt_override);
         }
     }
 
@@ -99,6 +107,7 @@ pub(super) fn attempt_authenticate(
     auth_user: &str,
     non_interactive: bool,
     max_tries: u16,
+    mut insults: Option<Insults>,
 ) -> Result<(), Error> {
     // Reject zero upfront so we don't ask for a password once when max_tries is 0.
     if max_tries == 0 {
@@ -123,6 +132,8 @@ pub(super) fn attempt_authenticate(
                     return Err(Error::MaxAuthAttempts(current_try));
                 } else if non_interactive {
                     return Err(Error::InteractionRequired);
+                } else if let Some(insults) = &mut insults {
+                    eprintln_ignore_io_error!("{}", insults.pick());
                 } else {
                     user_warn!("Authentication failed, try again.");
                 }
@@ -1,21 +1,25 @@
+use std::env;
 use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use super::cli::{SudoRunOptions, SudoValidateOptions};
 use super::diagnostic;
+use super::insults::Insults;
+use super::lecture;
 use crate::common::resolve::{AuthUser, CurrentUser};
 use crate::common::{Context, Error};
-use crate::log::{auth_info, auth_warn};
+use crate::log::{auth_info, auth_warn, dev_info};
 use crate::pam::PamContext;
 use crate::sudo::env::environment;
 use crate::sudo::pam::{InitPamArgs, attempt_authenticate, init_pam, pre_exec};
 use crate::sudoers::{
-    AuthenticatingUser, Authentication, AuthenticationScope, Authorization, Judgement, Logging,
-    Sudoers,
+    AuthenticatingUser, Authentication, AuthenticationScope, Authorization, DenialReason,
+    Judgement, Logging, Span, Sudoers,
 };
 use crate::system::term::current_tty_name;
 use crate::system::timestamp::{RecordScope, SessionRecordFile, TouchResult};
-use crate::system::{Process, escape_os_str_lossy};
+use crate::system::{Hostname, Process, escape_os_str_lossy};
 
 mod list;
 pub(super) use list::run_list;
@@ -23,6 +27,8 @@ pub(super) use list::run_list;
 mod edit;
 pub(super) use edit::run_edit;
 
+mod dry_run;
+
 fn read_sudoers() -> Result<Sudoers, Error> {
     let sudoers_path = &super::candidate_sudoers_file();
 
@@ -45,21 +51,64 @@ fn read_sudoers() -> Result<Sudoers, Error> {
         }
     })?;
 
+    let unknown_settings = reject_unknown_settings(sudoers_path, &syntax_errors);
+
     for crate::sudoers::Error {
         source,
         location,
         message,
+        ..
     } in syntax_errors
     {
         let path = source.as_deref().unwrap_or(sudoers_path);
+        // stderr may not be seen by anyone (e.g. when running non-interactively)
+        if let Some(Span {
+            start: (line, col), ..
+        }) = location
+        {
+            auth_warn!("{}:{line}:{col}: {message}", path.display());
+        } else {
+            auth_warn!("{}: {message}", path.display());
+        }
         diagnostic::diagnostic!("{message}", path @ location);
     }
 
+    unknown_settings?;
+
     Ok(sudoers)
 }
 
+/// With the strict-defaults feature, a sudoers file that contains settings which sudo-rs does
+/// not recognize is not used at all.
+fn reject_unknown_settings(
+    sudoers_path: &Path,
+    syntax_errors: &[crate::sudoers::Error],
+) -> Result<(), Error> {
+    if cfg!(feature = "strict-defaults")
+        && syntax_errors
+            .iter()
+            .any(crate::sudoers::Error::is_unknown_setting)
+    {
+        return Err(Error::Configuration(xlat!(
+            "refusing to run: {path} contains unknown settings",
+            path = sudoers_path.display()
+        )));
+    }
+
+    Ok(())
+}
+
+/// The directory with session records for `sudo -k` and `sudo -K`, which otherwise do not
+/// consult the policy; if the sudoers file can not be read, the default directory is used.
+pub(super) fn timestamp_dir(user: &CurrentUser) -> Option<PathBuf> {
+    let mut policy = read_sudoers().ok()?;
+    policy
+        .timestamp_dir(&Hostname::resolve(), &**user)
+        .map(PathBuf::from)
+}
+
 fn judge(mut policy: Sudoers, context: &Context) -> Result<Judgement, Error> {
-    Ok(policy.check(
+    let judgement = policy.check(
         &*context.current_user,
         &context.hostname,
         crate::sudoers::Request {
@@ -68,19 +117,40 @@ fn judge(mut policy: Sudoers, context: &Context) -> Result<Judgement, Error> {
             command: &context.command.command,
             arguments: &context.command.arguments,
         },
-    ))
+    );
+
+    // the log file can depend on the request (e.g. 'Defaults!cmd'), so it is only known now
+    if let Some(log_file) = judgement.log_file() {
+        crate::log::add_log_file(
+            log_file.path,
+            log_file.year,
+            log_file.host.then_some(&context.hostname),
+        );
+    }
+
+    Ok(judgement)
 }
 
 pub fn run(mut cmd_opts: SudoRunOptions) -> Result<(), Error> {
     let mut policy = read_sudoers()?;
 
     let user_requested_env_vars = std::mem::take(&mut cmd_opts.env_var_list);
+    let dry_run = cmd_opts.dry_run;
 
     let context = Context::from_run_opts(cmd_opts, &mut policy)?;
 
     let policy = judge(policy, &context)?;
 
     let Authorization::Allowed(auth, controls) = policy.authorization() else {
+        match policy.denial_reason() {
+            Some(DenialReason::CommandForbidden) => auth_warn!(
+                "{} : command explicitly forbidden ; COMMAND={}",
+                &context.current_user.name,
+                &context.command
+            ),
+            Some(reason) => dev_info!("request denied: {reason:?}"),
+            None => {}
+        }
         return Err(Error::Authorization(context.current_user.name.to_string()));
     };
 
@@ -115,6 +185,12 @@ pub fn run(mut cmd_opts: SudoRunOptions) -> Result<(), Error> {
 
     let options = context.try_as_run_options(&controls)?;
 
+    if dry_run {
+        dry_run::report(&options, &target_env);
+        pam_context.close_session();
+        return Ok(());
+    }
+
     // Log after try_as_run_options to avoid logging if the command is not resolved
     log_command_execution(controls.log, &context);
 
@@ -134,7 +210,12 @@ pub fn run_validate(cmd_opts: SudoValidateOptions) -> Result<(), Error> {
 
     match policy.check_validate_permission(&*context.current_user, &context.hostname) {
         Authorization::Forbidden => {
-            return Err(Error::Authorization(context.current_user.name.to_string()));
+            return Err(Error::NotAllowed {
+                username: context.current_user.name.clone(),
+                command: "sudo".into(),
+                hostname: context.hostname.clone(),
+                other_user: None,
+            });
         }
         Authorization::Allowed(auth, ()) => {
             auth_and_update_record_file(&context, auth)?;
@@ -153,8 +234,14 @@ fn auth_and_update_record_file(
         password_timeout,
         ref credential,
         pwfeedback,
+        insults,
+        passprompt_override,
         noninteractive_auth,
         scope,
+        lecture,
+        lecture_status_dir,
+        timestamp_dir,
+        askpass,
     }: Authentication,
 ) -> Result<PamContext, Error> {
     let auth_user = match credential {
@@ -170,6 +257,7 @@ fn auth_and_update_record_file(
     let scope = match scope {
         AuthenticationScope::Tty => RecordScope::for_tty(&Process::new()),
         AuthenticationScope::PPid => RecordScope::for_ppid(&Process::new()),
+        AuthenticationScope::Global => Some(RecordScope::Global),
     };
 
     let mut auth_status = determine_auth_status(
@@ -179,17 +267,21 @@ fn auth_and_update_record_file(
         &context.current_user,
         &auth_user,
         prior_validity,
+        timestamp_dir.as_deref(),
     );
 
     let mut pam_context = init_pam(InitPamArgs {
         launch: context.launch,
         use_askpass: context.askpass,
+        // the helper program named by the user takes precedence over the configured one
+        askpass: env::var_os("SUDO_ASKPASS").map(PathBuf::from).or(askpass),
         use_stdin: context.stdin,
         bell: context.bell,
         non_interactive: context.non_interactive,
         password_feedback: pwfeedback,
         password_timeout,
         auth_prompt: context.prompt.clone(),
+        auth_prompt_override: passprompt_override,
         auth_user: &auth_user.name,
         requesting_user: &context.current_user.name,
         target_user: &context.target_user.name,
@@ -200,12 +292,24 @@ fn auth_and_update_record_file(
             return Err(Error::InteractionRequired);
         }
 
+        lecture::lecture(
+            &pam_context,
+            &context.current_user,
+            &lecture,
+            lecture_status_dir.as_deref(),
+        );
         attempt_authenticate(
             &mut pam_context,
             &auth_user.name,
             context.non_interactive,
             allowed_attempts,
+            insults.then(Insults::new),
         )?;
+        lecture::mark_lectured(
+            &context.current_user,
+            &lecture,
+            lecture_status_dir.as_deref(),
+        );
         if let (Some(record_file), Some(scope)) = (&mut auth_status.record_file, scope) {
             match record_file.create(scope, &auth_user) {
                 Ok(_) => (),
@@ -230,11 +334,12 @@ fn determine_auth_status(
     current_user: &CurrentUser,
     auth_user: &AuthUser,
     prior_validity: Duration,
+    timestamp_dir: Option<&Path>,
 ) -> AuthStatus {
     if !must_policy_authenticate {
         AuthStatus::new(false, None)
     } else if let (true, Some(record_for)) = (use_session_records, record_for) {
-        match SessionRecordFile::open_for_user(current_user, prior_validity) {
+        match SessionRecordFile::open_for_user(current_user, prior_validity, timestamp_dir) {
             Ok(mut sr) => {
                 match sr.touch(record_for, auth_user) {
                     // if a record was found and updated within the timeout, we do not need to authenticate
@@ -298,3 +403,23 @@ fn log_command_execution(log: Logging, context: &Context) {
         &context.command
     );
 }
+
+#[cfg(all(test, feature = "strict-defaults"))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn unknown_settings_are_refused() {
+        let accepts = |text: &str| {
+            let (_, errors) = Sudoers::read(text.as_bytes(), "/etc/fakesudoers").unwrap();
+            reject_unknown_settings(Path::new("/etc/fakesudoers"), &errors).is_ok()
+        };
+
+        assert!(accepts("Defaults passwd_tries=5\n"));
+        // other errors are only reported
+        assert!(accepts("user ALL=(ALL ALL\n"));
+        assert!(accepts("Defaults !passwd_tries\n"));
+        assert!(!accepts("Defaults pasword_tries=5\n"));
+        assert!(!accepts("Defaults !use_ptty\n"));
+    }
+}
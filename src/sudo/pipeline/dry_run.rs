@@ -0,0 +1,60 @@
+//! Show what `sudo --dry-run` would execute, without executing it.
+
+use std::ffi::OsStr;
+
+use crate::common::DisplayOsStr;
+use crate::exec::RunOptions;
+use crate::sudo::env::environment::Environment;
+
+/// This is only reached after the policy allowed the command and the user authenticated,
+/// so it shows nothing the user could not also learn by running the command. Values in the
+/// target environment can come from PAM or the env_file however, so only their names are shown.
+pub(super) fn report(options: &RunOptions, target_env: &Environment) {
+    let arguments = options
+        .arguments
+        .iter()
+        .map(|arg| DisplayOsStr(arg).to_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let user = options.user;
+    let group = options.group;
+    let groups = user
+        .groups
+        .iter()
+        .map(|gid| gid.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let directory = match &options.chdir {
+        Some(dir) => dir.display().to_string(),
+        None if options.is_login => user.home.display().to_string(),
+        None => "(current directory)".to_string(),
+    };
+
+    let mut names = target_env
+        .keys()
+        .map(|name| &**name)
+        .collect::<Vec<&OsStr>>();
+    names.sort();
+    let names = names
+        .into_iter()
+        .map(|name| DisplayOsStr(name).to_string())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    println_ignore_io_error!("Command: {}", options.command.display());
+    println_ignore_io_error!("Arguments: {arguments}");
+    println_ignore_io_error!("User: {} (uid {})", user.name, user.uid);
+    println_ignore_io_error!(
+        "Group: {} (gid {})",
+        group.name.as_deref().unwrap_or("?"),
+        group.gid
+    );
+    println_ignore_io_error!("Groups: {groups}");
+    if let Some(root) = &options.chroot {
+        println_ignore_io_error!("Root directory: {}", root.display());
+    }
+    println_ignore_io_error!("Directory: {directory}");
+    println_ignore_io_error!("Environment: {names}");
+}
@@ -36,7 +36,13 @@ pub(in crate::sudo) fn run_list(cmd_opts: SudoListOptions) -> Result<(), Error>
     }
 
     if let Some(original_command) = original_command {
-        check_sudo_command_perms(&original_command, context, &other_user, &mut sudoers)?;
+        check_sudo_command_perms(
+            &original_command,
+            context,
+            &other_user,
+            &mut sudoers,
+            verbose_list_mode,
+        )?;
     } else {
         let inspected_user = other_user.as_ref().unwrap_or(&context.current_user);
         let mut matching_entries = sudoers
@@ -111,6 +117,7 @@ fn check_sudo_command_perms(
     context: Context,
     other_user: &Option<User>,
     sudoers: &mut Sudoers,
+    verbose_list_mode: bool,
 ) -> Result<(), Error> {
     let user = other_user.as_ref().unwrap_or(&context.current_user);
 
@@ -138,14 +145,19 @@ fn check_sudo_command_perms(
             resolved_command.as_os_str()
         };
 
-        if context.command.arguments.is_empty() {
-            println_ignore_io_error!("{}", DisplayOsStr(command));
+        let command_line = if context.command.arguments.is_empty() {
+            DisplayOsStr(command).to_string()
         } else {
-            println_ignore_io_error!(
+            format!(
                 "{} {}",
                 DisplayOsStr(command),
                 DisplayOsStr(&context.command.arguments.join(OsStr::new(" "))),
-            );
+            )
+        };
+
+        match judgement.verbose_match(&context.target_user.name, &command_line) {
+            Some(entry) if verbose_list_mode => println_ignore_io_error!("{entry}"),
+            _ => println_ignore_io_error!("{command_line}"),
         }
     }
 
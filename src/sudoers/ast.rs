@@ -7,6 +7,9 @@ use crate::common::{
     HARDENED_ENUM_VALUE_4, HARDENED_ENUM_VALUE_5,
 };
 use crate::defaults;
+use std::fmt;
+use std::path::Path;
+use std::rc::Rc;
 
 /// The Sudoers file allows negating items with the exclamation mark.
 #[cfg_attr(test, derive(Debug, Eq))]
@@ -61,6 +64,7 @@ pub enum UserSpecifier {
     User(Identifier) = HARDENED_ENUM_VALUE_0,
     Group(Identifier) = HARDENED_ENUM_VALUE_1,
     NonunixGroup(Identifier) = HARDENED_ENUM_VALUE_2,
+    Netgroup(SudoString) = HARDENED_ENUM_VALUE_3,
 }
 
 /// Peer credentials specification for @socket directive
@@ -120,10 +124,12 @@ pub enum ExecControl {
 pub struct Tag {
     pub(super) authenticate: Authenticate,
     pub(super) cwd: Option<ChDir>,
+    pub(super) chroot: Option<ChDir>,
     pub(super) env: EnvironmentControl,
     pub(super) apparmor_profile: Option<String>,
     pub(super) noexec: ExecControl,
     pub(super) ignored: Vec<Span>,
+    pub(super) origin: Option<Origin>,
 }
 
 impl Tag {
@@ -141,10 +147,37 @@ type PairVec<A, B> = Vec<(A, Vec<B>)>;
 pub struct PermissionSpec {
     pub users: SpecList<UserSpecifier>,
     pub permissions: PairVec<SpecList<Hostname>, (Option<RunAs>, CommandSpec)>,
+    pub(super) origin: Origin,
+}
+
+/// The place where a rule was defined; the file is only known once the rule is added to
+/// the policy.
+#[derive(Clone, Default, PartialEq)]
+#[cfg_attr(test, derive(Debug, Eq))]
+pub struct Origin {
+    pub(super) file: Option<Rc<Path>>,
+    pub(super) line: usize,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{}:{}", file.display(), self.line),
+            None => write!(f, "line {}", self.line),
+        }
+    }
 }
 
 pub type Defs<T> = Vec<Def<T>>;
-pub struct Def<T>(pub String, pub SpecList<T>);
+pub struct Def<T>(pub String, pub SpecList<T>, pub DefOrigin);
+
+/// The place where an alias was defined: the span of its name, and the file it is in, which is
+/// only known once the definition is added to the policy.
+#[derive(Clone)]
+pub struct DefOrigin {
+    pub(super) file: Option<Rc<Path>>,
+    pub(super) span: Span,
+}
 
 /// AST object for directive specifications (aliases, arguments, etc)
 #[repr(u32)]
@@ -273,8 +306,8 @@ impl Parse for UserSpecifier {
                 // in this case we must fail 'hard', since input has been consumed
                 ctor(expect_nonterminal(stream)?)
             } else if stream.eat_char('+') {
-                // TODO Netgroups
-                unrecoverable!(stream, "netgroups are not supported yet");
+                let Username(name) = expect_nonterminal(stream)?;
+                UserSpecifier::Netgroup(name)
             } else {
                 // in this case we must fail 'softly', since no input has been consumed yet
                 UserSpecifier::User(try_nonterminal(stream)?)
@@ -364,9 +397,14 @@ impl Parse for MetaOrTag {
                 let path: ChDir = expect_nonterminal(stream)?;
                 Box::new(move |tag| tag.cwd = Some(path.clone()))
             }
+            "CHROOT" => {
+                expect_syntax('=', stream)?;
+                let path: ChDir = expect_nonterminal(stream)?;
+                Box::new(move |tag| tag.chroot = Some(path.clone()))
+            }
 
             // we do not support these, and that should make sudo-rs "fail safe"
-            spec @ ("INTERCEPT" | "CHROOT" | "TIMEOUT" | "NOTBEFORE" | "NOTAFTER") => {
+            spec @ ("INTERCEPT" | "TIMEOUT" | "NOTBEFORE" | "NOTAFTER") => {
                 unrecoverable!(
                     pos = start_pos,
                     stream,
@@ -520,6 +558,11 @@ impl Parse for Sudo {
             return parse_include(stream);
         }
 
+        let origin = Origin {
+            file: None,
+            line: stream.get_pos().0,
+        };
+
         // the existence of "#include" forces us to handle lines that start with #<ID> explicitly
         if stream.peek() == Some('#') {
             return if let Ok(ident) = try_nonterminal::<Identifier>(stream) {
@@ -534,7 +577,11 @@ impl Parse for Sudo {
                 };
                 // no need to check get_directive as no other directive starts with #
                 let permissions = expect_nonterminal(stream)?;
-                make(Sudo::Spec(PermissionSpec { users, permissions }))
+                make(Sudo::Spec(PermissionSpec {
+                    users,
+                    permissions,
+                    origin,
+                }))
             } else {
                 // the failed "try_nonterminal::<Identifier>" will have consumed the '#'
                 // the most ignominious part of sudoers: having to parse bits of comments
@@ -550,7 +597,11 @@ impl Parse for Sudo {
             // a quoted userlist follows; this forces us to read a userlist
             let users = expect_nonterminal(stream)?;
             let permissions = expect_nonterminal(stream)?;
-            make(Sudo::Spec(PermissionSpec { users, permissions }))
+            make(Sudo::Spec(PermissionSpec {
+                users,
+                permissions,
+                origin,
+            }))
         } else if let Some(users) = maybe(try_nonterminal::<SpecList<_>>(stream))? {
             // this could be the start of a Defaults or Alias definition, so distinguish.
             // element 1 always exists (parse_list fails on an empty list)
@@ -564,7 +615,11 @@ impl Parse for Sudo {
                 make(Sudo::Decl(directive))
             } else {
                 let permissions = expect_nonterminal(stream)?;
-                make(Sudo::Spec(PermissionSpec { users, permissions }))
+                make(Sudo::Spec(PermissionSpec {
+                    users,
+                    permissions,
+                    origin,
+                }))
             }
         } else {
             // this will leave whatever could not be parsed on the input stream
@@ -669,9 +724,15 @@ where
                 name
             );
         }
+        let (line, col) = begin_pos;
+        let span = Span {
+            start: begin_pos,
+            end: (line, col + name.chars().count()),
+        };
         expect_syntax('=', stream)?;
 
-        make(Def(name, expect_nonterminal(stream)?))
+        let origin = DefOrigin { file: None, span };
+        make(Def(name, expect_nonterminal(stream)?, origin))
     }
 }
 
@@ -816,7 +877,13 @@ impl Parse for defaults::SettingsModifier {
                         "'{name}' cannot be used in a boolean context"
                     );
                 } else {
-                    unrecoverable!(pos = value_pos, stream, "unknown setting: '{name}'");
+                    return Err(Status::UnknownSetting(
+                        Span {
+                            start: value_pos,
+                            end: stream.get_pos(),
+                        },
+                        format!("unknown setting: '{name}'"),
+                    ));
                 }
             };
 
@@ -824,7 +891,13 @@ impl Parse for defaults::SettingsModifier {
         } else {
             let DefaultName(name) = try_nonterminal(stream)?;
             let Some(cfg) = defaults::set(&name) else {
-                unrecoverable!(pos = id_pos, stream, "unknown setting: '{name}'");
+                return Err(Status::UnknownSetting(
+                    Span {
+                        start: id_pos,
+                        end: stream.get_pos(),
+                    },
+                    format!("unknown setting: '{name}'"),
+                ));
             };
 
             if is_syntax('+', stream)? {
@@ -856,6 +929,13 @@ impl Parse for defaults::SettingsModifier {
                     }
                     defaults::SettingKind::Text(checker) => {
                         let text = text_item(stream)?;
+                        if defaults::is_directory(&name) && !text.starts_with('/') {
+                            unrecoverable!(
+                                pos = value_pos,
+                                stream,
+                                "values for '{name}' must start with a '/'"
+                            );
+                        }
                         let Some(modifier) = checker(&text) else {
                             unrecoverable!(
                                 pos = value_pos,
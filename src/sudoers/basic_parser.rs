@@ -37,8 +37,9 @@ pub struct Span {
 
 #[cfg_attr(test, derive(Debug, PartialEq))]
 pub enum Status {
-    Fatal(Span, String), // not recoverable; stream in inconsistent state
-    Reject,              // parsing failed by no input consumed
+    Fatal(Span, String),          // not recoverable; stream in inconsistent state
+    UnknownSetting(Span, String), // like Fatal, but for a Defaults setting that does not exist
+    Reject,                       // parsing failed by no input consumed
 }
 
 pub fn make<T>(value: T) -> Parsed<T> {
@@ -54,8 +55,9 @@ macro_rules! unrecoverable {
         return Err(crate::sudoers::basic_parser::Status::Fatal(Span { start: $pos, end: CharStream::get_pos($stream)}, format![$($str),*]))
     };
     ($stream:ident, $($str:expr),*) => {{
-        let pos = CharStream::get_pos($stream);
-        return Err(crate::sudoers::basic_parser::Status::Fatal(Span { start: pos, end: pos }, format![$($str),*]))
+        // point at the entire word that could not be parsed
+        let span = Span { start: CharStream::get_pos($stream), end: CharStream::get_word_end($stream) };
+        return Err(crate::sudoers::basic_parser::Status::Fatal(span, format![$($str),*]))
     }};
     ($($str:expr),*) => {
         return Err(crate::basic_parser::Status::Fatal(Default::default(), format![$($str),*]))
@@ -216,45 +218,60 @@ pub trait Token: Sized {
     fn escaped(_: char) -> bool {
         false
     }
+
+    /// Pass escaped characters on to [Token::construct] with their backslash still in front,
+    /// for tokens that need to tell them apart from unescaped ones.
+    const KEEP_ESCAPES: bool = false;
 }
 
 /// Implementation of the [Parse] trait for anything that implements [Token]
 impl<T: Token> Parse for T {
     fn parse(stream: &mut CharStream) -> Parsed<Self> {
+        const ESCAPE: char = '\\';
+
         fn accept_escaped<T: Token>(
             pred: fn(char) -> bool,
             stream: &mut CharStream,
-        ) -> Parsed<char> {
-            const ESCAPE: char = '\\';
+            str: &mut String,
+        ) -> Parsed<()> {
             if T::ALLOW_ESCAPE && stream.eat_char(ESCAPE) {
                 if let Some(c) = stream.next_if(T::escaped) {
-                    Ok(c)
+                    if T::KEEP_ESCAPES {
+                        str.push(ESCAPE);
+                    }
+                    str.push(c);
                 } else if pred(ESCAPE) {
-                    Ok(ESCAPE)
+                    str.push(ESCAPE);
                 } else if stream.eat_char('\n') {
                     if pred(' ') {
                         // escape + newline = line continuation (whitespace)
-                        Ok(' ')
+                        str.push(' ');
                     } else {
-                        reject()
+                        return reject();
                     }
                 } else {
                     unrecoverable!(stream, "illegal escape sequence")
                 }
             } else if let Some(c) = stream.next_if(pred) {
-                Ok(c)
+                str.push(c);
             } else {
-                reject()
+                return reject();
             }
+
+            Ok(())
         }
 
         let start_pos = stream.get_pos();
-        let mut str = accept_escaped::<T>(T::accept_1st, stream)?.to_string();
-        while let Some(c) = maybe(accept_escaped::<T>(T::accept, stream))? {
-            if str.len() >= T::MAX_LEN {
+        let mut str = String::new();
+        accept_escaped::<T>(T::accept_1st, stream, &mut str)?;
+        loop {
+            let len = str.len();
+            if maybe(accept_escaped::<T>(T::accept, stream, &mut str))?.is_none() {
+                break;
+            }
+            if len >= T::MAX_LEN {
                 unrecoverable!(stream, "token exceeds maximum length")
             }
-            str.push(c)
         }
 
         match T::construct(str) {
@@ -331,7 +348,7 @@ where
                 let error = |stream: &mut CharStream| unrecoverable!(stream, "{msg}");
                 result.push(error(stream));
             }
-            stream.skip_to_newline();
+            stream.skip_to_end_of_line();
         }
     }
 
@@ -1,6 +1,6 @@
 #[derive(Clone)]
 pub struct CharStream<'a> {
-    iter: std::iter::Peekable<std::str::Chars<'a>>,
+    iter: std::iter::Peekable<SkipCarriageReturn<'a>>,
     line: usize,
     col: usize,
 }
@@ -8,7 +8,7 @@ pub struct CharStream<'a> {
 impl<'a> CharStream<'a> {
     pub fn new_with_pos(src: &'a str, (line, col): (usize, usize)) -> Self {
         CharStream {
-            iter: src.chars().peekable(),
+            iter: SkipCarriageReturn(src.chars().peekable()).peekable(),
             line,
             col,
         }
@@ -39,6 +39,15 @@ impl<'a> CharStream<'a> {
         while self.next_if(|c| c != '\n').is_some() {}
     }
 
+    /// Like `skip_to_newline`, but an escaped newline does not end the line
+    pub fn skip_to_end_of_line(&mut self) {
+        while let Some(c) = self.next_if(|c| c != '\n') {
+            if c == '\\' {
+                self.advance(1);
+            }
+        }
+    }
+
     pub fn peek(&mut self) -> Option<char> {
         self.iter.peek().cloned()
     }
@@ -47,6 +56,14 @@ impl<'a> CharStream<'a> {
         (self.line, self.col)
     }
 
+    /// The position just past the word that starts at the current position; this is the current
+    /// position itself if that is whitespace or the end of the input.
+    pub fn get_word_end(&self) -> (usize, usize) {
+        let mut lookahead = self.clone();
+        while lookahead.next_if(|c| !c.is_whitespace()).is_some() {}
+        lookahead.get_pos()
+    }
+
     pub fn advance(&mut self, n: usize) {
         for _ in 0..n {
             self.next_if(|_| true);
@@ -54,6 +71,21 @@ impl<'a> CharStream<'a> {
     }
 }
 
+/// Turns CRLF line endings into plain newlines, so files edited on Windows parse the same.
+#[derive(Clone)]
+struct SkipCarriageReturn<'a>(std::iter::Peekable<std::str::Chars<'a>>);
+
+impl Iterator for SkipCarriageReturn<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        match self.0.next()? {
+            '\r' if self.0.peek() == Some(&'\n') => self.0.next(),
+            c => Some(c),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -70,4 +102,16 @@ mod test {
         assert!(stream.eat_char('3'));
         assert_eq!(stream.get_pos(), (2, 2));
     }
+
+    #[test]
+    fn test_crlf() {
+        let mut stream = CharStream::new("1\r\n\r2\r\n");
+        assert!(stream.eat_char('1'));
+        assert!(stream.eat_char('\n'));
+        assert!(stream.eat_char('\r'));
+        assert!(stream.eat_char('2'));
+        assert!(stream.eat_char('\n'));
+        assert_eq!(stream.peek(), None);
+        assert_eq!(stream.get_pos(), (3, 1));
+    }
 }
@@ -1,4 +1,5 @@
 use core::fmt;
+use std::ffi::OsStr;
 
 use crate::sudoers::{
     VecOrd,
@@ -11,6 +12,7 @@ use crate::{
 };
 
 use self::verbose::Verbose;
+pub use self::verbose::VerboseMatch;
 
 use super::{
     ast::{Authenticate, Def, EnvironmentControl, ExecControl, RunAs, Tag},
@@ -135,6 +137,11 @@ fn write_users(run_as: &RunAs, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Er
                         f.write_str("%:")?;
                         ident
                     }
+                    UserSpecifier::Netgroup(name) => {
+                        f.write_str("+")?;
+                        f.write_str(name)?;
+                        continue;
+                    }
                 };
 
                 match ident {
@@ -205,6 +212,15 @@ fn write_tag(
         f.write_str(" ")?;
     }
 
+    if tag.chroot != last_tag.chroot {
+        f.write_str("CHROOT=")?;
+        match tag.chroot.as_ref().expect("sudoers spec turned off") {
+            ChDir::Path(path) => write!(f, "{}", path.display())?,
+            ChDir::Any => f.write_str("*")?,
+        }
+        f.write_str(" ")?;
+    }
+
     let mut write_tag = |text, status: bool| {
         if !status {
             f.write_str("NO")?;
@@ -213,17 +229,18 @@ fn write_tag(
         f.write_str(": ")
     };
 
-    if tag.env != last_tag.env
-        && !(matches!(spec, Qualified::Allow(Meta::All)) && tag.env == EnvironmentControl::Setenv)
-    {
-        write_tag("SETENV", tag.env == EnvironmentControl::Setenv)?;
-    }
+    // same order as the original sudo
     if tag.noexec != last_tag.noexec {
         write_tag("EXEC", tag.noexec == ExecControl::Exec)?;
     }
     if tag.authenticate != last_tag.authenticate {
         write_tag("PASSWD", tag.authenticate != Authenticate::Nopasswd)?;
     }
+    if tag.env != last_tag.env
+        && !(matches!(spec, Qualified::Allow(Meta::All)) && tag.env == EnvironmentControl::Setenv)
+    {
+        write_tag("SETENV", tag.env == EnvironmentControl::Setenv)?;
+    }
 
     *last_tag = tag.clone();
     Ok(())
@@ -257,7 +274,7 @@ fn write_spec<'a>(
             match args {
                 Args::Exact(args) => {
                     for arg in args {
-                        write!(f, " {}", DisplayOsStr(arg))?;
+                        write!(f, " {}", DisplayArg(arg))?;
                     }
                     if args.is_empty() {
                         write!(f, " \"\"")?;
@@ -265,7 +282,7 @@ fn write_spec<'a>(
                 }
                 Args::Prefix(args) => {
                     for arg in args {
-                        write!(f, " {}", DisplayOsStr(arg))?;
+                        write!(f, " {}", DisplayArg(arg))?;
                     }
                     if !args.is_empty() {
                         write!(f, " *")?;
@@ -274,7 +291,7 @@ fn write_spec<'a>(
             }
         }
         Meta::Alias(alias) => {
-            if let Some(Def(_, spec_list)) = alias_list.find(|Def(id, _)| id == alias) {
+            if let Some(Def(_, spec_list, _)) = alias_list.find(|Def(id, ..)| id == alias) {
                 let mut is_first_iteration = true;
                 for spec in spec_list {
                     if !is_first_iteration {
@@ -295,3 +312,18 @@ fn write_spec<'a>(
 
     Ok(())
 }
+
+/// An argument that is empty or contains whitespace is quoted, as it was in the sudoers file.
+struct DisplayArg<'a>(&'a OsStr);
+
+impl fmt::Display for DisplayArg<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let arg = DisplayOsStr(self.0);
+        let bytes = self.0.as_encoded_bytes();
+        if bytes.is_empty() || bytes.iter().any(u8::is_ascii_whitespace) {
+            write!(f, "\"{arg}\"")
+        } else {
+            write!(f, "{arg}")
+        }
+    }
+}
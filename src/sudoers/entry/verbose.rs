@@ -42,6 +42,30 @@ impl fmt::Display for Verbose<'_> {
     }
 }
 
+/// The long format of `sudo -l command`: the command that was checked, and the options under
+/// which the matching rule allows it.
+pub struct VerboseMatch<'a> {
+    pub(in crate::sudoers) tag: &'a Tag,
+    pub(in crate::sudoers) run_as_user: &'a str,
+    pub(in crate::sudoers) command: &'a str,
+}
+
+impl fmt::Display for VerboseMatch<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            tag,
+            run_as_user,
+            command,
+        } = self;
+
+        write!(f, "{}", xlat!("Sudoers entry:"))?;
+        // TRANSLATORS: This is sudo-specific jargon.
+        write!(f, "\n    {}: {run_as_user}", xlat!("RunAsUsers"))?;
+        write_tag(f, tag)?;
+        write!(f, "\n    {}\n\t{command}", xlat!("Commands:"))
+    }
+}
+
 fn write_entry_header(run_as: &RunAs, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     write!(f, "\n{}", xlat!("Sudoers entry:"))?;
 
@@ -83,5 +107,14 @@ fn write_tag(f: &mut fmt::Formatter, tag: &Tag) -> fmt::Result {
         }
     }
 
+    if let Some(chroot) = &tag.chroot {
+        // TRANSLATORS: This is sudo-specific jargon.
+        write!(f, "\n    {}: ", xlat!("Chroot"))?;
+        match chroot {
+            ChDir::Path(path) => write!(f, "{}", path.display())?,
+            ChDir::Any => f.write_str("*")?,
+        }
+    }
+
     Ok(())
 }
@@ -0,0 +1,274 @@
+//! Static checks on a sudoers configuration that do not make it invalid, but that point at a
+//! likely mistake.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use super::ast::{
+    CommandSpec, ConfigScope, Def, Identifier, PermissionSpec, Qualified, RunAs, Spec, SpecList,
+    UserSpecifier,
+};
+use super::tokens::Meta;
+use super::{Error, Sudoers, VecOrd, distribute_tags};
+
+/// A rule that can never take effect, since a later rule matches every request that it matches.
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct ShadowedRule {
+    /// Position of the shadowed rule among all rules (counting from 1, including included files)
+    pub rule: usize,
+    /// Position of the (first) rule that shadows it
+    pub shadowed_by: usize,
+}
+
+impl Sudoers {
+    /// Find rules that are completely overridden by a later rule. Since the last matching rule
+    /// determines the outcome, such a rule has no effect at all.
+    ///
+    /// This is a conservative check: only later rules that apply to ALL hosts and ALL commands
+    /// (without exceptions) are considered, so a rule that is not reported may still be shadowed.
+    pub fn shadowed_rules(&self) -> Vec<ShadowedRule> {
+        let mut result = Vec::new();
+        for (index, earlier) in self.rules.iter().enumerate() {
+            let shadowed_by = self.rules[index + 1..]
+                .iter()
+                .position(|later| shadows(later, earlier));
+            if let Some(offset) = shadowed_by {
+                result.push(ShadowedRule {
+                    rule: index + 1,
+                    shadowed_by: index + offset + 2,
+                });
+            }
+        }
+
+        result
+    }
+
+    /// Report constructs that are accepted, but that have no effect: rules that are shadowed
+    /// by a later rule, command denials that follow a denial of ALL, and aliases that are never
+    /// referenced. None of these make the configuration invalid.
+    pub fn validate_semantics(&self) -> Vec<Error> {
+        let mut warnings = Vec::new();
+
+        for ShadowedRule { rule, shadowed_by } in self.shadowed_rules() {
+            warnings.push(rule_warning(
+                &self.rules[rule - 1],
+                format!(
+                    "user specification {rule} can never take effect, \
+                     since user specification {shadowed_by} overrides it"
+                ),
+            ));
+        }
+
+        for (index, rule) in self.rules.iter().enumerate() {
+            for command in ineffective_denials(rule) {
+                warnings.push(rule_warning(
+                    rule,
+                    format!(
+                        "denial of {command} in user specification {} has no effect, \
+                         since ALL is already denied",
+                        index + 1
+                    ),
+                ));
+            }
+        }
+
+        self.unused_aliases(&mut warnings);
+
+        warnings
+    }
+
+    fn unused_aliases(&self, warnings: &mut Vec<Error>) {
+        let mut user = HashSet::new();
+        let mut host = HashSet::new();
+        let mut cmnd = HashSet::new();
+        let mut runas = HashSet::new();
+
+        for rule in &self.rules {
+            user.extend(alias_names(&rule.users));
+            for (hosts, runas_cmds) in &rule.permissions {
+                host.extend(alias_names(hosts));
+                for (runas_spec, CommandSpec(_, command)) in runas_cmds {
+                    if let Some(RunAs { users, groups }) = runas_spec {
+                        runas.extend(alias_names(users));
+                        runas.extend(alias_names(groups));
+                    }
+                    cmnd.extend(alias_names(std::slice::from_ref(command)));
+                }
+            }
+        }
+
+        for (scope, _) in &self.customisers.non_cmnd {
+            match scope {
+                ConfigScope::Generic | ConfigScope::Command(_) => {}
+                ConfigScope::Host(list) => host.extend(alias_names(list)),
+                ConfigScope::User(list) => user.extend(alias_names(list)),
+                ConfigScope::RunAs(list) => runas.extend(alias_names(list)),
+            }
+        }
+        for (scope, _) in &self.customisers.cmnd {
+            cmnd.extend(alias_names(scope));
+        }
+
+        let aliases = &self.aliases;
+        report_unused("User_Alias", &aliases.user, user, warnings);
+        report_unused("Host_Alias", &aliases.host, host, warnings);
+        report_unused("Cmnd_Alias", &aliases.cmnd, cmnd, warnings);
+        report_unused("Runas_Alias", &aliases.runas, runas, warnings);
+    }
+}
+
+/// Rules do not record their span, so these warnings refer to the rule by its position instead.
+fn rule_warning(rule: &PermissionSpec, message: String) -> Error {
+    Error {
+        source: rule.origin.file.as_deref().map(Path::to_path_buf),
+        location: None,
+        message,
+        unknown_setting: false,
+    }
+}
+
+fn alias_names<T>(list: &[Spec<T>]) -> impl Iterator<Item = &str> {
+    list.iter().filter_map(|item| match item {
+        Qualified::Allow(Meta::Alias(name)) | Qualified::Forbid(Meta::Alias(name)) => {
+            Some(name.as_str())
+        }
+        _ => None,
+    })
+}
+
+/// Aliases used in the definition of another alias count as referenced, even if that other
+/// alias is itself unused; that one will be reported instead.
+fn report_unused<'a, T>(
+    kind: &str,
+    table: &'a VecOrd<Def<T>>,
+    mut referenced: HashSet<&'a str>,
+    warnings: &mut Vec<Error>,
+) {
+    for Def(_, members, _) in &table.1 {
+        referenced.extend(alias_names(members));
+    }
+
+    for Def(name, _, origin) in &table.1 {
+        if !referenced.contains(name.as_str()) {
+            warnings.push(Error {
+                source: origin.file.as_deref().map(Path::to_path_buf),
+                location: Some(origin.span),
+                message: format!("unused {kind}: '{name}'"),
+                unknown_setting: false,
+            });
+        }
+    }
+}
+
+/// Within a command list the last matching item decides, so once ALL has been denied (for a
+/// given RunAs specification) a further denial changes nothing until something is allowed again.
+/// Negated aliases are not considered, since an alias can itself contain negations.
+fn ineffective_denials(rule: &PermissionSpec) -> Vec<String> {
+    let mut result = Vec::new();
+    for (_, runas_cmds) in &rule.permissions {
+        // the position of the entry whose RunAs specification applies; two separate but equal
+        // specifications are (conservatively) treated as different
+        let mut runas_entry = None;
+        let mut denied_all_for = None;
+        for (index, (runas, CommandSpec(_, command))) in runas_cmds.iter().enumerate() {
+            if runas.is_some() {
+                runas_entry = Some(index);
+            }
+            if denied_all_for.is_some_and(|denied| denied != runas_entry) {
+                denied_all_for = None;
+            }
+
+            match command {
+                Qualified::Forbid(Meta::All) if denied_all_for.is_some() => {
+                    result.push("ALL".to_string())
+                }
+                Qualified::Forbid(Meta::All) => denied_all_for = Some(runas_entry),
+                Qualified::Forbid(Meta::Only((cmd, _))) if denied_all_for.is_some() => {
+                    result.push(format!("'{}'", cmd.as_str()))
+                }
+                Qualified::Forbid(_) => {}
+                Qualified::Allow(_) => denied_all_for = None,
+            }
+        }
+    }
+
+    result
+}
+
+/// Does `later` match every request that `earlier` matches?
+fn shadows(later: &PermissionSpec, earlier: &PermissionSpec) -> bool {
+    let later_negates_commands = later
+        .permissions
+        .iter()
+        .flat_map(|(_, runas_cmds)| distribute_tags(runas_cmds))
+        .any(|(_, (_, cmd))| matches!(cmd, Qualified::Forbid(_)));
+
+    if later_negates_commands || !covers_users(&later.users, &earlier.users) {
+        return false;
+    }
+
+    // the RunAs specifications under which `later` allows ALL commands on ALL hosts
+    let catch_alls = later
+        .permissions
+        .iter()
+        .filter(|(hosts, _)| matches_all(hosts))
+        .flat_map(|(_, runas_cmds)| distribute_tags(runas_cmds))
+        .filter(|(_, (_, cmd))| matches!(cmd, Qualified::Allow(Meta::All)))
+        .map(|(runas, _)| runas)
+        .collect::<Vec<_>>();
+
+    !catch_alls.is_empty()
+        && earlier
+            .permissions
+            .iter()
+            .flat_map(|(_, runas_cmds)| distribute_tags(runas_cmds))
+            .all(|(runas, _)| catch_alls.iter().any(|later| covers_runas(*later, runas)))
+}
+
+/// In a list, the last matching item decides; so a list that ends in `ALL` matches everything.
+fn matches_all<T>(list: &SpecList<T>) -> bool {
+    matches!(list.last(), Some(Qualified::Allow(Meta::All)))
+}
+
+/// Either `later` matches all users, or it lists every user that `earlier` lists (and excludes none).
+fn covers_users(later: &SpecList<UserSpecifier>, earlier: &SpecList<UserSpecifier>) -> bool {
+    matches_all(later)
+        || (!later.iter().any(|x| matches!(x, Qualified::Forbid(_)))
+            && earlier
+                .iter()
+                .all(|y| later.iter().any(|x| same_user(x, y))))
+}
+
+fn same_user(later: &Spec<UserSpecifier>, earlier: &Spec<UserSpecifier>) -> bool {
+    use UserSpecifier::*;
+
+    let same_identifier = |x: &Identifier, y: &Identifier| match (x, y) {
+        (Identifier::Name(x), Identifier::Name(y)) => x == y,
+        (Identifier::ID(x), Identifier::ID(y)) => x == y,
+        _ => false,
+    };
+
+    match (later, earlier) {
+        (Qualified::Allow(Meta::Only(x)), Qualified::Allow(Meta::Only(y))) => match (x, y) {
+            (User(x), User(y)) | (Group(x), Group(y)) | (NonunixGroup(x), NonunixGroup(y)) => {
+                same_identifier(x, y)
+            }
+            (Netgroup(x), Netgroup(y)) => x == y,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// A missing RunAs specification only allows running commands as root; otherwise the later
+/// specification must allow all users, and all groups if the earlier one allows any group.
+fn covers_runas(later: Option<&RunAs>, earlier: Option<&RunAs>) -> bool {
+    match (later, earlier) {
+        (None, None) => true,
+        (None, Some(_)) => false,
+        (Some(later), None) => matches_all(&later.users),
+        (Some(later), Some(earlier)) => {
+            matches_all(&later.users) && (earlier.groups.is_empty() || matches_all(&later.groups))
+        }
+    }
+}
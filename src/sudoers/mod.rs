@@ -8,13 +8,17 @@ mod ast_names;
 mod basic_parser;
 mod char_stream;
 mod entry;
+mod lint;
 mod tokens;
 
+use std::cell::OnceCell;
 use std::collections::{HashMap, HashSet};
-use std::ffi::OsString;
+use std::ffi::{CString, OsString};
 use std::fmt;
 use std::io;
-use std::path::{Path, PathBuf};
+use std::net::IpAddr;
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
 
 use crate::common::resolve::{is_valid_executable, resolve_path};
 use crate::defaults;
@@ -27,7 +31,7 @@ use tokens::*;
 pub type Settings = defaults::Settings;
 pub use basic_parser::Span;
 
-/// How many nested include files do we allow?
+/// How many nested include files do we allow? This also bounds the includedir_file_limit setting.
 const INCLUDE_LIMIT: u8 = 128;
 
 /// Export some necessary symbols from modules
@@ -35,6 +39,29 @@ pub struct Error {
     pub source: Option<PathBuf>,
     pub location: Option<basic_parser::Span>,
     pub message: String,
+    unknown_setting: bool,
+}
+
+impl Error {
+    fn parse_error(source: Option<PathBuf>, status: basic_parser::Status) -> Error {
+        let (location, message, unknown_setting) = match status {
+            basic_parser::Status::Fatal(pos, message) => (pos, message, false),
+            basic_parser::Status::UnknownSetting(pos, message) => (pos, message, true),
+            basic_parser::Status::Reject => panic!("internal parser error"),
+        };
+
+        Error {
+            source,
+            location: Some(location),
+            message,
+            unknown_setting,
+        }
+    }
+
+    /// Does this diagnostic report a Defaults setting that sudo-rs does not recognize?
+    pub fn is_unknown_setting(&self) -> bool {
+        self.unknown_setting
+    }
 }
 
 /// A "Customiser" represents a "Defaults" setting that has 'late binding'; i.e.
@@ -71,17 +98,18 @@ pub struct ListRequest<'a, User: UnixUser, Group: UnixGroup> {
 #[cfg_attr(test, derive(Clone))]
 pub struct Judgement {
     flags: Option<Tag>,
+    denial: Option<DenialReason>,
     settings: Settings,
 }
 
 mod policy;
 
 pub use policy::{
-    AuthenticatingUser, Authentication, AuthenticationScope, Authorization, DirChange, Logging,
-    Restrictions,
+    AuthenticatingUser, Authentication, AuthenticationScope, Authorization, DenialReason,
+    DirChange, Lecture, Logging, Restrictions,
 };
 
-pub use self::entry::Entry;
+pub use self::entry::{Entry, VerboseMatch};
 
 type MatchedCommand<'a> = (Option<&'a RunAs>, (Tag, &'a Spec<Command>));
 
@@ -100,16 +128,51 @@ impl Sudoers {
         Ok(analyze(path.as_ref(), sudoers))
     }
 
+    /// Check a fragment of a sudoers file that starts at `first_line`, without reading the rest
+    /// of the file; e.g. for an editor that validates a line as it is being typed. Only syntax
+    /// errors are reported, since checks like alias resolution need the entire file; the
+    /// reported locations refer to positions in the entire file.
+    #[cfg(test)]
+    pub fn check_lines(text: &str, first_line: usize) -> Vec<Error> {
+        // a fragment need not end with a newline, even if the file it is part of does
+        let mut text = text.to_string();
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+
+        let mut stream = char_stream::CharStream::new_with_pos(&text, (first_line, 1));
+        basic_parser::parse_lines::<Sudo>(&mut stream)
+            .into_iter()
+            .filter_map(|item| match item {
+                Ok(_) => None,
+                Err(status) => Some(Error::parse_error(None, status)),
+            })
+            .collect()
+    }
+
     fn specify_host_user_runas<User: UnixUser + PartialEq<User>>(
         &mut self,
         hostname: &system::Hostname,
         requesting_user: &User,
         target_user: Option<&User>,
     ) {
-        let customisers = std::mem::take(&mut self.customisers.non_cmnd);
+        let mut settings = self.settings.clone();
+        self.apply_host_user_runas(&mut settings, hostname, requesting_user, target_user);
+        self.settings = settings;
+        self.customisers.non_cmnd.clear();
+    }
 
-        let host_matcher = &match_token(hostname);
-        let host_aliases = get_aliases(&self.aliases.host, host_matcher);
+    /// Apply the Defaults lines that are not specific to a command, in the order in which they
+    /// appear in the sudoers file; without a target user, runas-specific lines do not apply.
+    fn apply_host_user_runas<User: UnixUser + PartialEq<User>>(
+        &self,
+        settings: &mut Settings,
+        hostname: &system::Hostname,
+        requesting_user: &User,
+        target_user: Option<&User>,
+    ) {
+        let host_matcher = HostMatcher::new(hostname);
+        let host_aliases = host_matcher.aliases(&self.aliases.host);
 
         let user_matcher = &match_user(requesting_user);
         let user_aliases = get_aliases(&self.aliases.user, user_matcher);
@@ -121,15 +184,15 @@ impl Sudoers {
             (runas_matcher, runas_aliases)
         });
 
-        let match_scope = |scope| match scope {
+        let match_scope = |scope: &ConfigScope| match scope {
             ConfigScope::Generic => true,
-            ConfigScope::Host(list) => find_item(&list, host_matcher, &host_aliases).is_some(),
-            ConfigScope::User(list) => find_item(&list, user_matcher, &user_aliases).is_some(),
+            ConfigScope::Host(list) => host_matcher.find(list, &host_aliases).is_some(),
+            ConfigScope::User(list) => find_item(list, user_matcher, &user_aliases).is_some(),
             ConfigScope::RunAs(list) => {
                 runas_matcher_aliases
                     .as_ref()
                     .is_some_and(|(runas_matcher, runas_aliases)| {
-                        find_item(&list, runas_matcher, runas_aliases).is_some()
+                        find_item(list, runas_matcher, runas_aliases).is_some()
                     })
             }
             ConfigScope::Command(_list) => {
@@ -137,10 +200,10 @@ impl Sudoers {
             }
         };
 
-        for (scope, modifiers) in customisers {
+        for (scope, modifiers) in &self.customisers.non_cmnd {
             if match_scope(scope) {
                 for modifier in modifiers {
-                    modifier(&mut self.settings);
+                    modifier(settings);
                 }
             }
         }
@@ -174,15 +237,24 @@ impl Sudoers {
         let skip_passwd =
             am_user.is_root() || (request.user == am_user && in_group(am_user, request.group));
 
-        let mut flags = check_permission(self, am_user, on_host, request);
+        let is_sudoedit = request.command == Path::new("sudoedit");
+
+        let mut flags = check_permission(self, am_user, on_host, &request);
+        let denial = flags
+            .is_none()
+            .then(|| denial_reason(self, am_user, on_host, &request));
         if let Some(Tag { authenticate, .. }) = flags.as_mut() {
             if skip_passwd {
                 *authenticate = Authenticate::Nopasswd;
+            } else if is_sudoedit && self.settings.sudoedit_authenticate() {
+                // the NOPASSWD tag does not apply to sudoedit
+                *authenticate = Authenticate::Passwd;
             }
         }
 
         Judgement {
             flags,
+            denial,
             settings: self.settings.clone(),
         }
     }
@@ -271,18 +343,30 @@ impl Sudoers {
     ) -> impl Iterator<Item = impl Iterator<Item = MatchedCommand<'a>>> {
         let Self { rules, aliases, .. } = self;
         let user_aliases = get_aliases(&aliases.user, &match_user(invoking_user));
-        let host_aliases = get_aliases(&aliases.host, &match_token(hostname));
+        let host_matcher = HostMatcher::new(hostname);
+        let host_aliases = host_matcher.aliases(&aliases.host);
 
         rules
             .iter()
             .filter_map(move |sudo| {
                 find_item(&sudo.users, &match_user(invoking_user), &user_aliases)?;
-                Some(&sudo.permissions)
+                Some(sudo.permissions.iter().map(|perm| (&sudo.origin, perm)))
             })
             .flatten()
-            .filter_map(move |(hosts, runas_cmds)| {
-                find_item(hosts, &match_token(hostname), &host_aliases)?;
-                Some(distribute_tags(runas_cmds))
+            .filter_map(move |(origin, (hosts, runas_cmds))| {
+                host_matcher.find(hosts, &host_aliases)?;
+                Some(distribute_tags(runas_cmds).map(|(runas, (tag, cmd))| {
+                    (
+                        runas,
+                        (
+                            Tag {
+                                origin: Some(origin.clone()),
+                                ..tag
+                            },
+                            cmd,
+                        ),
+                    )
+                }))
             })
     }
 
@@ -402,14 +486,41 @@ fn read_sudoers<R: io::Read>(mut reader: R) -> io::Result<Vec<basic_parser::Pars
     let mut buffer = String::new();
     reader.read_to_string(&mut buffer)?;
 
+    Ok(parse_sudoers(&buffer))
+}
+
+/// Like `read_sudoers`, but refuses to continue with a policy that does not have the size the file
+/// had when it was opened; on unreliable (network) filesystems, reads can silently come up short.
+fn read_sudoers_exact<R: io::Read>(
+    mut reader: R,
+    expected_len: u64,
+) -> io::Result<Vec<basic_parser::Parsed<Sudo>>> {
+    let mut buffer = String::new();
+    reader.read_to_string(&mut buffer)?;
+
+    if buffer.len() as u64 != expected_len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!(
+                "read {} bytes, but expected {expected_len}; file changed or was only partially read",
+                buffer.len()
+            ),
+        ));
+    }
+
+    Ok(parse_sudoers(&buffer))
+}
+
+fn parse_sudoers(buffer: &str) -> Vec<basic_parser::Parsed<Sudo>> {
     use basic_parser::parse_lines;
     use char_stream::*;
-    Ok(parse_lines(&mut CharStream::new(&buffer)))
+    parse_lines(&mut CharStream::new(buffer))
 }
 
 fn open_sudoers(path: &Path) -> io::Result<Vec<basic_parser::Parsed<Sudo>>> {
     let source = audit::secure_open_sudoers(path)?;
-    read_sudoers(source)
+    let expected_len = source.metadata()?.len();
+    read_sudoers_exact(source, expected_len)
 }
 
 #[cfg(feature = "unstable-remote-sudoers")]
@@ -495,7 +606,7 @@ fn check_permission<User: UnixUser + PartialEq<User>, Group: UnixGroup>(
     sudoers: &Sudoers,
     am_user: &User,
     on_host: &system::Hostname,
-    request: Request<User, Group>,
+    request: &Request<User, Group>,
 ) -> Option<Tag> {
     let cmdline = (request.command, request.arguments);
 
@@ -507,17 +618,13 @@ fn check_permission<User: UnixUser + PartialEq<User>, Group: UnixGroup>(
     let matching_user_specs = sudoers.matching_user_specs(am_user, on_host).flatten();
 
     let allowed_commands = matching_user_specs.filter_map(|(runas, cmdspec)| {
-        if let Some(RunAs { users, groups }) = runas {
-            let stays_in_group = in_group(request.user, request.group);
-            if request.user != am_user || (stays_in_group && !users.is_empty()) {
-                find_item(users, &match_user(request.user), &runas_user_aliases)?
-            }
-            if !stays_in_group {
-                find_item(groups, &match_group(request.group), &runas_group_aliases)?
-            }
-        } else if !(request.user.is_root() && in_group(request.user, request.group)) {
-            None?;
-        }
+        match_runas(
+            runas,
+            am_user,
+            request,
+            &runas_user_aliases,
+            &runas_group_aliases,
+        )?;
 
         Some(cmdspec)
     });
@@ -525,6 +632,107 @@ fn check_permission<User: UnixUser + PartialEq<User>, Group: UnixGroup>(
     find_item(allowed_commands, &match_command(cmdline), &cmnd_aliases)
 }
 
+/// Check whether a RunAs specification (or the absence of one) allows the target user and group
+/// of the request.
+fn match_runas<User: UnixUser + PartialEq<User>, Group: UnixGroup>(
+    runas: Option<&RunAs>,
+    am_user: &User,
+    request: &Request<User, Group>,
+    runas_user_aliases: &FoundAliases,
+    runas_group_aliases: &FoundAliases,
+) -> Option<()> {
+    if let Some(RunAs { users, groups }) = runas {
+        let stays_in_group = in_group(request.user, request.group);
+        let group_matches = || find_item(groups, &match_group(request.group), runas_group_aliases);
+        if request.user != am_user {
+            find_item(users, &match_user(request.user), runas_user_aliases)?
+        } else if stays_in_group && !users.is_empty() {
+            // a group-only request for one of the invoking user's own groups does not need the
+            // user list, as long as the group list names that group
+            find_item(users, &match_user(request.user), runas_user_aliases)
+                .or_else(group_matches)?
+        }
+        if !stays_in_group {
+            group_matches()?
+        }
+    } else if !(request.user.is_root() && in_group(request.user, request.group)) {
+        None?;
+    }
+
+    Some(())
+}
+
+/// Determine why `check_permission` did not allow a request, by retracing its steps and
+/// reporting the first one at which nothing matched anymore.
+fn denial_reason<User: UnixUser + PartialEq<User>, Group: UnixGroup>(
+    sudoers: &Sudoers,
+    am_user: &User,
+    on_host: &system::Hostname,
+    request: &Request<User, Group>,
+) -> DenialReason {
+    let Sudoers { rules, aliases, .. } = sudoers;
+    let user_aliases = get_aliases(&aliases.user, &match_user(am_user));
+    let host_matcher = HostMatcher::new(on_host);
+    let host_aliases = host_matcher.aliases(&aliases.host);
+
+    let mut user_specs = rules
+        .iter()
+        .filter(|sudo| find_item(&sudo.users, &match_user(am_user), &user_aliases).is_some())
+        .peekable();
+
+    if user_specs.peek().is_none() {
+        return DenialReason::NoMatchingUser;
+    }
+
+    if !user_specs
+        .flat_map(|sudo| &sudo.permissions)
+        .any(|(hosts, _)| host_matcher.find(hosts, &host_aliases).is_some())
+    {
+        return DenialReason::HostMismatch;
+    }
+
+    let runas_user_aliases = get_aliases(&aliases.runas, &match_user(request.user));
+    let runas_group_aliases = get_aliases(&aliases.runas, &match_group_alias(request.group));
+
+    let mut commands = sudoers
+        .matching_user_specs(am_user, on_host)
+        .flatten()
+        .filter(|(runas, _)| {
+            match_runas(
+                *runas,
+                am_user,
+                request,
+                &runas_user_aliases,
+                &runas_group_aliases,
+            )
+            .is_some()
+        })
+        .map(|(_, (_, cmd))| cmd)
+        .peekable();
+
+    if commands.peek().is_none() {
+        return DenialReason::RunAsMismatch;
+    }
+
+    // since the last matching command decides, and it did not allow the request, any command
+    // that matches at all means that the request was explicitly forbidden
+    let cmdline = (request.command, request.arguments);
+    let cmnd_aliases = get_aliases(&aliases.cmnd, &match_command(cmdline));
+    let mentioned = |cmd: &Spec<Command>| match cmd {
+        Qualified::Allow(who) | Qualified::Forbid(who) => match who {
+            Meta::All => true,
+            Meta::Only(ident) => match_command(cmdline)(ident),
+            Meta::Alias(id) => cmnd_aliases.contains_key(id),
+        },
+    };
+
+    if commands.any(mentioned) {
+        DenialReason::CommandForbidden
+    } else {
+        DenialReason::CommandNotPermitted
+    }
+}
+
 /// Process a raw parsed AST bit of RunAs + Command specifications:
 /// - RunAs specifications distribute over the commands that follow (until overridden)
 /// - Tags accumulate over the entire line
@@ -633,7 +841,8 @@ fn match_user(user: &impl UnixUser) -> impl Fn(&UserSpecifier) -> bool + '_ {
         UserSpecifier::User(id) => match_identifier(user, id),
         UserSpecifier::Group(Identifier::Name(name)) => user.in_group_by_name(name.as_cstr()),
         UserSpecifier::Group(Identifier::ID(num)) => user.in_group_by_gid(GroupId::new(*num)),
-        // nonunix-groups, netgroups, etc. are not implemented
+        UserSpecifier::Netgroup(name) => user.in_netgroup(name.as_cstr()),
+        // nonunix-groups are not implemented
         UserSpecifier::NonunixGroup(group) => {
             match group {
                 Identifier::Name(name) => auth_warn!("warning: non-unix group {name} was ignored"),
@@ -668,10 +877,82 @@ fn match_group_alias(group: &impl UnixGroup) -> impl Fn(&UserSpecifier) -> bool
     }
 }
 
-fn match_token<T: basic_parser::Token + std::ops::Deref<Target = String>>(
-    text: &str,
-) -> impl Fn(&T) -> bool + '_ {
-    move |token| token.as_str() == text
+/// Host names are case-insensitive, like in DNS; networks are matched against the
+/// addresses of the network interfaces of this machine, which are looked up when needed.
+struct HostMatcher<'a> {
+    hostname: &'a str,
+    addresses: OnceCell<Option<Vec<IpAddr>>>,
+}
+
+/// The host aliases that match, if networks match and if they do not (see [HostMatcher::find]).
+type FoundHostAliases = [FoundAliases; 2];
+
+impl<'a> HostMatcher<'a> {
+    fn new(hostname: &'a str) -> Self {
+        HostMatcher {
+            hostname,
+            addresses: OnceCell::new(),
+        }
+    }
+
+    /// If the network addresses can not be determined, every network matches if `assumed` is
+    /// set, and none of them match otherwise.
+    fn matcher(&self, assumed: bool) -> impl Fn(&Hostname) -> bool + '_ {
+        move |token| {
+            if let Some(network) = token.network() {
+                let addresses = self.addresses.get_or_init(|| {
+                    system::interface_addresses()
+                        .inspect_err(|e| {
+                            auth_warn!("warning: could not determine the network addresses: {e}")
+                        })
+                        .ok()
+                });
+                return match addresses {
+                    Some(addresses) => addresses.iter().any(|addr| match addr {
+                        IpAddr::V4(addr) => network.contains(*addr),
+                        IpAddr::V6(_) => false,
+                    }),
+                    None => assumed,
+                };
+            }
+
+            match token.strip_prefix('+') {
+                Some(netgroup) => host_in_netgroup(netgroup, self.hostname),
+                None => token.eq_ignore_ascii_case(self.hostname),
+            }
+        }
+    }
+
+    fn aliases(&self, table: &VecOrd<Def<Hostname>>) -> FoundHostAliases {
+        [false, true].map(|assumed| get_aliases(table, &self.matcher(assumed)))
+    }
+
+    /// Like [find_item]; if the network addresses can not be determined, the host only matches
+    /// if it does so whether the networks in `items` match or not, so that a negated network
+    /// still excludes it.
+    fn find<'b, Iter>(
+        &self,
+        items: Iter,
+        aliases: &FoundHostAliases,
+    ) -> Option<<Iter::Item as WithInfo>::Info>
+    where
+        Iter: IntoIterator + Copy,
+        Iter::Item: WithInfo<Item = &'b Spec<Hostname>>,
+    {
+        let [unmatched, matched] = aliases;
+        let result = find_item(items, &self.matcher(false), unmatched);
+        find_item(items, &self.matcher(true), matched).and(result)
+    }
+}
+
+fn host_in_netgroup(netgroup: &str, hostname: &str) -> bool {
+    match (CString::new(netgroup), CString::new(hostname)) {
+        (Ok(netgroup), Ok(hostname)) => system::in_netgroup(&netgroup, Some(&hostname), None),
+        _ => {
+            auth_warn!("warning: could not look up host {hostname} in netgroup +{netgroup}");
+            false
+        }
+    }
 }
 
 fn match_command<'a>((cmd, args): (&'a Path, &'a [OsString])) -> impl Fn(&Command) -> bool + 'a {
@@ -698,7 +979,7 @@ where
     let all = Qualified::Allow(Meta::All);
 
     let mut set = HashMap::new();
-    for Def(id, list) in table.iter() {
+    for Def(id, list, _) in table.iter() {
         if find_item(list, &pred, &set).is_some() {
             set.insert(id.clone(), true);
         } else if find_item(once(&all).chain(list), &pred, &set).is_none() {
@@ -797,12 +1078,32 @@ fn analyze(
         }
     }
 
+    /// Replace `%h` in an include path by the short host name. Since the host name is not under
+    /// control of the sudoers file, the result may not refer to a parent directory.
+    fn expand_hostname(path: &str) -> Result<String, String> {
+        let hostname = system::Hostname::resolve();
+        let short_name = hostname.split_once('.').map_or(&*hostname, |x| x.0);
+        let expanded = path.replace("%h", short_name);
+
+        if Path::new(&expanded)
+            .components()
+            .any(|part| part == Component::ParentDir)
+        {
+            Err(format!(
+                "cannot open sudoers file {expanded}: expanded path may not contain '..'"
+            ))
+        } else {
+            Ok(expanded)
+        }
+    }
+
     struct IncludeContext<'a> {
         path: &'a Path,
         parent: &'a Path,
         span: Span,
         diagnostics: &'a mut Vec<Error>,
         include_state: &'a mut IncludeState,
+        parse_defaults: &'a mut Settings,
         include_source: IncludeDirective,
     }
 
@@ -825,6 +1126,7 @@ fn analyze(
                 source: Some(ctx.parent.to_owned()),
                 location: Some(ctx.span),
                 message,
+                unknown_setting: false,
             });
         } else {
             let (res, next_state, kind) = match ctx.include_source {
@@ -838,7 +1140,14 @@ fn analyze(
             };
 
             match res {
-                Ok(subsudoer) => process(cfg, ctx.path, subsudoer, ctx.diagnostics, next_state),
+                Ok(subsudoer) => process(
+                    cfg,
+                    ctx.path,
+                    subsudoer,
+                    ctx.diagnostics,
+                    next_state,
+                    ctx.parse_defaults,
+                ),
                 Err(e) => {
                     let message = if e.kind() == io::ErrorKind::NotFound {
                         // improve the error message in this case
@@ -851,37 +1160,51 @@ fn analyze(
                         source: Some(ctx.parent.to_owned()),
                         location: Some(ctx.span),
                         message,
+                        unknown_setting: false,
                     })
                 }
             }
         }
     }
 
+    fn add_aliases<T>(table: &mut VecOrd<Def<T>>, defs: Vec<Def<T>>, cur_path: &Path) {
+        let file: Rc<Path> = Rc::from(cur_path);
+        table.1.extend(defs.into_iter().map(|mut def| {
+            def.2.file = Some(file.clone());
+            def
+        }));
+    }
+
     fn process(
         cfg: &mut Sudoers,
         cur_path: &Path,
         sudoers: impl IntoIterator<Item = basic_parser::Parsed<Sudo>>,
         diagnostics: &mut Vec<Error>,
         include_state: &mut IncludeState,
+        parse_defaults: &mut Settings,
     ) {
         for item in sudoers {
             match item {
                 Ok(line) => match line {
                     Sudo::LineComment => {}
 
-                    Sudo::Spec(permission) => {
+                    Sudo::Spec(mut permission) => {
                         diagnostics.extend(get_ignored_tags(&permission).map(|span| Error {
                             source: Some(cur_path.to_owned()),
                             location: Some(span),
                             message: "this tag is ignored by sudo-rs".to_string(),
+                            unknown_setting: false,
                         }));
+                        permission.origin.file = Some(Rc::from(cur_path));
                         cfg.rules.push(permission);
                     }
 
-                    Sudo::Decl(HostAlias(mut def)) => cfg.aliases.host.1.append(&mut def),
-                    Sudo::Decl(UserAlias(mut def)) => cfg.aliases.user.1.append(&mut def),
-                    Sudo::Decl(RunasAlias(mut def)) => cfg.aliases.runas.1.append(&mut def),
-                    Sudo::Decl(CmndAlias(mut def)) => cfg.aliases.cmnd.1.append(&mut def),
+                    Sudo::Decl(HostAlias(def)) => add_aliases(&mut cfg.aliases.host, def, cur_path),
+                    Sudo::Decl(UserAlias(def)) => add_aliases(&mut cfg.aliases.user, def, cur_path),
+                    Sudo::Decl(RunasAlias(def)) => {
+                        add_aliases(&mut cfg.aliases.runas, def, cur_path)
+                    }
+                    Sudo::Decl(CmndAlias(def)) => add_aliases(&mut cfg.aliases.cmnd, def, cur_path),
 
                     Sudo::Decl(Defaults(params, scope)) => {
                         if let ConfigScope::Command(specs) = scope {
@@ -897,21 +1220,45 @@ fn analyze(
                                 params,
                             ));
                         } else {
+                            // generic Defaults can already affect how the rest is processed
+                            if matches!(scope, ConfigScope::Generic) {
+                                for modifier in &params {
+                                    modifier(parse_defaults);
+                                }
+                            }
                             cfg.customisers.non_cmnd.push((scope, params));
                         }
                     }
 
-                    Sudo::Include(path, span) => include(
-                        cfg,
-                        IncludeContext {
-                            path: &resolve_relative(cur_path, path),
-                            parent: cur_path,
-                            span,
-                            diagnostics,
-                            include_state,
-                            include_source: IncludeDirective::Include,
-                        },
-                    ),
+                    Sudo::Include(mut path, span) => {
+                        if path.contains("%h") && parse_defaults.includedir_host_escape() {
+                            match expand_hostname(&path) {
+                                Ok(expanded) => path = expanded,
+                                Err(message) => {
+                                    diagnostics.push(Error {
+                                        source: Some(cur_path.to_owned()),
+                                        location: Some(span),
+                                        message,
+                                        unknown_setting: false,
+                                    });
+                                    continue;
+                                }
+                            }
+                        }
+
+                        include(
+                            cfg,
+                            IncludeContext {
+                                path: &resolve_relative(cur_path, path),
+                                parent: cur_path,
+                                span,
+                                diagnostics,
+                                include_state,
+                                parse_defaults,
+                                include_source: IncludeDirective::Include,
+                            },
+                        )
+                    }
 
                     #[cfg(feature = "unstable-remote-sudoers")]
                     Sudo::Remote(path, peer_spec, span) => {
@@ -923,6 +1270,7 @@ fn analyze(
                                 message: format!(
                                     "cannot open socket {path}: path must be absolute"
                                 ),
+                                unknown_setting: false,
                             });
                         } else {
                             include(
@@ -933,14 +1281,28 @@ fn analyze(
                                     span,
                                     diagnostics,
                                     include_state,
+                                    parse_defaults,
                                     include_source: IncludeDirective::Remote(peer_spec),
                                 },
                             );
                         }
                     }
 
-                    Sudo::IncludeDir(path, span) => {
-                        if path.contains("%h") {
+                    Sudo::IncludeDir(mut path, span) => {
+                        if path.contains("%h") && parse_defaults.includedir_host_escape() {
+                            match expand_hostname(&path) {
+                                Ok(expanded) => path = expanded,
+                                Err(message) => {
+                                    diagnostics.push(Error {
+                                        source: Some(cur_path.to_owned()),
+                                        location: Some(span),
+                                        message,
+                                        unknown_setting: false,
+                                    });
+                                    continue;
+                                }
+                            }
+                        } else if path.contains("%h") {
                             diagnostics.push(Error {
                                 source: Some(cur_path.to_owned()),
                                 location: Some(span),
@@ -948,6 +1310,7 @@ fn analyze(
                                     "cannot open sudoers file {path}: \
                                      percent escape %h in includedir is unsupported"
                                 ),
+                                unknown_setting: false,
                             });
                             continue;
                         }
@@ -958,6 +1321,7 @@ fn analyze(
                                 source: Some(cur_path.to_owned()),
                                 location: Some(span),
                                 message: format!("cannot open sudoers file {}", path.display()),
+                                unknown_setting: false,
                             });
                             continue;
                         };
@@ -973,6 +1337,19 @@ fn analyze(
                             })
                             .collect::<Vec<_>>();
                         safe_files.sort();
+                        let limit = parse_defaults.includedir_file_limit() as usize;
+                        if safe_files.len() > limit {
+                            diagnostics.push(Error {
+                                source: Some(cur_path.to_owned()),
+                                location: Some(span),
+                                message: format!(
+                                    "too many files in {}; only the first {limit} are used",
+                                    path.display()
+                                ),
+                                unknown_setting: false,
+                            });
+                            safe_files.truncate(limit);
+                        }
                         for file in safe_files {
                             include(
                                 cfg,
@@ -982,6 +1359,7 @@ fn analyze(
                                     span,
                                     diagnostics,
                                     include_state,
+                                    parse_defaults,
                                     include_source: IncludeDirective::IncludeDir,
                                 },
                             )
@@ -989,12 +1367,9 @@ fn analyze(
                     }
                 },
 
-                Err(basic_parser::Status::Fatal(pos, message)) => diagnostics.push(Error {
-                    source: Some(cur_path.to_owned()),
-                    location: Some(pos),
-                    message,
-                }),
-                Err(_) => panic!("internal parser error"),
+                Err(status) => {
+                    diagnostics.push(Error::parse_error(Some(cur_path.to_owned()), status))
+                }
             }
         }
     }
@@ -1020,6 +1395,7 @@ fn analyze(
         sudoers,
         &mut diagnostics,
         &mut IncludeState::Allowed(0),
+        &mut Settings::default(),
     );
 
     let alias = &mut result.aliases;
@@ -1051,32 +1427,35 @@ fn sanitize_alias_table<T>(table: &Vec<Def<T>>, diagnostics: &mut Vec<Error>) ->
     }
 
     impl<T> Visitor<'_, T> {
-        fn complain(&mut self, text: String) {
+        // the definition at `pos` is the best location we have for any problem with it
+        fn complain(&mut self, pos: usize, text: String) {
+            let Def(_, _, origin) = &self.table[pos];
             self.diagnostics.push(Error {
-                source: None,
-                location: None,
+                source: origin.file.as_deref().map(Path::to_path_buf),
+                location: Some(origin.span),
                 message: text,
+                unknown_setting: false,
             })
         }
 
         fn visit(&mut self, pos: usize) {
             if self.seen.insert(pos) {
-                let Def(_, members) = &self.table[pos];
+                let Def(_, members, _) = &self.table[pos];
                 for elem in members {
                     let Meta::Alias(name) = remqualify(elem) else {
                         continue;
                     };
-                    let Some(dependency) = self.table.iter().position(|Def(id, _)| id == name)
+                    let Some(dependency) = self.table.iter().position(|Def(id, ..)| id == name)
                     else {
-                        self.complain(format!("undefined alias: '{name}'"));
+                        self.complain(pos, format!("undefined alias: '{name}'"));
                         continue;
                     };
                     self.visit(dependency);
                 }
                 self.order.push(pos);
             } else if !self.order.contains(&pos) {
-                let Def(id, _) = &self.table[pos];
-                self.complain(format!("recursive alias: '{id}'"));
+                let Def(id, ..) = &self.table[pos];
+                self.complain(pos, format!("recursive alias: '{id}'"));
             }
         }
     }
@@ -1089,9 +1468,9 @@ fn sanitize_alias_table<T>(table: &Vec<Def<T>>, diagnostics: &mut Vec<Error>) ->
     };
 
     let mut dupe = HashSet::new();
-    for (i, Def(name, _)) in table.iter().enumerate() {
+    for (i, Def(name, ..)) in table.iter().enumerate() {
         if !dupe.insert(name) {
-            visitor.complain(format!("multiple occurrences of '{name}'"));
+            visitor.complain(i, format!("multiple occurrences of '{name}'"));
         } else {
             visitor.visit(i);
         }
@@ -4,17 +4,21 @@
 //! The trait definitions can be part of some global crate in the future, if we support more
 //! than just the sudoers file.
 
-use super::{Judgement, Sudoers};
+use super::{Judgement, Sudoers, VerboseMatch};
 use crate::common::{
-    HARDENED_ENUM_VALUE_0, HARDENED_ENUM_VALUE_1, HARDENED_ENUM_VALUE_2, SudoPath,
+    HARDENED_ENUM_VALUE_0, HARDENED_ENUM_VALUE_1, HARDENED_ENUM_VALUE_2, HARDENED_ENUM_VALUE_3,
+    HARDENED_ENUM_VALUE_4, SudoPath,
 };
 use crate::defaults::enums;
-use crate::exec::Umask;
-use crate::sudoers::ast::{EnvironmentControl, ExecControl, Tag};
-use crate::system::{Hostname, User};
+use crate::exec::{HomeFallback, Umask};
+use crate::sudoers::ast::{Authenticate, EnvironmentControl, ExecControl, Tag};
+use crate::system::{Hostname, User, interface::UnixUser};
 use std::collections::HashSet;
 use std::time::Duration;
-use std::{ffi::OsString, path::PathBuf};
+use std::{
+    ffi::{OsString, c_int},
+    path::{Path, PathBuf},
+};
 
 #[must_use]
 #[cfg_attr(test, derive(Debug, PartialEq))]
@@ -24,6 +28,19 @@ pub enum Authorization<T = ()> {
     Forbidden = HARDENED_ENUM_VALUE_1,
 }
 
+/// The first aspect of a request for which no rule in the sudoers file matched; in that order,
+/// the invoking user, the host, the target user and group, and the command.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum DenialReason {
+    NoMatchingUser = HARDENED_ENUM_VALUE_0,
+    HostMismatch = HARDENED_ENUM_VALUE_1,
+    RunAsMismatch = HARDENED_ENUM_VALUE_2,
+    CommandNotPermitted = HARDENED_ENUM_VALUE_3,
+    /// The command was matched, but the last match was a negated (`!`) one.
+    CommandForbidden = HARDENED_ENUM_VALUE_4,
+}
+
 #[cfg_attr(test, derive(Debug, PartialEq))]
 #[must_use]
 pub struct Authentication {
@@ -32,18 +49,34 @@ pub struct Authentication {
     pub allowed_attempts: u16,
     pub prior_validity: Duration,
     pub pwfeedback: bool,
+    /// Respond to a wrong password with an insult
+    pub insults: bool,
+    pub passprompt_override: bool,
     pub password_timeout: Option<Duration>,
     pub noninteractive_auth: bool,
     pub scope: AuthenticationScope,
+    pub lecture: Lecture,
+    /// Where to record that a user was lectured, if not in the default directory
+    pub lecture_status_dir: Option<PathBuf>,
+    /// Where session records are kept, if not in the default directory
+    pub timestamp_dir: Option<PathBuf>,
+    pub askpass: Option<PathBuf>,
 }
 
 impl super::Settings {
     pub(super) fn to_auth(&self, tag: &Tag) -> Authentication {
         Authentication {
-            must_authenticate: tag.needs_passwd(),
+            must_authenticate: match tag.authenticate {
+                // without a PASSWD or NOPASSWD tag, the authenticate setting decides
+                Authenticate::None => self.authenticate(),
+                Authenticate::Passwd => true,
+                Authenticate::Nopasswd => false,
+            },
             allowed_attempts: self.passwd_tries().try_into().unwrap(),
             prior_validity: Duration::from_secs(self.timestamp_timeout()),
             pwfeedback: self.pwfeedback(),
+            insults: self.insults(),
+            passprompt_override: self.passprompt_override(),
             password_timeout: match self.passwd_timeout() {
                 0 => None,
                 timeout => Some(Duration::from_secs(timeout)),
@@ -51,8 +84,17 @@ impl super::Settings {
             scope: match self.timestamp_type() {
                 enums::timestamp_type::tty => AuthenticationScope::Tty,
                 enums::timestamp_type::ppid => AuthenticationScope::PPid,
+                enums::timestamp_type::global => AuthenticationScope::Global,
+            },
+            lecture: match self.lecture() {
+                enums::lecture::never => Lecture::Never,
+                enums::lecture::once => Lecture::Once(self.lecture_file().map(PathBuf::from)),
+                enums::lecture::always => Lecture::Always(self.lecture_file().map(PathBuf::from)),
             },
+            lecture_status_dir: self.lecture_status_dir().map(PathBuf::from),
+            timestamp_dir: self.timestamp_dir().map(PathBuf::from),
             noninteractive_auth: self.noninteractive_auth(),
+            askpass: self.askpass().map(PathBuf::from),
             credential: if self.rootpw() {
                 AuthenticatingUser::Root
             } else if self.targetpw() {
@@ -72,10 +114,26 @@ pub struct Restrictions<'a> {
     pub noexec: bool,
     pub env_keep: &'a HashSet<String>,
     pub env_check: &'a HashSet<String>,
+    pub env_delete: &'a HashSet<String>,
+    pub env_file: Option<&'a str>,
     pub chdir: DirChange,
+    pub chroot: DirChange,
     pub path: Option<&'a str>,
     pub umask: Umask,
+    pub closefrom_override: bool,
+    /// Inherited file descriptors that the command may receive, in ascending order
+    pub preserve_fds: Vec<c_int>,
+    pub home_fallback: HomeFallback,
+    /// Whether HOME is set for the target user even if the environment is preserved (like -H)
+    pub always_set_home: bool,
+    pub command_line_max: Option<usize>,
+    /// How long the command may run before it is terminated
+    pub command_timeout: Option<Duration>,
+    /// Whether the user may choose a (shorter) command timeout themselves
+    pub user_command_timeouts: bool,
     pub log: Logging,
+    /// The rule that allowed the command, if it should be made known to the command
+    pub rule: Option<String>,
     #[cfg(feature = "apparmor")]
     pub apparmor_profile: Option<String>,
 }
@@ -96,6 +154,15 @@ pub enum Logging {
     Disabled = HARDENED_ENUM_VALUE_1,
 }
 
+#[cfg_attr(test, derive(Debug, PartialEq))]
+pub struct LogFile<'a> {
+    pub path: &'a Path,
+    /// Include the year in the timestamp of every line
+    pub year: bool,
+    /// Include the name of the host in every line
+    pub host: bool,
+}
+
 #[cfg_attr(test, derive(Debug, PartialEq))]
 #[repr(u32)]
 pub enum AuthenticatingUser {
@@ -109,9 +176,21 @@ pub enum AuthenticatingUser {
 /// The scope for the authentication being re-used:
 /// - Tty: valid for all future invocations in this TTY
 /// - PPid: valid for all future invocations under the current parent
+/// - Global: valid for all future invocations by the same user
 pub enum AuthenticationScope {
     Tty = HARDENED_ENUM_VALUE_0,
     PPid = HARDENED_ENUM_VALUE_1,
+    Global = HARDENED_ENUM_VALUE_2,
+}
+
+#[cfg_attr(test, derive(Debug, PartialEq))]
+#[repr(u32)]
+/// Whether to show a lecture before asking for a password, and which one; the standard lecture
+/// is used if no file was configured.
+pub enum Lecture {
+    Never = HARDENED_ENUM_VALUE_0,
+    Once(Option<PathBuf>) = HARDENED_ENUM_VALUE_1,
+    Always(Option<PathBuf>) = HARDENED_ENUM_VALUE_2,
 }
 
 impl Judgement {
@@ -138,6 +217,8 @@ impl Judgement {
                     },
                     env_keep: self.settings.env_keep(),
                     env_check: self.settings.env_check(),
+                    env_delete: self.settings.env_delete(),
+                    env_file: self.settings.env_file(),
                     chdir: match tag.cwd.clone().or_else(|| {
                         // a `runcwd` default acts as the working directory when no explicit CWD was set
                         self.settings
@@ -148,6 +229,11 @@ impl Judgement {
                         Some(super::ChDir::Any) => DirChange::Any,
                         Some(super::ChDir::Path(path)) => DirChange::Strict(Some(path)),
                     },
+                    chroot: match tag.chroot.clone() {
+                        None => DirChange::Strict(None),
+                        Some(super::ChDir::Any) => DirChange::Any,
+                        Some(super::ChDir::Path(path)) => DirChange::Strict(Some(path)),
+                    },
                     path: self.settings.secure_path(),
                     umask: {
                         let mask = self
@@ -163,11 +249,50 @@ impl Judgement {
                             Umask::Extend(mask)
                         }
                     },
+                    closefrom_override: self.settings.closefrom_override(),
+                    preserve_fds: {
+                        // the IO streams are always passed on; anything that is not a file
+                        // descriptor is ignored
+                        let mut fds = self
+                            .settings
+                            .preserve_fds()
+                            .iter()
+                            .filter_map(|fd| fd.parse::<c_int>().ok())
+                            .filter(|&fd| fd > libc::STDERR_FILENO)
+                            .collect::<Vec<_>>();
+                        fds.sort_unstable();
+                        fds.dedup();
+                        fds
+                    },
+                    home_fallback: match self.settings.inaccessible_home() {
+                        enums::inaccessible_home::stay => HomeFallback::Stay,
+                        enums::inaccessible_home::root => HomeFallback::Root,
+                        enums::inaccessible_home::error => HomeFallback::Error,
+                    },
+                    always_set_home: self.settings.always_set_home(),
+                    command_line_max: match self.settings.command_line_max() {
+                        0 => None,
+                        max => {
+                            Some(max.try_into().expect(
+                                "the command_line_max range should have prevented overflow",
+                            ))
+                        }
+                    },
+                    command_timeout: match self.settings.command_timeout() {
+                        0 => None,
+                        seconds => Some(Duration::from_secs(seconds)),
+                    },
+                    user_command_timeouts: self.settings.user_command_timeouts(),
                     log: if self.settings.log_allowed() {
                         Logging::Auth
                     } else {
                         Logging::Disabled
                     },
+                    rule: tag
+                        .origin
+                        .as_ref()
+                        .filter(|_| self.settings.set_sudo_rule())
+                        .map(|origin| origin.to_string()),
                     #[cfg(feature = "apparmor")]
                     apparmor_profile: tag
                         .apparmor_profile
@@ -181,6 +306,35 @@ impl Judgement {
         }
     }
 
+    /// If the request was denied, the reason why; this is only available for requests that
+    /// were checked using [`Sudoers::check`].
+    pub fn denial_reason(&self) -> Option<DenialReason> {
+        self.denial
+    }
+
+    /// The long format of `sudo -l command` for an allowed request, describing the options of
+    /// the rule that allowed it.
+    pub fn verbose_match<'a>(
+        &'a self,
+        run_as_user: &'a str,
+        command: &'a str,
+    ) -> Option<VerboseMatch<'a>> {
+        self.flags.as_ref().map(|tag| VerboseMatch {
+            tag,
+            run_as_user,
+            command,
+        })
+    }
+
+    /// The file to which log messages should also be written, if any.
+    pub fn log_file(&self) -> Option<LogFile<'_>> {
+        self.settings.logfile().map(|path| LogFile {
+            path: Path::new(path),
+            year: self.settings.log_year(),
+            host: self.settings.log_host(),
+        })
+    }
+
     pub(crate) fn preferred_editor(&self) -> (PathBuf, Vec<OsString>) {
         // if no editor could be selected, fall back to /bin/vi;
         // note that /bin/vi is also likely to have been tried as part of
@@ -200,6 +354,42 @@ impl Sudoers {
         self.specify_host_user_runas(on_host, current_user, Some(target_user));
         self.settings.secure_path()
     }
+
+    /// The user that commands are run as if neither a target user nor group was requested
+    ///
+    /// This does not settle the other settings yet: the Defaults lines for the target user
+    /// still have to be applied in order, once the target user is known.
+    pub fn runas_default<U: UnixUser + PartialEq<U>>(
+        &self,
+        on_host: &Hostname,
+        current_user: &U,
+    ) -> String {
+        let mut settings = self.settings.clone();
+        self.apply_host_user_runas(&mut settings, on_host, current_user, None);
+        settings.runas_default().to_string()
+    }
+
+    /// Where session records are kept, if not in the default directory; this is needed to
+    /// remove them without checking any permission.
+    pub fn timestamp_dir<U: UnixUser + PartialEq<U>>(
+        &mut self,
+        on_host: &Hostname,
+        current_user: &U,
+    ) -> Option<&str> {
+        self.specify_host_user_runas(on_host, current_user, None);
+        self.settings.timestamp_dir()
+    }
+
+    /// Whether commands must be given as an absolute path, instead of being searched for
+    pub fn require_absolute_command(
+        &mut self,
+        on_host: &Hostname,
+        current_user: &User,
+        target_user: &User,
+    ) -> bool {
+        self.specify_host_user_runas(on_host, current_user, Some(target_user));
+        self.settings.require_absolute_command()
+    }
 }
 
 #[cfg(test)]
@@ -234,9 +424,15 @@ mod test {
                 prior_validity: Duration::from_secs(15 * 60),
                 credential: AuthenticatingUser::InvokingUser,
                 pwfeedback: true,
+                insults: false,
+                passprompt_override: false,
                 noninteractive_auth: false,
                 password_timeout: Some(Duration::from_secs(300)),
                 scope: AuthenticationScope::Tty,
+                lecture: Lecture::Never,
+                lecture_status_dir: None,
+                timestamp_dir: None,
+                askpass: None,
             },
         );
 
@@ -253,9 +449,15 @@ mod test {
                 prior_validity: Duration::from_secs(15 * 60),
                 credential: AuthenticatingUser::InvokingUser,
                 pwfeedback: true,
+                insults: false,
+                passprompt_override: false,
                 noninteractive_auth: false,
                 password_timeout: Some(Duration::from_secs(300)),
                 scope: AuthenticationScope::Tty,
+                lecture: Lecture::Never,
+                lecture_status_dir: None,
+                timestamp_dir: None,
+                askpass: None,
             },
         );
         assert_eq!(restrictions, restrictions2);
@@ -3,6 +3,7 @@ use std::ffi::CStr;
 use super::ast;
 use super::char_stream::CharStream;
 use super::*;
+use crate::system::Group;
 use basic_parser::{Status, parse_eval, parse_lines, parse_string};
 
 impl<T> Qualified<T> {
@@ -87,6 +88,11 @@ impl UnixUser for Named {
         GroupId::new(dummy_cksum(self.0)) == gid
     }
 
+    fn in_netgroup(&self, netgroup: &CStr) -> bool {
+        // every user is only a member of the netgroup "<name>_ng"
+        netgroup.to_str().unwrap() == format!("{}_ng", self.0)
+    }
+
     fn is_root(&self) -> bool {
         self.0 == "root"
     }
@@ -105,6 +111,56 @@ impl UnixGroup for Named {
     }
 }
 
+/// A user whose groups are given explicitly, so that group matching can be tested against
+/// real gids and group names without consulting the user and group databases of the system.
+#[derive(PartialEq)]
+struct KnownUser {
+    uid: UserId,
+    name: String,
+    /// The primary group of the user
+    group: Group,
+    /// The supplementary groups of the user
+    groups: Vec<Group>,
+    /// The netgroups that the user is a member of
+    netgroups: Vec<String>,
+}
+
+impl KnownUser {
+    fn all_groups(&self) -> impl Iterator<Item = &Group> {
+        std::iter::once(&self.group).chain(&self.groups)
+    }
+}
+
+impl UnixUser for KnownUser {
+    fn has_name(&self, name: &str) -> bool {
+        self.name == name
+    }
+    fn has_uid(&self, uid: UserId) -> bool {
+        self.uid == uid
+    }
+    fn is_root(&self) -> bool {
+        self.has_uid(UserId::ROOT)
+    }
+    fn in_group_by_name(&self, name_c: &CStr) -> bool {
+        let name = name_c.to_str().ok();
+        self.all_groups()
+            .any(|group| group.name.is_some() && group.name.as_deref() == name)
+    }
+    fn in_group_by_gid(&self, gid: GroupId) -> bool {
+        self.all_groups().any(|group| group.gid == gid)
+    }
+    fn in_netgroup(&self, netgroup: &CStr) -> bool {
+        let netgroup = netgroup.to_str().ok();
+        self.netgroups
+            .iter()
+            .any(|name| Some(name.as_str()) == netgroup)
+    }
+    type Group = Group;
+    fn group(&self) -> Group {
+        self.group.clone()
+    }
+}
+
 macro_rules! request {
     ($user:ident) => {
         (&Named(stringify!($user)), &Named(stringify!($user)))
@@ -136,6 +192,29 @@ fn try_parse_line(s: &str) -> Option<Sudo> {
         .ok()
 }
 
+/// Analyze a sudoers file that consists of `lines`, ignoring any errors
+fn fake_sudoers(lines: &[&str]) -> Sudoers {
+    analyze(Path::new("/etc/fakesudoers"), sudoer![&lines.join("\n")]).0
+}
+
+/// Check whether `user` may run `command` with `arguments` as `target` on the host "server"
+fn check_request<User: UnixUser + PartialEq<User>, Group: UnixGroup>(
+    sudoers: &mut Sudoers,
+    user: &User,
+    (target, group): (&User, &Group),
+    command: &str,
+    arguments: &[&str],
+) -> Judgement {
+    let arguments = arguments.iter().map(OsString::from).collect::<Vec<_>>();
+    let request = Request {
+        user: target,
+        group,
+        command: Path::new(command),
+        arguments: &arguments,
+    };
+    sudoers.check(user, &system::Hostname::fake("server"), request)
+}
+
 #[test]
 fn ambiguous_spec() {
     assert!(parse_eval::<ast::Sudo>("marc, User_Alias ALL = ALL").is_spec());
@@ -191,14 +270,33 @@ fn permission_test() {
     pass!(["user ALL=(ALL:ALL) CWD=/ /bin/foo, CWD=* /bin/bar"], "user" => root(), "server"; "/bin/bar" => [cwd: Some(ChDir::Any)]);
     pass!(["user ALL=(ALL:ALL) CWD=/bin CWD=* /bin/foo"], "user" => root(), "server"; "/bin/foo" => [cwd: Some(ChDir::Any)]);
     pass!(["user ALL=(ALL:ALL) CWD=/usr/bin NOPASSWD: /bin/foo"], "user" => root(), "server"; "/bin/foo" => [authenticate: Authenticate::Nopasswd, cwd: Some(ChDir::Path("/usr/bin".into()))]);
+    pass!(["user ALL=(ALL:ALL) CHROOT=/srv/jail /bin/foo, /bin/bar"], "user" => root(), "server"; "/bin/bar" => [chroot: Some(ChDir::Path("/srv/jail".into()))]);
+    pass!(["user ALL=(ALL:ALL) CHROOT=* CWD=/ /bin/foo"], "user" => root(), "server"; "/bin/foo" => [chroot: Some(ChDir::Any), cwd: Some(ChDir::Path("/".into()))]);
     //note: original sudo does not allow the below
     pass!(["user ALL=(ALL:ALL) NOPASSWD: CWD=/usr/bin /bin/foo"], "user" => root(), "server"; "/bin/foo" => [authenticate: Authenticate::Nopasswd, cwd: Some(ChDir::Path("/usr/bin".into()))]);
 
+    // sudoedit_authenticate makes NOPASSWD not apply to sudoedit
+    pass!(["user ALL=(ALL:ALL) NOPASSWD: ALL"], "user" => root(), "server"; "sudoedit /etc/motd" => [authenticate: Authenticate::Nopasswd]);
+    pass!(["Defaults sudoedit_authenticate", "user ALL=(ALL:ALL) NOPASSWD: ALL"], "user" => root(), "server"; "sudoedit /etc/motd" => [authenticate: Authenticate::Passwd]);
+    pass!(["Defaults sudoedit_authenticate", "user ALL=(ALL:ALL) NOPASSWD: ALL"], "user" => root(), "server"; "/bin/foo" => [authenticate: Authenticate::Nopasswd]);
+    pass!(["Defaults sudoedit_authenticate", "root ALL=(ALL:ALL) NOPASSWD: ALL"], "root" => root(), "server"; "sudoedit /etc/motd" => [authenticate: Authenticate::Nopasswd]);
+
     pass!(["user ALL=/bin/e##o"], "user" => root(), "vm"; "/bin/e");
     SYNTAX!(["ALL ALL=(ALL) /bin/\n/echo"]);
 
     pass!(["user server=(ALL:ALL) ALL"], "user" => root(), "server"; "/bin/hello");
     FAIL!(["user laptop=(ALL:ALL) ALL"], "user" => root(), "server"; "/bin/hello");
+    pass!(["user Server=(ALL:ALL) ALL"], "user" => root(), "server"; "/bin/hello");
+    pass!(["user server=(ALL:ALL) ALL"], "user" => root(), "SERVER"; "/bin/hello");
+    pass!(["Host_Alias WEB=WebServer", "user WEB=(ALL:ALL) ALL"], "user" => root(), "webserver"; "/bin/hello");
+    FAIL!(["user ALL,!Laptop=(ALL:ALL) ALL"], "user" => root(), "laptop"; "/bin/hello");
+    FAIL!(["User server=(ALL:ALL) ALL"], "user" => root(), "server"; "/bin/hello");
+
+    pass!(["user ALL,!laptop=(ALL:ALL) ALL"], "user" => root(), "server"; "/bin/hello");
+    FAIL!(["user ALL,!laptop=(ALL:ALL) ALL"], "user" => root(), "laptop"; "/bin/hello");
+    pass!(["user !laptop,ALL=(ALL:ALL) ALL"], "user" => root(), "laptop"; "/bin/hello");
+    FAIL!(["user ALL,!laptop,!server=(ALL:ALL) ALL"], "user" => root(), "server"; "/bin/hello");
+    pass!(["user ALL,!laptop,server=(ALL:ALL) ALL"], "user" => root(), "server"; "/bin/hello");
 
     pass!(["user ALL=!/bin/hello", "user ALL=/bin/hello"], "user" => root(), "server"; "/bin/hello");
     FAIL!(["user ALL=/bin/hello", "user ALL=!/bin/hello"], "user" => root(), "server"; "/bin/hello");
@@ -211,6 +309,14 @@ fn permission_test() {
         pass!([alias,"GROUP ALL=/bin/hello"], "user2" => root(), "server"; "/bin/hello");
         FAIL!([alias,"GROUP ALL=/bin/hello"], "user3" => root(), "server"; "/bin/hello");
     }
+    for alias in [
+        "Host_Alias MACHINE=laptop, server",
+        "Host_Alias MACHINE=ALL,!desktop",
+    ] {
+        pass!([alias,"user MACHINE=/bin/hello"], "user" => root(), "laptop"; "/bin/hello");
+        pass!([alias,"user MACHINE=/bin/hello"], "user" => root(), "server"; "/bin/hello");
+        FAIL!([alias,"user MACHINE=/bin/hello"], "user" => root(), "desktop"; "/bin/hello");
+    }
     pass!(["user ALL=/bin/hello arg"], "user" => root(), "server"; "/bin/hello arg");
     pass!(["user ALL=/bin/hello  arg"], "user" => root(), "server"; "/bin/hello arg");
     pass!(["user ALL=/bin/hello arg"], "user" => root(), "server"; "/bin/hello  arg");
@@ -233,6 +339,15 @@ fn permission_test() {
     pass!(["user ALL=/bin/hel* *"], "user" => root(), "server"; "/bin/help me please");
     pass!(["user ALL=/bin/hel* me *"], "user" => root(), "server"; "/bin/help me please");
     pass!(["user ALL=/bin/hel* me please *"], "user" => root(), "server"; "/bin/help me please");
+    // like fnmatch with FNM_PATHNAME, a wildcard does not match a '/'
+    pass!(["user ALL=/opt/scripts/*"], "user" => root(), "server"; "/opt/scripts/backup");
+    FAIL!(["user ALL=/opt/scripts/*"], "user" => root(), "server"; "/opt/scripts/old/backup");
+    FAIL!(["user ALL=/opt/scr*"], "user" => root(), "server"; "/opt/scripts/backup");
+    pass!(["user ALL=/usr/lib/*/helper"], "user" => root(), "server"; "/usr/lib/foo/helper");
+    FAIL!(["user ALL=/usr/lib/*/helper"], "user" => root(), "server"; "/usr/lib/foo/bar/helper");
+    FAIL!(["user ALL=/usr/lib?helper"], "user" => root(), "server"; "/usr/lib/helper");
+    pass!(["Cmnd_Alias SCRIPTS=/opt/scripts/*", "user ALL=SCRIPTS"], "user" => root(), "server"; "/opt/scripts/backup");
+    FAIL!(["Cmnd_Alias SCRIPTS=/opt/scripts/*", "user ALL=SCRIPTS"], "user" => root(), "server"; "/opt/scripts/old/backup");
 
     pass!(["user ALL=(ALL:ALL) /bin/foo"], "user" => root(), "server"; "/bin/foo" => [authenticate: Authenticate::None]);
     pass!(["root ALL=(ALL:ALL) /bin/foo"], "root" => root(), "server"; "/bin/foo" => [authenticate: Authenticate::Nopasswd]);
@@ -273,6 +388,26 @@ fn permission_test() {
     // slightly counterintuitive test which simulates only -g being passed
     pass!(["user ALL=(sudo:sudo) /bin/foo"], "user" => request! { user, sudo }, "server"; "/bin/foo");
 
+    // a group-only request: the target user is the invoking user, only the group changes
+    pass!(["user ALL=(:sudo) /bin/foo"], "user" => request! { user, sudo }, "server"; "/bin/foo");
+    pass!(["user ALL=(ALL:sudo) /bin/foo"], "user" => request! { user, sudo }, "server"; "/bin/foo");
+    FAIL!(["user ALL=(:sudo) /bin/foo"], "user" => request! { user, wheel }, "server"; "/bin/foo");
+    FAIL!(["user ALL=(ALL:sudo) /bin/foo"], "user" => request! { user, wheel }, "server"; "/bin/foo");
+    FAIL!(["user ALL=(ALL) /bin/foo"], "user" => request! { user, sudo }, "server"; "/bin/foo");
+    pass!(["user ALL=(root:user) /bin/foo"], "user" => request! { user, user }, "server"; "/bin/foo");
+    FAIL!(["user ALL=(root) /bin/foo"], "user" => request! { user, user }, "server"; "/bin/foo");
+
+    // runas group lists can mix names and numerical group IDs
+    pass!(["user ALL=(ALL:wheel,#1466) /bin/foo"], "user" => request! { root, wheel }, "server"; "/bin/foo");
+    pass!(["user ALL=(ALL:wheel,#1466) /bin/foo"], "user" => request! { root, user }, "server"; "/bin/foo");
+    FAIL!(["user ALL=(ALL:wheel,#1466) /bin/foo"], "user" => request! { root, other }, "server"; "/bin/foo");
+    pass!(["user ALL=(ALL:#1466,wheel) /bin/foo"], "user" => request! { root, wheel }, "server"; "/bin/foo");
+    pass!(["user ALL=(ALL:#1466,wheel) /bin/foo"], "user" => request! { root, user }, "server"; "/bin/foo");
+    FAIL!(["user ALL=(ALL:#1466,wheel) /bin/foo"], "user" => request! { root, other }, "server"; "/bin/foo");
+    FAIL!(["user ALL=(ALL:ALL,!#1466,!wheel) /bin/foo"], "user" => request! { root, user }, "server"; "/bin/foo");
+    FAIL!(["user ALL=(ALL:ALL,!#1466,!wheel) /bin/foo"], "user" => request! { root, wheel }, "server"; "/bin/foo");
+    pass!(["user ALL=(ALL:ALL,!#1466,!wheel) /bin/foo"], "user" => request! { root, other }, "server"; "/bin/foo");
+
     // tests with multiple runas specs
     pass!(["user ALL=(root) /bin/ls, (sudo) /bin/true"], "user" => request! { root }, "server"; "/bin/ls");
     pass!(["user ALL=(root) NOPASSWD: /bin/ls, (sudo) /bin/true"], "user" => request! { sudo }, "server"; "/bin/true" => [authenticate: Authenticate::Nopasswd]);
@@ -300,6 +435,16 @@ fn permission_test() {
     pass!(["Cmd_Alias WHAT=/bin/dd,/bin/rm","user ALL=WHAT"], "user" => root(), "laptop"; "/bin/dd");
     FAIL!(["Cmnd_Alias WHAT=/bin/dd,/bin/rm","user ALL=WHAT"], "user" => root(), "desktop"; "/bin/bash");
 
+    pass!(["Cmnd_Alias FOO=/bin/hello arg","user ALL=FOO"], "user" => root(), "server"; "/bin/hello arg");
+    FAIL!(["Cmnd_Alias FOO=/bin/hello arg","user ALL=FOO"], "user" => root(), "server"; "/bin/hello");
+    FAIL!(["Cmnd_Alias FOO=/bin/hello arg","user ALL=FOO"], "user" => root(), "server"; "/bin/hello arg more");
+    FAIL!(["Cmnd_Alias FOO=/bin/hello \"\"","user ALL=FOO"], "user" => root(), "server"; "/bin/hello arg");
+    pass!(["Cmnd_Alias FOO=/bin/hello \"\"","user ALL=FOO"], "user" => root(), "server"; "/bin/hello");
+    pass!(["Cmnd_Alias FOO=/bin/hello arg","Cmnd_Alias BAR=FOO","user ALL=BAR"], "user" => root(), "server"; "/bin/hello arg");
+    FAIL!(["Cmnd_Alias FOO=/bin/hello arg","Cmnd_Alias BAR=FOO","user ALL=BAR"], "user" => root(), "server"; "/bin/hello other");
+    FAIL!(["Cmnd_Alias FOO=/bin/hello arg","user ALL=ALL,!FOO"], "user" => root(), "server"; "/bin/hello arg");
+    pass!(["Cmnd_Alias FOO=/bin/hello arg","user ALL=ALL,!FOO"], "user" => root(), "server"; "/bin/hello other");
+
     pass!(["User_Alias A=B","User_Alias B=user","A ALL=ALL"], "user" => root(), "vm"; "/bin/ls");
     pass!(["Host_Alias A=B","Host_Alias B=vm","ALL A=ALL"], "user" => root(), "vm"; "/bin/ls");
     pass!(["Cmnd_Alias A=B","Cmnd_Alias B=/bin/ls","ALL ALL=A"], "user" => root(), "vm"; "/bin/ls");
@@ -315,6 +460,12 @@ fn permission_test() {
     // test the less-intuitive "substitution-like" alias mechanism
     FAIL!(["User_Alias FOO=!user", "ALL, FOO ALL=ALL"], "user" => root(), "vm"; "/bin/ls");
     pass!(["User_Alias FOO=!user", "!FOO ALL=ALL"], "user" => root(), "vm"; "/bin/ls");
+    FAIL!(["Host_Alias FOO=!vm", "user ALL, FOO=ALL"], "user" => root(), "vm"; "/bin/ls");
+    pass!(["Host_Alias FOO=!vm", "user !FOO=ALL"], "user" => root(), "vm"; "/bin/ls");
+    pass!(["Host_Alias MACHINE=ALL,!laptop","user !MACHINE=ALL"], "user" => root(), "laptop"; "/bin/ls");
+    FAIL!(["Host_Alias MACHINE=ALL,!laptop","user !MACHINE=ALL"], "user" => root(), "server"; "/bin/ls");
+    FAIL!(["Host_Alias MACHINE=laptop,server","user ALL,!MACHINE=ALL"], "user" => root(), "laptop"; "/bin/ls");
+    pass!(["Host_Alias MACHINE=laptop,server","user ALL,!MACHINE=ALL"], "user" => root(), "desktop"; "/bin/ls");
 
     // quoting
     pass!(["a\\,b ALL=ALL"], "a,b" => request! { root, root }, "server"; "/bin/foo");
@@ -421,6 +572,38 @@ fn default_set_test() {
     assert!(parse_string::<Sudo>("Defaults !runcwd").is_ok());
 }
 
+#[test]
+fn negated_list_is_emptied_in_its_scope() {
+    let sudoers = || {
+        fake_sudoers(&[
+            "Defaults:user !env_keep",
+            "Defaults:user env_keep += FOO",
+            "Defaults:user !env_check",
+        ])
+    };
+
+    let mut other_sudoers = sudoers();
+    other_sudoers.specify_host_user_runas(
+        &system::Hostname::fake("host"),
+        &Named("other"),
+        Some(&Named("root")),
+    );
+    assert!(other_sudoers.settings.env_keep().contains("COLORS"));
+    assert!(other_sudoers.settings.env_check().contains("TZ"));
+
+    let mut user_sudoers = sudoers();
+    user_sudoers.specify_host_user_runas(
+        &system::Hostname::fake("host"),
+        &Named("user"),
+        Some(&Named("root")),
+    );
+    assert_eq!(
+        user_sudoers.settings.env_keep(),
+        &["FOO".to_string()].into()
+    );
+    assert!(user_sudoers.settings.env_check().is_empty());
+}
+
 #[test]
 fn default_multi_test() {
     let (mut sudoers, _) = analyze(
@@ -490,12 +673,527 @@ fn list_does_not_take_args() {
     parse_eval::<ast::CommandSpec>("list /etc/tmux.conf");
 }
 
+#[test]
+fn quoted_command_arguments() {
+    let args = |spec: &str| {
+        let CommandSpec(_, Qualified::Allow(Meta::Only((_, args)))) =
+            parse_eval::<ast::CommandSpec>(spec)
+        else {
+            panic!();
+        };
+        match args {
+            Args::Exact(args) => (true, args.into_vec()),
+            Args::Prefix(args) => (false, args.into_vec()),
+        }
+    };
+
+    assert_eq!(
+        args(r#"/bin/echo "hello world""#),
+        (true, vec!["hello world".into()])
+    );
+    assert_eq!(
+        args(r"/bin/echo hello\ world"),
+        (true, vec!["hello world".into()])
+    );
+    assert_eq!(
+        args(r#"/bin/echo "a  b" c"#),
+        (true, vec!["a  b".into(), "c".into()])
+    );
+    assert_eq!(
+        args(r#"/bin/echo --name="a b" *"#),
+        (false, vec!["--name=a b".into()])
+    );
+    assert_eq!(
+        args(r#"/bin/echo "" foo"#),
+        (true, vec!["".into(), "foo".into()])
+    );
+    assert_eq!(args(r#"/bin/echo """#), (true, vec![]));
+    assert_eq!(args(r"/bin/echo a\,b"), (true, vec!["a,b".into()]));
+    assert!(try_parse_line(r#"user ALL = /bin/echo "hello world"#).is_none());
+    assert!(try_parse_line(r#"user ALL = /bin/echo "*""#).is_none());
+
+    let judge = |arguments: &[&str]| {
+        let mut sudoers = fake_sudoers(&[r#"user ALL = /usr/bin/echo "hello world""#]);
+        check_request(
+            &mut sudoers,
+            &Named("user"),
+            request!(root),
+            "/usr/bin/echo",
+            arguments,
+        )
+        .flags
+        .is_some()
+    };
+    assert!(judge(&["hello world"]));
+    assert!(!judge(&["hello", "world"]));
+
+    // `sudo -l` shows such arguments the way they were written
+    let sudoers = fake_sudoers(&[r"user ALL = /usr/bin/echo hello\ world"]);
+    let entries = sudoers
+        .matching_entries(&Named("user"), &system::Hostname::fake("server"))
+        .map(|entry| entry.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(entries, [r#"    (root) /usr/bin/echo "hello world""#]);
+}
+
+#[test]
+fn list_entries_for_supplied_host() {
+    let sudoers = fake_sudoers(&[
+        "Host_Alias REMOTE = server, backup",
+        "user local = (root) /usr/local/bin/foo",
+        "user REMOTE = (root) /usr/local/bin/bar",
+        "user ALL = (root) /usr/local/bin/baz",
+    ]);
+
+    let entries = |host: &str| {
+        sudoers
+            .matching_entries(&Named("user"), &system::Hostname::fake(host))
+            .map(|entry| entry.to_string())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        entries("local"),
+        [
+            "    (root) /usr/local/bin/foo",
+            "    (root) /usr/local/bin/baz"
+        ]
+    );
+    assert_eq!(
+        entries("server"),
+        [
+            "    (root) /usr/local/bin/bar",
+            "    (root) /usr/local/bin/baz"
+        ]
+    );
+    assert_eq!(
+        entries("backup"),
+        [
+            "    (root) /usr/local/bin/bar",
+            "    (root) /usr/local/bin/baz"
+        ]
+    );
+    assert_eq!(entries("elsewhere"), ["    (root) /usr/local/bin/baz"]);
+}
+
+#[test]
+fn list_entries_keep_alias_arguments() {
+    let sudoers = fake_sudoers(&[
+        "Cmnd_Alias FOO = /usr/local/bin/foo -l, /usr/local/bin/bar \"\"",
+        "user ALL = (root) FOO",
+    ]);
+
+    let entries = sudoers
+        .matching_entries(&Named("user"), &system::Hostname::fake("server"))
+        .map(|entry| entry.to_string())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        entries,
+        ["    (root) /usr/local/bin/foo -l, /usr/local/bin/bar \"\""]
+    );
+}
+
+#[test]
+fn list_entries_are_grouped_per_runas() {
+    let sudoers = fake_sudoers(&[
+        "user ALL = (root) NOPASSWD: /usr/bin/ls, /usr/bin/cat, (alice : staff) /usr/bin/date, PASSWD: /usr/bin/echo",
+        "user ALL = (ALL) NOEXEC: SETENV: /usr/bin/vi, EXEC: /usr/bin/less, !/usr/bin/more",
+    ]);
+
+    let entries = sudoers
+        .matching_entries(&Named("user"), &system::Hostname::fake("server"))
+        .map(|entry| entry.to_string())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        entries,
+        [
+            "    (root) NOPASSWD: /usr/bin/ls, /usr/bin/cat",
+            "    (alice : staff) NOPASSWD: /usr/bin/date, PASSWD: /usr/bin/echo",
+            "    (ALL) NOEXEC: SETENV: /usr/bin/vi, EXEC: /usr/bin/less, !/usr/bin/more",
+        ]
+    );
+}
+
+#[test]
+fn list_entries_show_pseudo_commands() {
+    let sudoers = fake_sudoers(&[
+        "user ALL = (ALL) /usr/bin/ls, list",
+        "user ALL = (root) sudoedit /etc/hosts, sudoedit /etc/motd /etc/issue, sudoedit",
+    ]);
+
+    let entries = sudoers
+        .matching_entries(&Named("user"), &system::Hostname::fake("server"))
+        .map(|entry| entry.to_string())
+        .collect::<Vec<_>>();
+    let verbose = sudoers
+        .matching_entries(&Named("user"), &system::Hostname::fake("server"))
+        .map(|entry| entry.verbose().to_string())
+        .collect::<Vec<_>>();
+
+    // the pseudo-commands are shown without a path, sudoedit with the files that may be edited
+    assert_eq!(
+        entries,
+        [
+            "    (ALL) /usr/bin/ls, list",
+            "    (root) sudoedit /etc/hosts, sudoedit /etc/motd /etc/issue, sudoedit"
+        ]
+    );
+    assert_eq!(
+        verbose,
+        [
+            "\nSudoers entry:\n    RunAsUsers: ALL\n    Commands:\n\t/usr/bin/ls\n\tlist",
+            "\nSudoers entry:\n    RunAsUsers: root\n    Commands:\n\tsudoedit /etc/hosts\n\tsudoedit /etc/motd /etc/issue\n\tsudoedit"
+        ]
+    );
+}
+
+#[test]
+fn verbose_list_entries_show_later_denials() {
+    let sudoers = fake_sudoers(&[
+        "Cmnd_Alias PAGERS = /usr/bin/less, /usr/bin/more",
+        "user ALL = (root) NOPASSWD: /usr/bin/ls, PAGERS",
+        "user ALL = (root) !/usr/bin/ls, !PAGERS",
+    ]);
+
+    let entries = sudoers
+        .matching_entries(&Named("user"), &system::Hostname::fake("server"))
+        .map(|entry| entry.verbose().to_string())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        entries,
+        [
+            "\nSudoers entry:\n    RunAsUsers: root\n    Options: !authenticate\n    Commands:\n\t/usr/bin/ls\n\t/usr/bin/less\n\t/usr/bin/more",
+            "\nSudoers entry:\n    RunAsUsers: root\n    Commands:\n\t!/usr/bin/ls\n\t!/usr/bin/less\n\t!/usr/bin/more",
+        ]
+    );
+}
+
+#[test]
+fn short_read_fails_closed() {
+    struct Flaky<'a>(&'a [u8]);
+
+    impl io::Read for Flaky<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Err(io::Error::other("stale file handle"));
+            }
+            let len = self.0.len().min(buf.len()).min(8);
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    let policy = b"root ALL=(ALL:ALL) ALL\nuser ALL=(ALL:ALL) ALL\n";
+    let len = policy.len() as u64;
+
+    assert!(read_sudoers_exact(&policy[..], len).is_ok());
+    assert!(matches!(
+        read_sudoers_exact(&policy[..20], len),
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof
+    ));
+    assert!(read_sudoers_exact(&policy[..], len - 1).is_err());
+    assert!(read_sudoers_exact(Flaky(&policy[..]), len).is_err());
+}
+
+#[test]
+fn crlf_line_endings() {
+    let lines = [
+        "# a comment",
+        "Defaults passwd_tries = 5",
+        "Cmnd_Alias TOOLS = /bin/foo, \\",
+        "    /bin/bar",
+        "user ALL=(ALL) TOOLS",
+        "",
+    ];
+
+    let outcome = |text: &str, command: &str| {
+        let (mut sudoers, errs) = Sudoers::read(text.as_bytes(), "/etc/fakesudoers").unwrap();
+        assert!(errs.is_empty());
+        let permitted = check_request(&mut sudoers, &Named("user"), request!(root), command, &[])
+            .flags
+            .is_some();
+        (permitted, sudoers.settings.passwd_tries())
+    };
+
+    let unix = lines.join("\n");
+    let windows = lines.join("\r\n");
+    for command in ["/bin/foo", "/bin/bar", "/bin/baz"] {
+        assert_eq!(outcome(&unix, command), outcome(&windows, command));
+    }
+    assert_eq!(outcome(&windows, "/bin/bar"), (true, 5));
+    assert_eq!(outcome(&windows, "/bin/baz"), (false, 5));
+}
+
+#[test]
+fn all_syntax_errors_are_reported() {
+    let text = [
+        "user ALL=(ALL ALL",
+        "User_Alias lowercase = user",
+        "root ALL=(ALL:ALL) ALL",
+        "user ALL=(ALL:ALL ALL \\",
+        "    /bin/foo, \\",
+        "    /bin/bar",
+        "user server=(ALL) /bin/foo",
+        "%%% ALL=ALL",
+        "user ALL",
+        "",
+    ]
+    .join("\n");
+
+    let (_, errs) = Sudoers::read(text.as_bytes(), "/etc/fakesudoers").unwrap();
+    let lines = errs
+        .iter()
+        .map(|err| err.location.as_ref().unwrap().start.0)
+        .collect::<Vec<_>>();
+
+    // the continuation lines of a faulty line do not cause errors of their own
+    assert_eq!(lines, [1, 2, 4, 8, 9]);
+}
+
+#[test]
+fn command_specific_passwd_tries() {
+    let allowed_attempts = |command: &str| {
+        let mut sudoers = fake_sudoers(&[
+            "Defaults passwd_tries=5",
+            "Defaults!/bin/sensitive passwd_tries=1",
+            "Defaults!/bin/unguarded passwd_tries=0",
+            "user ALL=(ALL) ALL",
+        ]);
+        let judgement = check_request(&mut sudoers, &Named("user"), request!(root), command, &[]);
+        let Authorization::Allowed(auth, _) = judgement.authorization() else {
+            panic!("user should be allowed")
+        };
+        auth.allowed_attempts
+    };
+
+    assert_eq!(allowed_attempts("/bin/foo"), 5);
+    assert_eq!(allowed_attempts("/bin/sensitive"), 1);
+    assert_eq!(allowed_attempts("/bin/unguarded"), 0);
+}
+
+#[test]
+fn runas_specific_authenticate() {
+    let must_authenticate = |target: &'static str, command: &str| {
+        let mut sudoers = fake_sudoers(&[
+            "Defaults>root !authenticate",
+            "user ALL=(ALL:ALL) ALL, PASSWD: /bin/guarded",
+        ]);
+        let target = (&Named(target), &Named(target));
+        let judgement = check_request(&mut sudoers, &Named("user"), target, command, &[]);
+        let Authorization::Allowed(auth, _) = judgement.authorization() else {
+            panic!("user should be allowed")
+        };
+        auth.must_authenticate
+    };
+
+    assert!(!must_authenticate("root", "/bin/foo"));
+    assert!(must_authenticate("operator", "/bin/foo"));
+    // an explicit tag takes precedence
+    assert!(must_authenticate("root", "/bin/guarded"));
+}
+
+#[test]
+fn runas_default_depends_on_the_invoking_user() {
+    // the second argument is the expected runas_default, so it can be used in a request
+    let runas_default = |user: &'static str, target: &'static str| {
+        let mut sudoers = fake_sudoers(&[
+            "Defaults runas_default=operator",
+            "Defaults:admin runas_default=root",
+            "Defaults>operator passwd_tries=1",
+            "user,admin ALL=(ALL:ALL) ALL",
+        ]);
+        let runas_default = sudoers.runas_default(&system::Hostname::fake("server"), &Named(user));
+
+        assert_eq!(runas_default, target);
+
+        // defaults for the target user still apply once it is known
+        let target = (&Named(target), &Named("root"));
+        let judgement = check_request(&mut sudoers, &Named(user), target, "/bin/foo", &[]);
+        let Authorization::Allowed(auth, _) = judgement.authorization() else {
+            panic!("user should be allowed")
+        };
+
+        auth.allowed_attempts
+    };
+
+    assert_eq!(runas_default("user", "operator"), 1);
+    assert_eq!(runas_default("admin", "root"), 3);
+}
+
+#[test]
+fn runas_defaults_keep_their_place_in_the_file() {
+    // the last matching Defaults line wins, also after runas_default has been looked up
+    let allowed_attempts = |first: &str, second: &str| {
+        let mut sudoers = fake_sudoers(&[first, second, "user ALL=(ALL:ALL) ALL"]);
+        let hostname = system::Hostname::fake("server");
+        assert_eq!(sudoers.runas_default(&hostname, &Named("user")), "root");
+
+        let judgement = check_request(
+            &mut sudoers,
+            &Named("user"),
+            request!(root),
+            "/bin/foo",
+            &[],
+        );
+        let Authorization::Allowed(auth, _) = judgement.authorization() else {
+            panic!("user should be allowed")
+        };
+
+        auth.allowed_attempts
+    };
+
+    assert_eq!(
+        allowed_attempts("Defaults>root passwd_tries=1", "Defaults passwd_tries=2"),
+        2
+    );
+    assert_eq!(
+        allowed_attempts("Defaults passwd_tries=2", "Defaults>root passwd_tries=1"),
+        1
+    );
+}
+
+#[test]
+fn matching_rule_is_reported() {
+    let rule = |defaults: &str, command: &str| {
+        let mut sudoers = fake_sudoers(&[
+            defaults,
+            "# the last matching rule applies",
+            "user ALL=(ALL) /bin/foo",
+            "user ALL=(ALL) \\",
+            "    /bin/bar, /bin/foo",
+            "user ALL=(ALL) /bin/baz",
+        ]);
+        let judgement = check_request(&mut sudoers, &Named("user"), request!(root), command, &[]);
+        let Authorization::Allowed(_, restrictions) = judgement.authorization() else {
+            panic!("user should be allowed")
+        };
+        restrictions.rule
+    };
+
+    assert_eq!(rule("", "/bin/foo"), None);
+    let rule = |command| rule("Defaults set_sudo_rule", command);
+    assert_eq!(rule("/bin/foo").as_deref(), Some("/etc/fakesudoers:4"));
+    assert_eq!(rule("/bin/bar").as_deref(), Some("/etc/fakesudoers:4"));
+    assert_eq!(rule("/bin/baz").as_deref(), Some("/etc/fakesudoers:6"));
+}
+
+#[test]
+fn lecture_settings() {
+    let lecture = |defaults: &str| {
+        let mut sudoers = fake_sudoers(&[defaults, "user ALL=(ALL) ALL"]);
+        let judgement = check_request(
+            &mut sudoers,
+            &Named("user"),
+            request!(root),
+            "/bin/foo",
+            &[],
+        );
+        let Authorization::Allowed(auth, _) = judgement.authorization() else {
+            panic!("user should be allowed")
+        };
+        auth.lecture
+    };
+
+    assert_eq!(lecture(""), Lecture::Never);
+    assert_eq!(lecture("Defaults lecture=once"), Lecture::Once(None));
+    assert_eq!(lecture("Defaults lecture=always"), Lecture::Always(None));
+    assert_eq!(lecture("Defaults lecture=always, !lecture"), Lecture::Never);
+    assert_eq!(
+        lecture("Defaults lecture=once, lecture_file=/etc/sudo_lecture"),
+        Lecture::Once(Some("/etc/sudo_lecture".into()))
+    );
+    assert_eq!(
+        lecture("Defaults lecture_file=/etc/sudo_lecture"),
+        Lecture::Never
+    );
+}
+
+#[test]
+fn status_directories() {
+    let sudoers = || {
+        fake_sudoers(&[
+            "Defaults timestamp_dir=/run/sudo-rs/ts",
+            "Defaults:admin lecture_status_dir=/var/lib/lectured, !timestamp_dir",
+            "user,admin ALL=(ALL) ALL",
+        ])
+    };
+    let host = system::Hostname::fake("server");
+
+    assert_eq!(
+        sudoers().timestamp_dir(&host, &Named("user")),
+        Some("/run/sudo-rs/ts")
+    );
+    assert_eq!(sudoers().timestamp_dir(&host, &Named("admin")), None);
+
+    let judgement = check_request(
+        &mut sudoers(),
+        &Named("admin"),
+        request!(root),
+        "/bin/foo",
+        &[],
+    );
+    let Authorization::Allowed(auth, _) = judgement.authorization() else {
+        panic!("admin should be allowed")
+    };
+    assert_eq!(auth.timestamp_dir, None);
+    assert_eq!(auth.lecture_status_dir, Some("/var/lib/lectured".into()));
+
+    // relative paths would depend on the working directory of the invoking user
+    assert!(try_parse_line("Defaults timestamp_dir=run/sudo-rs/ts\n").is_none());
+    assert!(try_parse_line("Defaults lecture_status_dir=\"lectured\"\n").is_none());
+    assert!(try_parse_line("Defaults lecture_status_dir=\"/var/lib/lectured\"\n").is_some());
+}
+
+#[test]
+fn list_other_user_permission() {
+    let sudoers = || {
+        fake_sudoers(&[
+            "user ALL=(ALL) /bin/ls",
+            "auditor ALL=(user) NOPASSWD: list",
+            "other ALL=(ALL) ALL",
+        ])
+    };
+    let host = system::Hostname::fake("server");
+    let list = |invoking: &'static str, inspected: &'static str| {
+        sudoers().check_list_permission(
+            &Named(invoking),
+            &host,
+            ListRequest {
+                inspected_user: &Named(inspected),
+                target_user: &Named("root"),
+                target_group: &Named("root"),
+            },
+        )
+    };
+
+    // listing one's own privileges only requires some matching rule
+    assert!(matches!(list("user", "user"), Authorization::Allowed(..)));
+    // listing someone else's privileges requires the "list" pseudo-command,
+    // which is also covered by ALL
+    assert!(matches!(list("user", "auditor"), Authorization::Forbidden));
+    assert!(matches!(list("other", "user"), Authorization::Allowed(..)));
+    let Authorization::Allowed(auth, _) = list("auditor", "user") else {
+        panic!("auditor should be allowed to list user")
+    };
+    assert!(!auth.must_authenticate);
+    assert!(matches!(list("auditor", "other"), Authorization::Forbidden));
+    // root may always list, without authenticating
+    let Authorization::Allowed(auth, _) = list("root", "user") else {
+        panic!("root should be allowed to list user")
+    };
+    assert!(!auth.must_authenticate);
+}
+
 #[test]
 fn directive_test() {
     let y = parse_eval::<Spec<UserSpecifier>>;
     match parse_eval::<ast::Sudo>("User_Alias HENK = user1, user2") {
         Sudo::Decl(Directive::UserAlias(defs)) => {
-            let [Def(name, list)] = &defs[..] else {
+            let [Def(name, list, _)] = &defs[..] else {
                 panic!("incorrectly parsed")
             };
             assert_eq!(name, "HENK");
@@ -506,7 +1204,7 @@ fn directive_test() {
 
     match parse_eval::<ast::Sudo>("Runas_Alias FOO = foo : BAR = bar") {
         Sudo::Decl(Directive::RunasAlias(defs)) => {
-            let [Def(name1, list1), Def(name2, list2)] = &defs[..] else {
+            let [Def(name1, list1, _), Def(name2, list2, _)] = &defs[..] else {
                 panic!("incorrectly parsed")
             };
             assert_eq!(name1, "FOO");
@@ -564,6 +1262,91 @@ fn gh676_percent_h_escape_unsupported() {
     );
 }
 
+#[test]
+fn percent_h_escape_when_enabled() {
+    let hostname = system::Hostname::resolve();
+    let short_name = hostname.split_once('.').map_or(&*hostname, |x| x.0);
+
+    // the directory does not exist, so the diagnostic shows which one would have been read;
+    // any sudoers file that the test creates itself would be rejected unless owned by root
+    let (_, errs) = analyze(
+        Path::new("/etc/fakesudoers"),
+        sudoer![
+            "Defaults includedir_host_escape",
+            "@includedir /nonexistent/sudoers.d/%h"
+        ],
+    );
+
+    let messages = errs.into_iter().map(|err| err.message).collect::<Vec<_>>();
+    assert_eq!(
+        messages,
+        [format!(
+            "cannot open sudoers file /nonexistent/sudoers.d/{short_name}"
+        )]
+    );
+}
+
+#[test]
+fn percent_h_escape_is_checked() {
+    // the flag only affects what comes after it
+    let (_, errs) = analyze(
+        Path::new("/etc/fakesudoers"),
+        sudoer![
+            r#"@includedir "/etc/%h" "#,
+            "Defaults includedir_host_escape",
+            "@include /etc/sudoers.d/%h/../../shadow"
+        ],
+    );
+    let messages = errs.into_iter().map(|err| err.message).collect::<Vec<_>>();
+    assert_eq!(messages.len(), 2);
+    assert!(messages[0].ends_with("percent escape %h in includedir is unsupported"));
+    assert!(messages[1].ends_with("expanded path may not contain '..'"));
+}
+
+#[test]
+fn alias_errors_point_at_the_definition() {
+    let (_, errs) = analyze(
+        Path::new("/etc/fakesudoers"),
+        sudoer![
+            "User_Alias FOO = BAR",
+            "Host_Alias SELF = SELF",
+            "User_Alias QUUX = user : FOO = user"
+        ],
+    );
+    let found = errs
+        .into_iter()
+        .map(|err| (err.message, err.source, err.location))
+        .collect::<Vec<_>>();
+
+    let source = Some(PathBuf::from("/etc/fakesudoers"));
+    let span = |line, col, len| {
+        Some(Span {
+            start: (line, col),
+            end: (line, col + len),
+        })
+    };
+    assert_eq!(
+        found,
+        vec![
+            (
+                "undefined alias: 'BAR'".to_string(),
+                source.clone(),
+                span(1, 12, 3)
+            ),
+            (
+                "multiple occurrences of 'FOO'".to_string(),
+                source.clone(),
+                span(3, 26, 3)
+            ),
+            (
+                "recursive alias: 'SELF'".to_string(),
+                source,
+                span(2, 12, 4)
+            ),
+        ]
+    );
+}
+
 #[test]
 fn gh1295_escaped_equal_argument_ok() {
     assert!(try_parse_line("Cmd_Alias FOO_CMD = /bin/foo --bar=1").is_some());
@@ -576,12 +1359,97 @@ fn gh1466_hostname_cannot_be_ip() {
     assert!(try_parse_line("ALL 1ba.168.0.0=ALL").is_some());
     assert!(try_parse_line("ALL 192.168.0.1.5=ALL").is_some());
     assert!(try_parse_line("ALL 192.1682.0.1=ALL").is_some());
-    assert!(try_parse_line("ALL 192.168.0.1=ALL").is_none());
     assert!(try_parse_line("ALL 192.168.0=ALL").is_none());
     assert!(try_parse_line("ALL 192.168=ALL").is_none());
     assert!(try_parse_line("ALL 192=ALL").is_none());
 }
 
+#[test]
+fn hosts_can_be_networks() {
+    assert!(try_parse_line("ALL 192.168.0.1=ALL").is_some());
+    assert!(try_parse_line("ALL 192.168.0.0/16=ALL").is_some());
+    assert!(try_parse_line("ALL 192.168.0.0/255.255.0.0=ALL").is_some());
+    assert!(try_parse_line("Host_Alias SUBNET = 10.0.0.0/8, 172.16.0.1").is_some());
+    assert!(try_parse_line("ALL 192.168.0.256=ALL").is_none());
+    assert!(try_parse_line("ALL 192.168.0.0/33=ALL").is_none());
+    assert!(try_parse_line("ALL 192.168.0.0/=ALL").is_none());
+    assert!(try_parse_line("ALL 192.168.0.0/255.255.0=ALL").is_none());
+    assert!(try_parse_line("ALL server/24=ALL").is_none());
+
+    let network = |text: &str| Hostname(text.to_string()).network().unwrap();
+    let addr = |text: &str| text.parse().unwrap();
+    assert!(network("10.0.0.0/8").contains(addr("10.1.2.3")));
+    assert!(!network("10.0.0.0/8").contains(addr("11.0.0.1")));
+    assert!(network("192.168.1.0/255.255.255.0").contains(addr("192.168.1.77")));
+    assert!(!network("192.168.1.0/255.255.255.0").contains(addr("192.168.2.77")));
+    assert!(network("172.16.0.1").contains(addr("172.16.0.1")));
+    assert!(!network("172.16.0.1").contains(addr("172.16.0.2")));
+    assert!(network("0.0.0.0/0").contains(addr("8.8.8.8")));
+    assert!(Hostname("server".to_string()).network().is_none());
+
+    let allowed_on = |network: &str| {
+        let host_alias = format!("Host_Alias LOCAL = {network}");
+        let mut sudoers = fake_sudoers(&[&host_alias, "user LOCAL=(ALL) ALL"]);
+        check_request(
+            &mut sudoers,
+            &Named("user"),
+            request!(root),
+            "/bin/foo",
+            &[],
+        )
+        .flags
+        .is_some()
+    };
+
+    // the loopback interface does not count as an address of this machine
+    assert!(!allowed_on("127.0.0.0/8"));
+
+    let addresses = system::interface_addresses().unwrap();
+    assert!(addresses.iter().all(|addr| !addr.is_loopback()));
+    let ipv4 = addresses.iter().find_map(|addr| match addr {
+        std::net::IpAddr::V4(addr) => Some(addr),
+        std::net::IpAddr::V6(_) => None,
+    });
+    if let Some(addr) = ipv4 {
+        assert!(allowed_on(&addr.to_string()));
+        assert!(allowed_on(&format!("{addr}/24")));
+        assert!(allowed_on(&format!("{addr}/255.255.255.0")));
+    }
+}
+
+#[test]
+fn networks_without_interface_addresses() {
+    let sudoers = fake_sudoers(&[
+        "Host_Alias LAN = 10.0.0.0/8",
+        "Host_Alias NOT_LAN = ALL, !10.0.0.0/8",
+        "user server = ALL",
+        "user 10.0.0.0/8 = ALL",
+        "user ALL, !10.0.0.0/8 = ALL",
+        "user server, LAN = ALL",
+        "user ALL, !LAN = ALL",
+        "user NOT_LAN = ALL",
+    ]);
+
+    // as if the addresses of the network interfaces could not be determined
+    let host_matcher = HostMatcher {
+        hostname: "server",
+        addresses: OnceCell::from(None),
+    };
+    let host_aliases = host_matcher.aliases(&sudoers.aliases.host);
+    let matches = sudoers
+        .rules
+        .iter()
+        .map(|rule| {
+            host_matcher
+                .find(&rule.permissions[0].0, &host_aliases)
+                .is_some()
+        })
+        .collect::<Vec<_>>();
+
+    // a negated network can not be ruled out, so it always excludes the host
+    assert_eq!(matches, [true, false, false, true, false, false]);
+}
+
 #[test]
 fn hashsign_error() {
     assert!(parse_line("#include foo bar").is_line_comment());
@@ -740,6 +1608,32 @@ fn default_specific_test() {
     assert!(mod_sudoers.settings.use_pty());
 }
 
+#[test]
+fn secure_path_can_be_disabled_for_a_user() {
+    let sudoers = || {
+        fake_sudoers(&[
+            "Defaults secure_path=\"/usr/bin\"",
+            "Defaults:deploy !secure_path",
+        ])
+    };
+
+    let mut sudoers_for_other = sudoers();
+    sudoers_for_other.specify_host_user_runas(
+        &system::Hostname::fake("host"),
+        &Named("other"),
+        Some(&Named("root")),
+    );
+    assert_eq!(sudoers_for_other.settings.secure_path(), Some("/usr/bin"));
+
+    let mut sudoers_for_deploy = sudoers();
+    sudoers_for_deploy.specify_host_user_runas(
+        &system::Hostname::fake("host"),
+        &Named("deploy"),
+        Some(&Named("root")),
+    );
+    assert_eq!(sudoers_for_deploy.settings.secure_path(), None);
+}
+
 #[test]
 fn useralias_underscore_regression() {
     let sudo = parse_line("FOO_BAR ALL=ALL");
@@ -752,6 +1646,62 @@ fn useralias_underscore_regression() {
     );
 }
 
+/// Included files must be owned by root, so tests that create them can only run as root.
+fn can_create_included_files() -> bool {
+    system::User::effective_uid() == UserId::ROOT
+}
+
+#[test]
+fn includedir_file_limit() {
+    // the limit can not be raised beyond the number of files that may be included at all
+    assert!(try_parse_line("Defaults includedir_file_limit=128\n").is_some());
+    assert!(try_parse_line("Defaults includedir_file_limit=129\n").is_none());
+    assert!(try_parse_line("Defaults includedir_file_limit=0\n").is_none());
+
+    if !can_create_included_files() {
+        return;
+    }
+
+    // the last entry of `lines` is an includedir with `files` files, of which only the last one
+    // sets passwd_tries to 1; returns the diagnostics and the resulting passwd_tries
+    let include = |lines: &[&str], files: usize| {
+        let dir = std::env::temp_dir().join(format!("sudoers-includedir-{}", std::process::id()));
+        std::fs::create_dir(&dir).unwrap();
+        for i in 0..files - 1 {
+            std::fs::write(dir.join(format!("f{i:05}")), "Defaults passwd_tries=5\n").unwrap();
+        }
+        // this file sorts last
+        std::fs::write(dir.join("zz"), "Defaults passwd_tries=1\n").unwrap();
+
+        let includedir = format!("@includedir {}", dir.display());
+        let (mut sudoers, errs) = analyze(
+            Path::new("/etc/fakesudoers"),
+            sudoer![&[lines, &[&includedir]].concat().join("\n")],
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let messages = errs
+            .into_iter()
+            .map(|err| err.message.replace(&dir.display().to_string(), "DIR"))
+            .collect::<Vec<_>>();
+        sudoers.specify_host_user_runas(&system::Hostname::fake("server"), &Named("user"), None);
+        (messages, sudoers.settings.passwd_tries())
+    };
+
+    let too_many = |limit: usize| {
+        vec![format!(
+            "too many files in DIR; only the first {limit} are used"
+        )]
+    };
+
+    assert_eq!(include(&[], 100), (vec![], 1));
+    assert_eq!(include(&[], 101), (too_many(100), 5));
+
+    let lines = ["Defaults includedir_file_limit=3"];
+    assert_eq!(include(&lines, 3), (vec![], 1));
+    assert_eq!(include(&lines, 4), (too_many(3), 5));
+}
+
 #[test]
 fn regression_check_recursion() {
     let (_, error) = analyze(
@@ -827,21 +1777,29 @@ fn remote_parsing() {
     assert_remote_failure("@socket (:user8) /var/run/fake-8.socket", "expected elem");
 }
 
+fn def<T>(name: &str, list: SpecList<T>) -> Def<T> {
+    let span = Span {
+        start: (1, 1),
+        end: (1, 1),
+    };
+    Def(name.to_string(), list, DefOrigin { file: None, span })
+}
+
 fn test_topo_sort(n: usize) {
     let alias = |s: &str| Qualified::Allow(Meta::<UserSpecifier>::Alias(s.to_string()));
     let stop = || Qualified::Allow(Meta::<UserSpecifier>::All);
     type Elem = Spec<UserSpecifier>;
     let test_case = |x1: Elem, x2: Elem, x3: Elem| {
         let table = vec![
-            Def("AAP".to_string(), vec![x1]),
-            Def("NOOT".to_string(), vec![x2]),
-            Def("MIES".to_string(), vec![x3]),
+            def("AAP", vec![x1]),
+            def("NOOT", vec![x2]),
+            def("MIES", vec![x3]),
         ];
         let mut err = vec![];
         let order = sanitize_alias_table(&table, &mut err);
         assert!(err.is_empty());
         let mut seen = HashSet::new();
-        for Def(id, defns) in order.iter().map(|&i| &table[i]) {
+        for Def(id, defns, _) in order.iter().map(|&i| &table[i]) {
             if defns.iter().any(|spec| {
                 let Qualified::Allow(Meta::Alias(id2)) = spec else {
                     return false;
@@ -910,7 +1868,7 @@ fn fuzz_topo_sort(siz: usize) {
         let table = data
             .into_iter()
             .enumerate()
-            .map(|(i, x)| Def(name(i as u8), vec![x]))
+            .map(|(i, x)| def(&name(i as u8), vec![x]))
             .collect();
 
         let mut err = vec![];
@@ -920,7 +1878,7 @@ fn fuzz_topo_sort(siz: usize) {
         }
 
         let mut seen = HashSet::new();
-        for Def(id, defns) in order.iter().map(|&i| &table[i]) {
+        for Def(id, defns, _) in order.iter().map(|&i| &table[i]) {
             if defns.iter().any(|spec| {
                 let Qualified::Allow(Meta::Alias(id2)) = spec else {
                     return false;
@@ -939,3 +1897,468 @@ fn fuzz_topo_sort(siz: usize) {
 fn fuzz_topo_sort7() {
     fuzz_topo_sort(7)
 }
+
+#[test]
+fn runas_user_by_name_or_uid_is_equivalent() {
+    use crate::common::resolve::{CurrentUser, resolve_target_user_and_group};
+
+    let current_user = CurrentUser::resolve().unwrap();
+
+    let (name_user, name_group) =
+        resolve_target_user_and_group(&Some("root".into()), &None, &current_user).unwrap();
+    let (uid_user, uid_group) =
+        resolve_target_user_and_group(&Some("#0".into()), &None, &current_user).unwrap();
+    assert_eq!(name_user, uid_user);
+    assert_eq!(name_group.gid, uid_group.gid);
+
+    for rule in [
+        "ALL ALL=(root) /bin/foo",
+        "ALL ALL=(#0) /bin/foo",
+        "ALL ALL=(ALL,!root) /bin/foo",
+        "ALL ALL=(ALL,!#0) /bin/foo",
+    ] {
+        let judge = |user, group| {
+            check_request(
+                &mut fake_sudoers(&[rule]),
+                &*current_user,
+                (user, group),
+                "/bin/foo",
+                &[],
+            )
+            .flags
+        };
+
+        assert_eq!(judge(&name_user, &name_group), judge(&uid_user, &uid_group));
+    }
+}
+
+#[test]
+fn shadowed_rules() {
+    let shadowed = |lines: &[&str]| {
+        let sudoers = fake_sudoers(lines);
+        sudoers
+            .shadowed_rules()
+            .into_iter()
+            .map(|lint::ShadowedRule { rule, shadowed_by }| (rule, shadowed_by))
+            .collect::<Vec<_>>()
+    };
+
+    // a broader rule for the same user or for everyone overrides an earlier one
+    let rules = [
+        "user ALL=(ALL:ALL) NOPASSWD: /usr/bin/foo",
+        "user ALL=(ALL:ALL) ALL",
+    ];
+    assert_eq!(shadowed(&rules), [(1, 2)]);
+    let rules = [
+        "user server=NOPASSWD: /usr/bin/foo, (sudo) /usr/bin/bar",
+        "ALL ALL=(ALL) ALL",
+        "ALL ALL=(ALL:ALL) ALL",
+    ];
+    assert_eq!(shadowed(&rules), [(1, 2), (2, 3)]);
+    let rules = [
+        "user ALL=ALL, !/usr/bin/foo",
+        "%sudo,user ALL=(ALL:ALL) ALL",
+    ];
+    assert_eq!(shadowed(&rules), [(1, 2)]);
+
+    // nothing is shadowed if the later rule is narrower in some respect
+    for rules in [
+        [
+            "user ALL=(ALL:ALL) /usr/bin/foo",
+            "user ALL=(ALL:ALL) /usr/bin/bar",
+        ],
+        ["user ALL=(ALL:ALL) /usr/bin/foo", "other ALL=(ALL:ALL) ALL"],
+        [
+            "user,other ALL=(ALL:ALL) /usr/bin/foo",
+            "user ALL=(ALL:ALL) ALL",
+        ],
+        [
+            "user ALL=(ALL:ALL) /usr/bin/foo",
+            "ALL,!other ALL=(ALL:ALL) ALL",
+        ],
+        [
+            "user ALL=(ALL:ALL) /usr/bin/foo",
+            "user server=(ALL:ALL) ALL",
+        ],
+        ["user ALL=(ALL:ALL) /usr/bin/foo", "user ALL=(ALL) ALL"],
+        ["user ALL=(sudo) /usr/bin/foo", "user ALL=ALL"],
+        [
+            "user ALL=(ALL:ALL) /usr/bin/foo",
+            "user ALL=(ALL:ALL) ALL, !/usr/bin/foo",
+        ],
+        [
+            "user ALL=(ALL:ALL) ALL",
+            "user ALL=(ALL:ALL) NOPASSWD: /usr/bin/foo",
+        ],
+    ] {
+        assert_eq!(shadowed(&rules), [], "{rules:?}");
+    }
+}
+
+#[test]
+fn semantic_warnings() {
+    let warnings = |lines: &[&str]| {
+        let (sudoers, errors) = analyze(Path::new("/etc/fakesudoers"), sudoer![&lines.join("\n")]);
+        assert!(errors.is_empty());
+        sudoers
+            .validate_semantics()
+            .into_iter()
+            .map(
+                |Error {
+                     location, message, ..
+                 }| (location.map(|span| span.start), message),
+            )
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        warnings(&[
+            "User_Alias ADMINS = user, %wheel",
+            "User_Alias UNUSED = other",
+            "Host_Alias SERVERS = server",
+            "Cmnd_Alias SHELLS = /bin/sh, /bin/bash",
+            "Cmnd_Alias EDITORS = /usr/bin/vi",
+            "Runas_Alias OPS = ops",
+            "Defaults!EDITORS env_reset",
+            "ADMINS SERVERS=(OPS) ALL, !SHELLS",
+        ]),
+        [(Some((2, 12)), "unused User_Alias: 'UNUSED'".to_string())]
+    );
+
+    // aliases referenced by other aliases are in use
+    assert_eq!(
+        warnings(&[
+            "Cmnd_Alias SHELLS = /bin/sh",
+            "Cmnd_Alias DANGER = SHELLS, /bin/rm",
+            "user ALL=DANGER",
+        ]),
+        []
+    );
+
+    assert_eq!(
+        warnings(&[
+            "user ALL=(ALL:ALL) /usr/bin/foo",
+            "user ALL=(ALL:ALL) ALL",
+            "other ALL=ALL, !ALL, !/usr/bin/sh, /usr/bin/foo, !/usr/bin/bar",
+        ]),
+        [
+            (
+                None,
+                "user specification 1 can never take effect, \
+                 since user specification 2 overrides it"
+                    .to_string()
+            ),
+            (
+                None,
+                "denial of '/usr/bin/sh' in user specification 3 has no effect, \
+                 since ALL is already denied"
+                    .to_string()
+            ),
+        ]
+    );
+
+    // a denial under a different RunAs specification is not affected by !ALL
+    assert_eq!(warnings(&["user ALL=(ALL) !ALL, (root) !/bin/sh"]), []);
+    assert_eq!(
+        warnings(&["user ALL=(root) !ALL, !/usr/bin/sh, (root) !/usr/bin/bash"]),
+        [(
+            None,
+            "denial of '/usr/bin/sh' in user specification 1 has no effect, \
+             since ALL is already denied"
+                .to_string()
+        )]
+    );
+}
+
+#[test]
+fn runas_group_list_rejects_user_group_syntax() {
+    // the %group syntax designates a group in a list of *users*; a runas group list already
+    // consists of groups, so % is not accepted there (not even mixed with other forms)
+    assert!(try_parse_line("user ALL=(ALL:wheel,#1466) /bin/foo\n").is_some());
+    assert!(try_parse_line("user ALL=(ALL:%wheel) /bin/foo\n").is_none());
+    assert!(try_parse_line("user ALL=(ALL:wheel,#1466,%#1466) /bin/foo\n").is_none());
+}
+
+#[test]
+fn denial_reasons() {
+    let reason = |rule: &str, target: &'static str, command: &str| {
+        let target = (&Named(target), &Named(target));
+        check_request(
+            &mut fake_sudoers(&[rule]),
+            &Named("user"),
+            target,
+            command,
+            &[],
+        )
+        .denial_reason()
+    };
+
+    use DenialReason::*;
+    assert_eq!(reason("user ALL=(root) /bin/foo", "root", "/bin/foo"), None);
+    assert_eq!(
+        reason("other ALL=(ALL) ALL", "root", "/bin/foo"),
+        Some(NoMatchingUser)
+    );
+    assert_eq!(
+        reason("ALL,!user ALL=(ALL) ALL", "root", "/bin/foo"),
+        Some(NoMatchingUser)
+    );
+    assert_eq!(
+        reason("user otherhost=(ALL) ALL", "root", "/bin/foo"),
+        Some(HostMismatch)
+    );
+    assert_eq!(
+        reason("user ALL,!server=(ALL) ALL", "root", "/bin/foo"),
+        Some(HostMismatch)
+    );
+    assert_eq!(
+        reason("user ALL=(root) /bin/foo", "other", "/bin/foo"),
+        Some(RunAsMismatch)
+    );
+    assert_eq!(
+        reason("user ALL=/bin/foo", "other", "/bin/foo"),
+        Some(RunAsMismatch)
+    );
+    assert_eq!(
+        reason("user ALL=(root) /bin/foo", "root", "/bin/bar"),
+        Some(CommandNotPermitted)
+    );
+    assert_eq!(
+        reason("user ALL=(root) ALL, !/bin/foo", "root", "/bin/foo"),
+        Some(CommandForbidden)
+    );
+    assert_eq!(
+        reason("user ALL=(root) ALL, !/bin/foo", "root", "/bin/bar"),
+        None
+    );
+    assert_eq!(
+        reason(
+            "user ALL=(root) /bin/*, !/bin/foo, /bin/bar",
+            "root",
+            "/bin/foo"
+        ),
+        Some(CommandForbidden)
+    );
+    // a denial in an alias is also an explicit one
+    assert_eq!(
+        reason(
+            "Cmnd_Alias FOO=/bin/foo\nuser ALL=(root) ALL, !FOO",
+            "root",
+            "/bin/foo"
+        ),
+        Some(CommandForbidden)
+    );
+    // but a denial under a RunAs specification that does not apply is not
+    assert_eq!(
+        reason(
+            "user ALL=(root) /bin/bar, (other) !/bin/foo",
+            "root",
+            "/bin/foo"
+        ),
+        Some(CommandNotPermitted)
+    );
+
+    // the first blocking aspect is reported, even if later ones would also block
+    assert_eq!(
+        reason("user otherhost=(root) /bin/foo", "other", "/bin/bar"),
+        Some(HostMismatch)
+    );
+}
+
+#[test]
+fn policy_for_a_known_user() {
+    let group = |gid, name: &str| Group {
+        gid: GroupId::new(gid),
+        name: Some(name.to_string()),
+    };
+    let root = KnownUser {
+        uid: UserId::ROOT,
+        name: "root".to_string(),
+        group: group(0, "root"),
+        groups: vec![],
+        netgroups: vec![],
+    };
+    let alice = KnownUser {
+        uid: UserId::new(1000),
+        name: "alice".to_string(),
+        group: group(1000, "alice"),
+        groups: vec![group(27, "wheel"), group(1500, "deploy")],
+        netgroups: vec!["webadmins".to_string()],
+    };
+    let bob = KnownUser {
+        uid: UserId::new(1001),
+        name: "bob".to_string(),
+        group: group(1001, "bob"),
+        groups: vec![],
+        netgroups: vec![],
+    };
+
+    let allowed = |rule: &str, user: &KnownUser| {
+        let target = (&root, &root.group);
+        let judgement = check_request(
+            &mut fake_sudoers(&[rule]),
+            user,
+            target,
+            "/usr/bin/true",
+            &[],
+        );
+        matches!(judgement.authorization(), Authorization::Allowed(..))
+    };
+
+    assert!(allowed("alice ALL=(ALL) ALL", &alice));
+    assert!(allowed("#1000 ALL=(ALL) ALL", &alice));
+    assert!(allowed("%wheel ALL=(ALL) ALL", &alice));
+    assert!(allowed("%#1500 ALL=(ALL) ALL", &alice));
+    assert!(allowed("%alice ALL=(ALL) ALL", &alice));
+    assert!(!allowed("%wheel ALL=(ALL) ALL", &bob));
+    assert!(!allowed("%#1500 ALL=(ALL) ALL", &bob));
+    assert!(!allowed("ALL,!%wheel ALL=(ALL) ALL", &alice));
+    assert!(allowed("ALL,!%wheel ALL=(ALL) ALL", &bob));
+    assert!(allowed("+webadmins ALL=(ALL) ALL", &alice));
+    assert!(!allowed("+webadmins ALL=(ALL) ALL", &bob));
+}
+
+#[test]
+fn netgroups() {
+    let allowed = |rule: &str, user: &'static str, target: &'static str| {
+        let target = (&Named(target), &Named(target));
+        check_request(
+            &mut fake_sudoers(&[rule]),
+            &Named(user),
+            target,
+            "/bin/foo",
+            &[],
+        )
+        .flags
+        .is_some()
+    };
+
+    assert!(allowed("+user_ng ALL=(ALL) ALL", "user", "root"));
+    assert!(!allowed("+user_ng ALL=(ALL) ALL", "other", "root"));
+    assert!(allowed("ALL,!+other_ng ALL=(ALL) ALL", "user", "root"));
+    assert!(!allowed("ALL,!+user_ng ALL=(ALL) ALL", "user", "root"));
+    assert!(allowed(
+        "User_Alias ADMINS=+user_ng\nADMINS ALL=(ALL) ALL",
+        "user",
+        "root"
+    ));
+    assert!(allowed("user ALL=(+admin_ng) ALL", "user", "admin"));
+    assert!(!allowed("user ALL=(+admin_ng) ALL", "user", "root"));
+
+    // the fake host is not a member of any netgroup
+    assert!(!allowed("user +servers=(ALL) ALL", "user", "root"));
+    assert!(allowed("user ALL,!+servers=(ALL) ALL", "user", "root"));
+
+    // a netgroup needs a name
+    assert!(try_parse_line("+ ALL=(ALL) ALL\n").is_none());
+    assert!(try_parse_line("user +=(ALL) ALL\n").is_none());
+}
+
+#[test]
+fn digest_specs_are_rejected_for_every_algorithm() {
+    // sudo-rs does not verify command digests; a rule that requires one must not be accepted
+    // as if it was a rule without one, whatever the length or encoding of the digest
+    let assert_rejected = |digest: &str| {
+        let line = format!("user ALL=(ALL) {digest} /usr/bin/foo\n");
+        let parsed = parse_lines::<Sudo>(&mut CharStream::new(&line));
+        let Some(Err(Status::Fatal(_, message))) = parsed.first() else {
+            panic!("{digest} was not rejected");
+        };
+        assert_eq!(message, "digest specifications are not supported");
+    };
+
+    for (algorithm, hex_len) in [
+        ("sha224", 56),
+        ("sha256", 64),
+        ("sha384", 96),
+        ("sha512", 128),
+    ] {
+        assert_rejected(&format!("{algorithm}:{}", "0".repeat(hex_len)));
+    }
+
+    assert_rejected("sha256:47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=");
+    assert_rejected("sha224:0UoCjCo6K8lHYQK7KII0xBWisB+CjqYqxbPkLw==");
+}
+
+#[test]
+fn editor_digests_are_rejected() {
+    // likewise, there is no setting that pins the digest of the editor used by sudoedit
+    let line = format!("Defaults editor_checksum=sha256:{}\n", "0".repeat(64));
+    let parsed = parse_lines::<Sudo>(&mut CharStream::new(&line));
+    let Some(Err(Status::UnknownSetting(_, message))) = parsed.first() else {
+        panic!("editor_checksum was not rejected");
+    };
+    assert_eq!(message, "unknown setting: 'editor_checksum'");
+}
+
+#[test]
+fn unknown_settings_are_recognized() {
+    let text = [
+        "Defaults passwd_tries=5",
+        "Defaults pasword_tries=5",
+        "Defaults !use_ptty",
+        "Defaults !passwd_tries",
+        "user ALL=(ALL ALL",
+        "",
+    ]
+    .join("\n");
+
+    let (_, errs) = Sudoers::read(text.as_bytes(), "/etc/fakesudoers").unwrap();
+    let unknown = errs
+        .iter()
+        .map(|err| {
+            (
+                err.location.as_ref().unwrap().start.0,
+                err.is_unknown_setting(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    assert_eq!(unknown, [(2, true), (3, true), (4, false), (5, false)]);
+}
+
+#[test]
+fn single_lines_can_be_checked() {
+    let check = |text: &str, line| {
+        Sudoers::check_lines(text, line)
+            .into_iter()
+            .map(|err| (err.location.unwrap(), err.message))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(check("user ALL=(ALL:ALL) ALL", 7), []);
+    assert_eq!(check("Defaults passwd_tries=5\n", 7), []);
+    assert_eq!(check("# just a comment", 12), []);
+
+    assert_eq!(
+        check("user ALL=(ALL ALL", 42),
+        [(
+            Span {
+                start: (42, 15),
+                end: (42, 18)
+            },
+            "expecting ')' but found 'A'".to_string()
+        )]
+    );
+    assert_eq!(
+        check("Defaults pasword_tries=5", 3),
+        [(
+            Span {
+                start: (3, 10),
+                end: (3, 23)
+            },
+            "unknown setting: 'pasword_tries'".to_string()
+        )]
+    );
+    // the lines after the first one are numbered accordingly
+    assert_eq!(
+        check("Defaults passwd_tries=5\nuser ALL=/bin/ls, ", 10),
+        [(
+            Span {
+                start: (11, 19),
+                end: (11, 19)
+            },
+            "expected elem".to_string()
+        )]
+    );
+}
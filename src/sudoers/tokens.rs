@@ -2,6 +2,7 @@
 
 use crate::common::{SudoPath, SudoString};
 use std::ffi::OsString;
+use std::net::Ipv4Addr;
 
 use super::basic_parser::{Many, Token};
 use crate::common::{HARDENED_ENUM_VALUE_0, HARDENED_ENUM_VALUE_1, HARDENED_ENUM_VALUE_2};
@@ -70,7 +71,8 @@ impl Token for Numeric {
     }
 }
 
-/// A hostname consists of alphanumeric characters and ".", "-",  "_"
+/// A hostname consists of alphanumeric characters and ".", "-",  "_"; a host can also be
+/// specified by an IPv4 address, or a network in the form `address/bits` or `address/netmask`.
 pub struct Hostname(pub String);
 
 impl std::ops::Deref for Hostname {
@@ -81,24 +83,83 @@ impl std::ops::Deref for Hostname {
     }
 }
 
+impl Hostname {
+    /// The network this token designates, if it is not a host name or netgroup.
+    pub fn network(&self) -> Option<IpNetwork> {
+        IpNetwork::recognize(self).and_then(Result::ok)
+    }
+}
+
 impl Token for Hostname {
     fn construct(text: String) -> Result<Self, String> {
-        // reject hostnames that resemble IPv4 addresses too closely
-        // (IPv6 addresses will already be reject since ':' is not in the accept-set)
-        let mut chunks = text.rsplitn(5, '.');
-        if (&mut chunks)
-            .take(4)
-            .all(|part| !part.is_empty() && part.len() <= 3 && part.chars().all(char::is_numeric))
-            && chunks.next().is_none()
-        {
-            return Err("hosts cannot be specified using an IPv4 address".to_string());
+        if let Some(network) = IpNetwork::recognize(&text) {
+            network?;
+        }
+
+        if text == "+" {
+            return Err("expected a netgroup name".to_string());
         }
 
         Ok(Hostname(text))
     }
 
     fn accept(c: char) -> bool {
-        c.is_ascii_alphanumeric() || ".-_".contains(c)
+        c.is_ascii_alphanumeric() || ".-_/".contains(c)
+    }
+
+    // a leading '+' designates a netgroup
+    fn accept_1st(c: char) -> bool {
+        c == '+' || Self::accept(c)
+    }
+}
+
+/// An IPv4 network; a single address is a network that has a netmask of 32 bits.
+#[cfg_attr(test, derive(Debug, PartialEq, Eq))]
+pub struct IpNetwork {
+    address: Ipv4Addr,
+    netmask: Ipv4Addr,
+}
+
+impl IpNetwork {
+    /// Parse `text` if it resembles an IPv4 address or network; a host name can not
+    /// contain a '/', and should not resemble an IPv4 address too closely either.
+    fn recognize(text: &str) -> Option<Result<Self, String>> {
+        let (address, netmask) = match text.split_once('/') {
+            Some((address, netmask)) => (address, Some(netmask)),
+            None => (text, None),
+        };
+
+        let mut chunks = address.rsplitn(5, '.');
+        let resembles_ipv4 = (&mut chunks)
+            .take(4)
+            .all(|part| !part.is_empty() && part.len() <= 3 && part.chars().all(char::is_numeric))
+            && chunks.next().is_none();
+        if !resembles_ipv4 && netmask.is_none() {
+            return None;
+        }
+
+        let Ok(address) = address.parse::<Ipv4Addr>() else {
+            return Some(Err(format!("invalid IPv4 address '{address}'")));
+        };
+
+        let netmask = match netmask {
+            None => Ipv4Addr::BROADCAST,
+            Some(bits) if bits.chars().all(|c| c.is_ascii_digit()) => match bits.parse::<u32>() {
+                Ok(bits @ 0..=32) => Ipv4Addr::from(u32::MAX.checked_shl(32 - bits).unwrap_or(0)),
+                _ => return Some(Err(format!("invalid network prefix length '{bits}'"))),
+            },
+            Some(netmask) => match netmask.parse::<Ipv4Addr>() {
+                Ok(netmask) => netmask,
+                Err(_) => return Some(Err(format!("invalid netmask '{netmask}'"))),
+            },
+        };
+
+        Some(Ok(IpNetwork { address, netmask }))
+    }
+
+    pub fn contains(&self, address: Ipv4Addr) -> bool {
+        let netmask = u32::from(self.netmask);
+        u32::from(address) & netmask == u32::from(self.address) & netmask
     }
 }
 
@@ -145,6 +206,8 @@ impl<T: Token> Token for Meta<T> {
     fn escaped(c: char) -> bool {
         T::escaped(c)
     }
+
+    const KEEP_ESCAPES: bool = T::KEEP_ESCAPES;
 }
 
 impl<T: Many> Many for Meta<T> {
@@ -184,14 +247,61 @@ pub type Command = (SimpleCommand, Args);
 /// which is essentially a subset of "Command"
 pub type SimpleCommand = glob::Pattern;
 
+/// A word in a command specification, after removing quotes and escapes; `literal` records
+/// whether any were used, so that e.g. a quoted `"*"` is not mistaken for the magic `*`.
+struct Word {
+    text: String,
+    literal: bool,
+}
+
+/// Split a command specification into words, like a shell would: unescaped whitespace separates
+/// words, a backslash escapes the next character, and double quotes keep whitespace inside a word.
+fn split_words(s: &str) -> Result<Vec<Word>, String> {
+    let mut words = Vec::new();
+    let mut current: Option<Word> = None;
+    let mut in_quotes = false;
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() && !in_quotes {
+            words.extend(current.take());
+            continue;
+        }
+
+        let word = current.get_or_insert_with(|| Word {
+            text: String::new(),
+            literal: false,
+        });
+        match c {
+            '\\' => {
+                // the tokenizer only passes on a backslash together with the character it escapes
+                word.text.extend(chars.next());
+                word.literal = true;
+            }
+            '"' => {
+                in_quotes = !in_quotes;
+                word.literal = true;
+            }
+            _ => word.text.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err("unterminated quote in command arguments".to_string());
+    }
+    words.extend(current);
+
+    Ok(words)
+}
+
 impl Token for Command {
     const MAX_LEN: usize = 1024;
 
     fn construct(s: String) -> Result<Self, String> {
         // the tokenizer should not give us a token that consists of only whitespace
-        let mut cmd_iter = s.split_whitespace();
-        let cmd = cmd_iter.next().unwrap().to_string();
-        let mut args = cmd_iter.map(OsString::from).collect::<Vec<OsString>>();
+        let mut words = split_words(&s)?.into_iter();
+        let cmd = words.next().unwrap().text;
+        let mut args = words.collect::<Vec<Word>>();
 
         let command = SimpleCommand::construct(cmd)?;
 
@@ -199,37 +309,37 @@ impl Token for Command {
             // if no arguments are mentioned, anything is allowed
             Args::Prefix(Box::default())
         } else {
-            if args
-                .first()
-                .is_some_and(|x| x.as_encoded_bytes().starts_with(b"^"))
-            {
+            if args.first().is_some_and(|x| x.text.starts_with('^')) {
                 // regular expressions are not supported, give an error message. If there is only a
                 // terminating '$', this is not treated as a malformed regex by millersudo, so we don't
                 // need to seperately check for that
                 return Err("regular expressions are not supported".to_string());
             }
-            let match_type = match args.last().map(|x| x.as_encoded_bytes()) {
+            let match_type = match args.last() {
                 // if the magic * appears, any further arguments are allowed
-                Some(b"*") => {
+                Some(Word {
+                    text,
+                    literal: false,
+                }) if text == "*" => {
                     args.pop();
                     Args::Prefix
                 }
                 // if the magic "" appears, no (further) arguments are allowed
-                Some(b"\"\"") => {
+                Some(Word {
+                    text,
+                    literal: true,
+                }) if text.is_empty() => {
                     args.pop();
                     Args::Exact
                 }
                 _ => Args::Exact,
             };
 
-            if args
-                .iter()
-                .any(|arg| arg.as_encoded_bytes().iter().any(|c| b"?*".contains(c)))
-            {
+            if args.iter().any(|arg| arg.text.contains(['?', '*'])) {
                 return Err("wildcards are not allowed in command arguments".to_string());
             }
 
-            match_type(args.into_boxed_slice())
+            match_type(args.into_iter().map(|arg| arg.text.into()).collect())
         };
 
         if command.as_str() == "list" && argpat != Args::Prefix(Box::default()) {
@@ -252,6 +362,9 @@ impl Token for Command {
     fn escaped(c: char) -> bool {
         SimpleCommand::escaped(c)
     }
+
+    // an escaped space does not separate arguments
+    const KEEP_ESCAPES: bool = true;
 }
 
 impl Token for SimpleCommand {
@@ -26,6 +26,30 @@ pub(crate) fn no_new_privs_enabled() -> io::Result<bool> {
     Ok(no_new_privs != 0)
 }
 
+/// Check whether we are running inside a user namespace that has no mapping for uid 0; in that
+/// case the setuid bit on a root-owned binary cannot give us root privileges.
+#[cfg(target_os = "linux")]
+pub(crate) fn root_unmapped_in_user_namespace() -> io::Result<bool> {
+    match fs::read_to_string("/proc/self/uid_map") {
+        Ok(uid_map) => Ok(!uid_map_contains_root(&uid_map)),
+        // without procfs, we cannot tell; assume no namespace is involved
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+/// Every line in a uid_map has the form "<inside id> <outside id> <length>"
+#[cfg(target_os = "linux")]
+fn uid_map_contains_root(uid_map: &str) -> bool {
+    uid_map.lines().any(|line| {
+        let mut fields = line.split_whitespace().map(str::parse::<u64>);
+        matches!(
+            (fields.next(), fields.next(), fields.next()),
+            (Some(Ok(0)), Some(Ok(_)), Some(Ok(len))) if len > 0
+        )
+    })
+}
+
 /// Temporary change privileges --- essentially a 'mini sudo'
 /// This is only used for sudoedit.
 pub(crate) fn sudo_call<T>(
@@ -157,12 +181,33 @@ fn mode(who: Category, what: Op) -> u32 {
     (what as u32) << (3 * who as u32)
 }
 
+/// The owner and group that sudoers files must have, and the mode that visudo gives them.
+/// Packagers can set SUDOERS_UID, SUDOERS_GID and SUDOERS_MODE (in octal) when building.
+pub const SUDOERS_UID: u32 = build_setting(option_env!("SUDOERS_UID"), 10, 0);
+pub const SUDOERS_GID: u32 = build_setting(option_env!("SUDOERS_GID"), 10, 0);
+pub const SUDOERS_MODE: u32 = build_setting(option_env!("SUDOERS_MODE"), 8, 0o440);
+
+const fn build_setting(value: Option<&str>, radix: u32, default: u32) -> u32 {
+    match value {
+        Some(value) => match u32::from_str_radix(value, radix) {
+            Ok(number) => number,
+            Err(_) => panic!("SUDOERS_UID, SUDOERS_GID and SUDOERS_MODE must be numbers"),
+        },
+        None => default,
+    }
+}
+
 /// Open sudo configuration using various security checks
 pub fn secure_open_sudoers(path: impl AsRef<Path>) -> io::Result<File> {
     let mut open_options = OpenOptions::new();
     open_options.read(true);
 
-    secure_open_impl(path.as_ref(), &mut open_options, false)
+    secure_open_impl(
+        path.as_ref(),
+        &mut open_options,
+        (SUDOERS_UID, SUDOERS_GID),
+        false,
+    )
 }
 
 #[cfg(feature = "unstable-remote-sudoers")]
@@ -184,7 +229,7 @@ pub fn secure_open_cookie_file(path: impl AsRef<Path>) -> io::Result<File> {
         .truncate(false)
         .mode(mode(Category::Owner, Op::Write) | mode(Category::Owner, Op::Read));
 
-    secure_open_impl(path.as_ref(), &mut open_options, true)
+    secure_open_impl(path.as_ref(), &mut open_options, (0, 0), true)
 }
 
 /// Return the system zoneinfo path after validating that it is safe
@@ -202,15 +247,38 @@ pub fn zoneinfo_path() -> Option<&'static str> {
 }
 
 fn checks(path: &Path, meta: Metadata) -> io::Result<()> {
+    checks_owned_by(path, &meta, (0, 0))
+}
+
+fn checks_owned_by(path: &Path, meta: &Metadata, owner: (u32, u32)) -> io::Result<()> {
+    ownership_checks(
+        path,
+        (meta.uid(), meta.gid()),
+        meta.permissions().mode(),
+        owner,
+    )
+}
+
+// The file must be owned by `owner`, and only the group `group` may have write access to it.
+fn ownership_checks(
+    path: &Path,
+    (uid, gid): (u32, u32),
+    path_mode: u32,
+    (owner, group): (u32, u32),
+) -> io::Result<()> {
     let error = |msg| Error::new(ErrorKind::PermissionDenied, msg);
 
-    let path_mode = meta.permissions().mode();
-    if meta.uid() != 0 {
-        Err(error(xlat!(
-            "{path} must be owned by root",
-            path = path.display()
-        )))
-    } else if meta.gid() != 0 && (path_mode & mode(Category::Group, Op::Write) != 0) {
+    if uid != owner {
+        Err(error(if owner == 0 {
+            xlat!("{path} must be owned by root", path = path.display())
+        } else {
+            xlat!(
+                "{path} must be owned by uid {uid}",
+                path = path.display(),
+                uid = owner
+            )
+        }))
+    } else if gid != group && (path_mode & mode(Category::Group, Op::Write) != 0) {
         Err(error(xlat!(
             "{path} cannot be group-writable",
             path = path.display()
@@ -226,12 +294,14 @@ fn checks(path: &Path, meta: Metadata) -> io::Result<()> {
 }
 
 // Open `path` with options `open_options`, provided that it is "secure".
-// "Secure" means that it passes the `checks` function above.
+// "Secure" means that it passes the `checks_owned_by` function above for `owner`; its parent
+// directory must pass `checks`.
 // If `check_parent_dir` is set, also check that the parent directory is "secure" also.
 // If `create_parent_dirs` is set, create the path to the file if it does not already exist.
 fn secure_open_impl(
     path: &Path,
     open_options: &mut OpenOptions,
+    owner: (u32, u32),
     create_parent_dirs: bool,
 ) -> io::Result<File> {
     let error = |msg| Error::new(ErrorKind::PermissionDenied, msg);
@@ -261,7 +331,7 @@ fn secure_open_impl(
 
     let file = open_options.open(path)?;
     let meta = file.metadata()?;
-    checks(path, meta)?;
+    checks_owned_by(path, &meta, owner)?;
 
     Ok(file)
 }
@@ -499,6 +569,41 @@ mod test {
         assert!(secure_open_sudoers("/etc/shadow").is_err());
     }
 
+    #[test]
+    fn sudoers_ownership_build_settings() {
+        assert_eq!(build_setting(None, 10, 0), 0);
+        assert_eq!(build_setting(None, 8, 0o440), 0o440);
+        assert_eq!(build_setting(Some("27"), 10, 0), 27);
+        assert_eq!(build_setting(Some("640"), 8, 0o440), 0o640);
+    }
+
+    #[test]
+    fn ownership_checks_use_expected_owner() {
+        let path = Path::new("/etc/sudoers");
+        for owner @ (uid, gid) in [(0, 0), (0, 27), (1000, 1000)] {
+            assert!(ownership_checks(path, owner, 0o440, owner).is_ok());
+            assert!(ownership_checks(path, owner, 0o460, owner).is_ok());
+            assert!(ownership_checks(path, (uid, gid + 1), 0o440, owner).is_ok());
+
+            assert!(ownership_checks(path, (uid + 1, gid), 0o440, owner).is_err());
+            assert!(ownership_checks(path, (uid, gid + 1), 0o460, owner).is_err());
+            assert!(ownership_checks(path, owner, 0o442, owner).is_err());
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_uid_map_contains_root() {
+        // the initial user namespace
+        assert!(uid_map_contains_root("         0          0 4294967295\n"));
+        // a typical rootless container
+        assert!(uid_map_contains_root("0 1000 1\n1 100000 65536\n"));
+        // a namespace where only an unprivileged user is mapped
+        assert!(!uid_map_contains_root("1000 1000 1\n"));
+        assert!(!uid_map_contains_root("0 1000 0\n"));
+        assert!(!uid_map_contains_root(""));
+    }
+
     #[test]
     fn test_secure_open_cookie_file() {
         assert!(secure_open_cookie_file("/etc/hosts").is_err());
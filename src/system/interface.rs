@@ -116,6 +116,7 @@ pub trait UnixUser {
     fn is_root(&self) -> bool;
     fn in_group_by_name(&self, _name: &CStr) -> bool;
     fn in_group_by_gid(&self, _gid: GroupId) -> bool;
+    fn in_netgroup(&self, _netgroup: &CStr) -> bool;
 
     type Group: UnixGroup;
     fn group(&self) -> Self::Group;
@@ -146,6 +147,9 @@ impl UnixUser for super::User {
     fn in_group_by_gid(&self, gid: GroupId) -> bool {
         self.groups.contains(&gid)
     }
+    fn in_netgroup(&self, netgroup: &CStr) -> bool {
+        super::in_netgroup(netgroup, None, Some(self.name.as_cstr()))
+    }
     type Group = super::Group;
     fn group(&self) -> super::Group {
         Self::Group {
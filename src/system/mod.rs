@@ -2,12 +2,14 @@
 #[cfg(target_os = "linux")]
 use std::str::FromStr;
 use std::{
-    ffi::{CStr, c_int, c_long, c_uint},
+    ffi::{CStr, c_char, c_int, c_long, c_uint},
     fmt, fs, io,
     mem::MaybeUninit,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
     ops,
     os::unix,
     path::PathBuf,
+    sync::OnceLock,
 };
 
 use crate::{
@@ -47,68 +49,148 @@ pub(crate) fn _exit(status: c_int) -> ! {
 
 /// Mark every file descriptor that is not one of the IO streams as CLOEXEC.
 pub(crate) fn mark_fds_as_cloexec() -> io::Result<()> {
-    let lowfd = STDERR_FILENO + 1;
+    mark_fds_as_cloexec_from(STDERR_FILENO + 1, &[])
+}
+
+/// Mark every file descriptor greater than or equal to `lowfd` as CLOEXEC, so they are closed
+/// once the command is executed; the (sorted) file descriptors in `except` are left alone.
+pub(crate) fn mark_fds_as_cloexec_from(lowfd: c_int, except: &[c_int]) -> io::Result<()> {
+    debug_assert!(lowfd > STDERR_FILENO);
+    debug_assert!(except.is_sorted());
+
+    let mut first = lowfd;
+    for &fd in except.iter().filter(|&&fd| fd >= lowfd) {
+        if fd > first {
+            match mark_range_as_cloexec(first as c_uint, (fd - 1) as c_uint) {
+                Err(err) if is_unsupported(&err) => return mark_open_fds_as_cloexec(lowfd, except),
+                res => res?,
+            }
+        }
+        first = fd.saturating_add(1);
+    }
+
+    match mark_range_as_cloexec(first as c_uint, c_uint::MAX) {
+        Err(err) if is_unsupported(&err) => mark_open_fds_as_cloexec(lowfd, except),
+        res => res,
+    }
+}
+
+/// The kernel doesn't support close_range or CLOSE_RANGE_CLOEXEC
+fn is_unsupported(err: &io::Error) -> bool {
+    err.raw_os_error() == Some(ENOSYS) || err.raw_os_error() == Some(EINVAL)
+}
 
+fn mark_range_as_cloexec(first: c_uint, last: c_uint) -> io::Result<()> {
     // SAFETY: this function is safe to call:
     // - any errors while closing a specific fd will be effectively ignored
     #[allow(clippy::diverging_sub_expression)]
     let res = unsafe {
         'a: {
             #[cfg(not(target_os = "linux"))]
-            break 'a cerr(libc::close_range(
-                lowfd as c_uint,
-                c_uint::MAX,
-                CLOSE_RANGE_CLOEXEC as c_int,
-            ));
+            break 'a cerr(libc::close_range(first, last, CLOSE_RANGE_CLOEXEC as c_int));
             // on Linux, close_range was only added in glibc 2.34, and is not
             // part of musl, so we go perform a straight syscall instead
             #[cfg(target_os = "linux")]
             break 'a cerr(libc::syscall(
                 libc::SYS_close_range,
-                lowfd as c_uint,
-                c_uint::MAX,
+                first,
+                last,
                 CLOSE_RANGE_CLOEXEC as c_uint,
             ));
         }
     };
 
-    match res {
-        Err(err) if err.raw_os_error() == Some(ENOSYS) || err.raw_os_error() == Some(EINVAL) => {
-            // The kernel doesn't support close_range or CLOSE_RANGE_CLOEXEC,
-            // fallback to finding all open fds using /proc/self/fd.
-
-            // FIXME use /dev/fd on macOS
-            for entry in fs::read_dir("/proc/self/fd")? {
-                let entry = entry?;
-                let file_name = entry.file_name();
-                let file_name = file_name.to_str().ok_or(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    "procfs returned non-integer fd name",
-                ))?;
-                if file_name == "." || file_name == ".." {
-                    continue;
-                }
-                let fd = file_name.parse::<c_int>().map_err(|_| {
-                    io::Error::new(
-                        io::ErrorKind::InvalidData,
-                        "procfs returned non-integer fd name",
-                    )
-                })?;
-                if fd < lowfd {
-                    continue;
-                }
-                // SAFETY: This only sets the CLOEXEC flag for the given fd. Nothing is
-                // going to need it after exec.
-                unsafe {
-                    cerr(libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC))?;
-                }
-            }
+    res.map(|_| ())
+}
 
-            Ok(())
+/// Fallback for [`mark_fds_as_cloexec_from`] that finds all open fds using /proc/self/fd.
+fn mark_open_fds_as_cloexec(lowfd: c_int, except: &[c_int]) -> io::Result<()> {
+    for fd in open_fds()? {
+        if fd < lowfd || except.binary_search(&fd).is_ok() {
+            continue;
+        }
+        // SAFETY: This only sets the CLOEXEC flag for the given fd. Nothing is
+        // going to need it after exec.
+        unsafe {
+            cerr(libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC))?;
         }
-        Err(err) => Err(err),
-        Ok(_) => Ok(()),
     }
+
+    Ok(())
+}
+
+/// The file descriptors that are currently open, in ascending order.
+fn open_fds() -> io::Result<Vec<c_int>> {
+    // FIXME use /dev/fd on macOS
+    let mut fds = Vec::new();
+    for entry in fs::read_dir("/proc/self/fd")? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let file_name = file_name.to_str().ok_or(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "procfs returned non-integer fd name",
+        ))?;
+        if file_name == "." || file_name == ".." {
+            continue;
+        }
+        let fd = file_name.parse::<c_int>().map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "procfs returned non-integer fd name",
+            )
+        })?;
+        fds.push(fd);
+    }
+
+    // the directory itself was listed as well, but has been closed by now
+    // SAFETY: F_GETFD only inspects the given fd
+    fds.retain(|&fd| unsafe { libc::fcntl(fd, libc::F_GETFD) } != -1);
+    fds.sort_unstable();
+
+    Ok(fds)
+}
+
+/// Fallback for [`open_fds`] where /proc is not available (e.g. on FreeBSD): ask about every
+/// file descriptor that the process may have open, up to a limit.
+fn probe_open_fds() -> io::Result<Vec<c_int>> {
+    const MAX_PROBED_FDS: c_long = 1 << 16;
+
+    // SAFETY: sysconf has no preconditions
+    let open_max = cerr(unsafe { libc::sysconf(libc::_SC_OPEN_MAX) })?;
+
+    Ok((0..open_max.min(MAX_PROBED_FDS) as c_int)
+        // SAFETY: F_GETFD only inspects the given fd
+        .filter(|&fd| unsafe { libc::fcntl(fd, libc::F_GETFD) } != -1)
+        .collect())
+}
+
+static INHERITED_FDS: OnceLock<io::Result<Vec<c_int>>> = OnceLock::new();
+
+/// Remember which file descriptors sudo was started with; this has to be done before sudo opens
+/// any file descriptors of its own.
+pub(crate) fn record_inherited_fds() {
+    INHERITED_FDS.get_or_init(|| open_fds().or_else(|_| probe_open_fds()));
+}
+
+/// The (sorted) file descriptors in `fds` that sudo was started with, so that a file descriptor
+/// opened by sudo itself is never passed on to the command. If it is not known which file
+/// descriptors sudo was started with, none of them are passed on.
+pub(crate) fn inherited_fds(fds: &[c_int]) -> Vec<c_int> {
+    let inherited = match INHERITED_FDS.get() {
+        Some(Ok(inherited)) => inherited.as_slice(),
+        Some(Err(e)) => {
+            if !fds.is_empty() {
+                crate::log::auth_warn!("not preserving any file descriptors: {e}");
+            }
+            &[]
+        }
+        None => &[],
+    };
+
+    fds.iter()
+        .copied()
+        .filter(|fd| inherited.binary_search(fd).is_ok())
+        .collect()
 }
 
 pub(crate) enum ForkResult {
@@ -210,6 +292,14 @@ impl Hostname {
         }
     }
 
+    /// A hostname supplied on the command line (`sudo -l -h host`); it is only used to
+    /// evaluate the policy as it would apply on that host, and does not need to resolve.
+    pub fn from_cli_string(hostname: SudoString) -> Self {
+        Self {
+            inner: hostname.into(),
+        }
+    }
+
     pub fn resolve() -> Self {
         // see `man 2 gethostname`
         const MAX_HOST_NAME_SIZE_ACCORDING_TO_SUSV2: c_long = 255;
@@ -356,6 +446,134 @@ pub fn chown<S: AsRef<CStr>>(
     cerr(unsafe { libc::chown(path, uid.inner(), gid.inner()) }).map(|_| ())
 }
 
+/// Have `SIGALRM` delivered to the current process after `timeout` (rounded down to whole
+/// seconds), replacing any earlier alarm; a zero timeout cancels the alarm.
+pub(crate) fn alarm(timeout: std::time::Duration) {
+    let seconds = timeout.as_secs().try_into().unwrap_or(libc::c_uint::MAX);
+
+    // SAFETY: alarm is always safe to call
+    unsafe { libc::alarm(seconds) };
+}
+
+/// Change the root directory of the current process; this does not change the working
+/// directory.
+pub fn chroot<S: AsRef<CStr>>(path: &S) -> io::Result<()> {
+    let path = path.as_ref().as_ptr();
+
+    // SAFETY: path is a valid pointer to a null-terminated C string
+    cerr(unsafe { libc::chroot(path) }).map(|_| ())
+}
+
+#[cfg(not(target_env = "musl"))]
+unsafe extern "C" {
+    fn innetgr(
+        netgroup: *const c_char,
+        host: *const c_char,
+        user: *const c_char,
+        domain: *const c_char,
+    ) -> c_int;
+}
+
+/// Check whether a host and/or user is a member of the given netgroup; a `None` argument
+/// matches any host or user.
+pub(crate) fn in_netgroup(netgroup: &CStr, host: Option<&CStr>, user: Option<&CStr>) -> bool {
+    #[cfg(not(target_env = "musl"))]
+    {
+        let as_ptr = |s: Option<&CStr>| s.map_or(std::ptr::null(), CStr::as_ptr);
+        // SAFETY: all arguments are either NULL or valid NUL-terminated strings
+        unsafe {
+            innetgr(
+                netgroup.as_ptr(),
+                as_ptr(host),
+                as_ptr(user),
+                std::ptr::null(),
+            ) == 1
+        }
+    }
+
+    // musl does not support netgroups
+    #[cfg(target_env = "musl")]
+    {
+        let _ = (netgroup, host, user);
+        false
+    }
+}
+
+/// The addresses of the network interfaces of this machine; like in original sudo, loopback
+/// interfaces, interfaces that are down and interfaces without an IPv4 or IPv6 address are
+/// skipped.
+pub(crate) fn interface_addresses() -> io::Result<Vec<IpAddr>> {
+    let mut list = std::ptr::null_mut();
+    // SAFETY: getifaddrs stores a pointer to a linked list that we own in `list`
+    cerr(unsafe { libc::getifaddrs(&mut list) })?;
+
+    let mut addresses = Vec::new();
+    let mut cur = list;
+    while !cur.is_null() {
+        // SAFETY: `cur` is an element of the list returned by getifaddrs, which stays valid
+        // until it is freed; ifa_addr is either NULL or points to a socket address whose
+        // actual type is given by its sa_family field.
+        unsafe {
+            let addr = (*cur).ifa_addr;
+            let flags = (*cur).ifa_flags as c_int;
+            let active = flags & libc::IFF_UP != 0 && flags & libc::IFF_LOOPBACK == 0;
+            if active && !addr.is_null() {
+                match c_int::from((*addr).sa_family) {
+                    libc::AF_INET => {
+                        let addr = &*addr.cast::<libc::sockaddr_in>();
+                        addresses.push(IpAddr::V4(Ipv4Addr::from(u32::from_be(
+                            addr.sin_addr.s_addr,
+                        ))));
+                    }
+                    libc::AF_INET6 => {
+                        let addr = &*addr.cast::<libc::sockaddr_in6>();
+                        addresses.push(IpAddr::V6(Ipv6Addr::from(addr.sin6_addr.s6_addr)));
+                    }
+                    _ => {}
+                }
+            }
+            cur = (*cur).ifa_next;
+        }
+    }
+
+    // SAFETY: `list` was returned by getifaddrs and is not used after this
+    unsafe { libc::freeifaddrs(list) };
+
+    Ok(addresses)
+}
+
+/// Look up the groups a user is a member of in the group database; `gid` is the primary group
+/// of the user, which is always included.
+fn group_list(name: &CStr, gid: libc::gid_t) -> Vec<GroupId> {
+    // NOTE: on Linux, getgrouplist could be used to simply inquire as to the size needed;
+    // but on FreeBSD, getgrouplist does not specify this in its function contract, so a
+    // blind allocation loop is needed.
+    let Some(groups_buffer) =
+        dynamic_fill::<libc::gid_t, std::num::TryFromIntError>(32..65536, |groups_buffer| {
+            let mut buf_len: c_int = groups_buffer.len() as c_int;
+            // SAFETY: getgrouplist is passed valid pointers
+            // in particular `groups_buffer` is an array of `buf.len()` bytes, as required
+            let result = unsafe {
+                libc::getgrouplist(name.as_ptr(), gid, groups_buffer.as_mut_ptr(), &mut buf_len)
+            };
+
+            Ok(if result != -1 {
+                Some(buf_len.try_into()?)
+            } else {
+                None
+            })
+        })
+        .expect("negative group size, this should not happen")
+    else {
+        panic!("user has too many groups (> 65536), this should not happen");
+    };
+
+    groups_buffer
+        .iter()
+        .map(|id| GroupId::new(*id))
+        .collect::<Vec<_>>()
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct User {
     pub uid: UserId,
@@ -371,49 +589,25 @@ impl User {
     /// This function expects `pwd` to be a result from a successful call to `getpwXXX_r`.
     /// (It can cause UB if any of `pwd`'s pointed-to strings does not have a null-terminator.)
     unsafe fn from_libc(pwd: &libc::passwd) -> Result<User, Error> {
-        // NOTE: on Linux, getgrouplist could be used to simply inquire as to the size needed;
-        // but on FreeBSD, getgrouplist does not specify this in its function contract, so a
-        // blind allocation loop is needed.
-        let Some(groups_buffer) =
-            dynamic_fill::<libc::gid_t, std::num::TryFromIntError>(32..65536, |groups_buffer| {
-                let mut buf_len: c_int = groups_buffer.len() as c_int;
-                // SAFETY: getgrouplist is passed valid pointers
-                // in particular `groups_buffer` is an array of `buf.len()` bytes, as required
-                let result = unsafe {
-                    libc::getgrouplist(
-                        pwd.pw_name,
-                        pwd.pw_gid,
-                        groups_buffer.as_mut_ptr(),
-                        &mut buf_len,
-                    )
-                };
-
-                Ok(if result != -1 {
-                    Some(buf_len.try_into()?)
-                } else {
-                    None
-                })
-            })
-            .expect("negative group size, this should not happen")
-        else {
-            panic!("user has too many groups (> 65536), this should not happen");
-        };
-
         // SAFETY: All pointers were initialized by a successful call to `getpwXXX_r` as per the
         // safety invariant of this function.
-        unsafe {
-            Ok(User {
+        let mut user = unsafe {
+            User {
                 uid: UserId::new(pwd.pw_uid),
                 gid: GroupId::new(pwd.pw_gid),
                 name: SudoString::new(string_from_ptr(pwd.pw_name))?,
                 home: SudoPath::new(os_string_from_ptr(pwd.pw_dir).into())?,
                 shell: os_string_from_ptr(pwd.pw_shell).into(),
-                groups: groups_buffer
-                    .iter()
-                    .map(|id| GroupId::new(*id))
-                    .collect::<Vec<_>>(),
-            })
+                groups: group_list(CStr::from_ptr(pwd.pw_name), pwd.pw_gid),
+            }
+        };
+
+        // an empty shell field in the passwd database means the standard shell
+        if user.shell.as_os_str().is_empty() {
+            user.shell = PathBuf::from("/bin/sh");
         }
+
+        Ok(user)
     }
 
     pub fn from_uid(uid: UserId) -> Result<Option<User>, Error> {
@@ -469,6 +663,18 @@ impl User {
         Self::from_uid(Self::real_uid())
     }
 
+    /// Query the group database again for the supplementary groups of this user.
+    ///
+    /// The group list of a `User` is a snapshot taken when it was resolved, so a long-lived
+    /// `User` will not notice when it is added to or removed from a group. Any copy of the old
+    /// value (e.g. one that was cached or cloned into a context) keeps the stale list, and
+    /// needs to be invalidated or refreshed as well.
+    // the sudo and su binaries resolve their users once per invocation, so they don't need this
+    #[allow(dead_code)]
+    pub fn refresh_groups(&mut self) {
+        self.groups = group_list(self.name.as_cstr(), self.gid.inner());
+    }
+
     pub fn primary_group(&self) -> std::io::Result<Group> {
         // Use from_gid_unchecked here to ensure that we can still resolve when
         // the /etc/group entry for the primary group is missing.
@@ -938,6 +1144,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn refresh_groups_rereads_membership() {
+        let fresh = User::from_uid(UserId::ROOT).unwrap().unwrap();
+
+        let mut stale = fresh.clone();
+        stale.groups = vec![GroupId::new(32767)];
+        stale.refresh_groups();
+        assert_eq!(stale, fresh);
+        assert!(stale.groups.contains(&stale.gid));
+    }
+
     #[test]
     fn test_group_lookup_fails_gracefully() {
         assert_eq!(Group::from_gid(GroupId::new(32767)).unwrap(), None);
@@ -1097,6 +1314,76 @@ mod tests {
         assert_eq!(status.exit_status(), Some(0));
     }
 
+    #[test]
+    fn mark_fds_as_cloexec_except() {
+        let child_pid = unsafe {
+            fork_for_test(|| {
+                let open_inherited = |name: &str| {
+                    let file = std::fs::File::create(std::env::temp_dir().join(name)).unwrap();
+                    crate::cutils::cerr(libc::fcntl(file.as_fd().as_raw_fd(), libc::F_SETFD, 0))
+                        .unwrap();
+                    file
+                };
+                let should_close = open_inherited("should_close_before.txt");
+                let should_keep = open_inherited("should_keep.txt");
+                let should_close_too = open_inherited("should_close_after.txt");
+
+                let mut except = [should_keep.as_fd().as_raw_fd(), 1000];
+                except.sort();
+                super::mark_fds_as_cloexec_from(libc::STDERR_FILENO + 1, &except).unwrap();
+
+                assert!(is_cloexec(&should_close));
+                assert!(!is_cloexec(&should_keep));
+                assert!(is_cloexec(&should_close_too));
+
+                exit(0)
+            })
+        };
+
+        let (_, status) = child_pid.wait(WaitOptions::new()).unwrap();
+        assert_eq!(status.exit_status(), Some(0));
+    }
+
+    #[test]
+    fn only_inherited_fds_are_preserved() {
+        let child_pid = unsafe {
+            fork_for_test(|| {
+                let open = |name: &str| std::fs::File::create(std::env::temp_dir().join(name));
+                let inherited = open("inherited.txt").unwrap();
+                super::record_inherited_fds();
+                let opened_later = open("opened_later.txt").unwrap();
+
+                let inherited = inherited.as_fd().as_raw_fd();
+                let opened_later = opened_later.as_fd().as_raw_fd();
+                let mut fds = [inherited, opened_later, 1000];
+                fds.sort();
+                assert_eq!(super::inherited_fds(&fds), [inherited]);
+
+                exit(0)
+            })
+        };
+
+        let (_, status) = child_pid.wait(WaitOptions::new()).unwrap();
+        assert_eq!(status.exit_status(), Some(0));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn probing_finds_the_open_fds() {
+        // in a separate process, so no other test opens a file in the meantime
+        let child_pid = unsafe {
+            fork_for_test(|| {
+                let _file = std::fs::File::create(std::env::temp_dir().join("probed.txt"));
+                assert_eq!(super::probe_open_fds().unwrap(), super::open_fds().unwrap());
+
+                exit(0)
+            })
+        };
+
+        let (_, status) = child_pid.wait(WaitOptions::new()).unwrap();
+        assert_eq!(status.exit_status(), Some(0));
+    }
+
     #[cfg(target_os = "linux")]
     #[test]
     fn proc_stat_test() {
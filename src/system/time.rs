@@ -1,10 +1,34 @@
 use std::{
+    ffi::CStr,
     io::{Read, Write},
     mem::MaybeUninit,
     ops::{Add, Sub},
     time::Duration,
 };
 
+/// The current wall clock time in the local time zone, formatted using strftime(3).
+pub(crate) fn format_local_time(format: &CStr) -> Option<String> {
+    // SAFETY: time accepts a null pointer, in which case it only returns the time
+    let now = unsafe { libc::time(std::ptr::null_mut()) };
+    let mut tm = MaybeUninit::<libc::tm>::uninit();
+    // SAFETY: both pointers are valid; localtime_r returns null if it failed
+    if unsafe { libc::localtime_r(&now, tm.as_mut_ptr()) }.is_null() {
+        return None;
+    }
+    // SAFETY: localtime_r succeeded, so it initialized `tm`
+    let tm = unsafe { tm.assume_init() };
+
+    let mut buf = [0u8; 64];
+    // SAFETY: `buf` is valid for writing `buf.len()` bytes and `format` is NUL-terminated;
+    // strftime returns the number of bytes written (excluding the NUL), or 0 if it did not fit
+    let len = unsafe { libc::strftime(buf.as_mut_ptr().cast(), buf.len(), format.as_ptr(), &tm) };
+    if len == 0 {
+        return None;
+    }
+
+    Some(String::from_utf8_lossy(&buf[..len]).into_owned())
+}
+
 /// A timestamp relative to `CLOCK_BOOTTIME`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct SystemTime {
@@ -65,7 +89,7 @@ impl SystemTime {
     }
 
     #[inline]
-    fn checked_sub(self, rhs: Duration) -> Option<SystemTime> {
+    pub(super) fn checked_sub(self, rhs: Duration) -> Option<SystemTime> {
         let rhs_secs = rhs.as_secs().try_into().ok()?;
         let rhs_nsecs = rhs.subsec_nanos().into();
 
@@ -153,6 +177,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_format_local_time() {
+        let year = format_local_time(c"%Y").unwrap();
+        assert_eq!(year.len(), 4);
+        assert!(year.chars().all(|c| c.is_ascii_digit()));
+        assert_eq!(format_local_time(c""), None);
+    }
+
     #[test]
     fn test_time_ops() {
         assert_eq!(
@@ -1,7 +1,7 @@
 use std::{
     fs::File,
     io::{self, Cursor, Read, Seek, Write},
-    path::PathBuf,
+    path::Path,
     time::Duration,
 };
 
@@ -35,11 +35,30 @@ pub struct SessionRecordFile {
 impl SessionRecordFile {
     const BASE_PATH: &'static str = "/var/run/sudo-rs/ts";
 
-    pub fn open_for_user(user: &CurrentUser, timeout: Duration) -> io::Result<Self> {
+    /// Open the session records of a user in the directory `dir`; if that directory can not be
+    /// used safely, the records in the default directory are used instead.
+    pub fn open_for_user(
+        user: &CurrentUser,
+        timeout: Duration,
+        dir: Option<&Path>,
+    ) -> io::Result<Self> {
         let uid = user.uid;
-        let mut path = PathBuf::from(Self::BASE_PATH);
-        path.push(uid.to_string());
-        SessionRecordFile::new(uid, secure_open_cookie_file(&path)?, timeout)
+        let file_name = uid.to_string();
+        let default_path = Path::new(Self::BASE_PATH).join(&file_name);
+
+        let file = match dir {
+            Some(dir) => secure_open_cookie_file(dir.join(&file_name)).or_else(|e| {
+                auth_warn!(
+                    "cannot use timestamp_dir {}: {e}; using {} instead",
+                    dir.display(),
+                    Self::BASE_PATH
+                );
+                secure_open_cookie_file(&default_path)
+            })?,
+            None => secure_open_cookie_file(&default_path)?,
+        };
+
+        SessionRecordFile::new(uid, file, timeout)
     }
 
     const FILE_VERSION: u16 = 2;
@@ -198,7 +217,12 @@ impl SessionRecordFile {
             // only touch if record is enabled
             if record.enabled && record.matches(&scope, auth_user) {
                 let now = SystemTime::now()?;
-                if record.written_between(now - self.timeout, now) {
+                let valid = match now.checked_sub(self.timeout) {
+                    Some(earliest) => record.written_between(earliest, now),
+                    // the timeout reaches back further than the clock does, i.e. it never expires
+                    None => record.timestamp <= now,
+                };
+                if valid {
                     // move back to where the timestamp is and overwrite with the latest time
                     self.file.seek(io::SeekFrom::Current(-MOD_OFFSET))?;
                     let new_time = SystemTime::now()?;
@@ -344,6 +368,8 @@ pub enum RecordScope {
         session_pid: ProcessId,
         init_time: ProcessCreateTime,
     },
+    /// Valid for every session of the user
+    Global,
 }
 
 impl RecordScope {
@@ -373,6 +399,9 @@ impl RecordScope {
                 target.write_all(&b)?;
                 init_time.encode(target)?;
             }
+            RecordScope::Global => {
+                target.write_all(&[3u8])?;
+            }
         }
 
         Ok(())
@@ -410,6 +439,7 @@ impl RecordScope {
                     init_time,
                 })
             }
+            3 => Ok(RecordScope::Global),
             x => Err(io::Error::new(
                 io::ErrorKind::InvalidInput,
                 format!("Unexpected scope variant discriminator: {x}"),
@@ -645,6 +675,12 @@ mod tests {
         let bytes = ppid_sample.as_bytes().unwrap();
         let decoded = SessionRecord::from_bytes(&bytes).unwrap();
         assert_eq!(ppid_sample, decoded);
+
+        let global_sample = SessionRecord::new(RecordScope::Global, UserId::new(123)).unwrap();
+        let bytes = global_sample.as_bytes().unwrap();
+        let decoded = SessionRecord::from_bytes(&bytes).unwrap();
+        assert_eq!(global_sample, decoded);
+        assert!(!global_sample.matches(&ppid_sample.scope, &auth_user_from_uid(123)));
     }
 
     #[test]
@@ -790,4 +826,111 @@ mod tests {
         let data = data_from_tempfile(c).unwrap();
         assert_eq!(&data, &[0xD0, 0x50, 0x02, 0x00]);
     }
+
+    #[test]
+    fn ppid_records_are_only_shared_with_the_same_parent() {
+        let timeout = Duration::from_secs(30);
+        let c = tempfile_with_data(&[]).unwrap();
+        let mut srf = SessionRecordFile::new(TEST_USER_ID, c, timeout).unwrap();
+        let auth_user = auth_user_from_uid(2424);
+
+        let process = Process::new();
+        let scope = RecordScope::for_ppid(&process).unwrap();
+        let RecordScope::Ppid { group_pid, .. } = scope else {
+            panic!("Expected a parent process scope");
+        };
+        assert_eq!(Some(group_pid), process.parent_pid);
+
+        srf.create(scope, &auth_user).unwrap();
+
+        // another invocation from the same parent process
+        let same_parent = RecordScope::for_ppid(&Process::new()).unwrap();
+        assert!(matches!(
+            srf.touch(same_parent, &auth_user).unwrap(),
+            TouchResult::Updated { .. }
+        ));
+
+        // an unrelated parent process in the same session
+        let RecordScope::Ppid {
+            session_pid,
+            init_time,
+            ..
+        } = scope
+        else {
+            unreachable!()
+        };
+        let other_parent = RecordScope::Ppid {
+            group_pid: ProcessId::new(group_pid.inner() + 1),
+            session_pid,
+            init_time,
+        };
+        assert_eq!(
+            srf.touch(other_parent, &auth_user).unwrap(),
+            TouchResult::NotFound
+        );
+
+        // a new process that reused the pid of the parent
+        let reused_pid = RecordScope::Ppid {
+            group_pid,
+            session_pid,
+            init_time: ProcessCreateTime::new(0, 0),
+        };
+        assert_eq!(
+            srf.touch(reused_pid, &auth_user).unwrap(),
+            TouchResult::NotFound
+        );
+    }
+
+    #[test]
+    fn disabling_and_resetting_records() {
+        let timeout = Duration::from_secs(30);
+        let c = tempfile_with_data(&[]).unwrap();
+        let mut srf = SessionRecordFile::new(TEST_USER_ID, c, timeout).unwrap();
+        let auth_user = auth_user_from_uid(2424);
+        let tty_scope = |session_pid| RecordScope::Tty {
+            tty_device: DeviceId::new(10),
+            session_pid: ProcessId::new(session_pid),
+            init_time: ProcessCreateTime::new(1, 0),
+        };
+        srf.create(tty_scope(42), &auth_user).unwrap();
+        srf.create(tty_scope(43), &auth_user).unwrap();
+
+        // sudo -k only invalidates the record of the current session
+        srf.disable(tty_scope(42)).unwrap();
+        assert_eq!(
+            srf.touch(tty_scope(42), &auth_user).unwrap(),
+            TouchResult::NotFound
+        );
+        assert!(matches!(
+            srf.touch(tty_scope(43), &auth_user).unwrap(),
+            TouchResult::Updated { .. }
+        ));
+
+        // sudo -K removes all records of the user
+        srf.reset().unwrap();
+        assert_eq!(
+            srf.touch(tty_scope(43), &auth_user).unwrap(),
+            TouchResult::NotFound
+        );
+    }
+
+    #[test]
+    fn records_only_expire_with_a_finite_timeout() {
+        let auth_user = auth_user_from_uid(2424);
+        let scope = RecordScope::Tty {
+            tty_device: DeviceId::new(10),
+            session_pid: ProcessId::new(42),
+            init_time: ProcessCreateTime::new(1, 0),
+        };
+
+        for (timeout, still_valid) in [(Duration::ZERO, false), (Duration::MAX, true)] {
+            let c = tempfile_with_data(&[]).unwrap();
+            let mut srf = SessionRecordFile::new(TEST_USER_ID, c, timeout).unwrap();
+            srf.create(scope, &auth_user).unwrap();
+            std::thread::sleep(Duration::from_millis(1));
+
+            let result = srf.touch(scope, &auth_user).unwrap();
+            assert_eq!(matches!(result, TouchResult::Updated { .. }), still_valid);
+        }
+    }
 }
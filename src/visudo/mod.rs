@@ -22,6 +22,7 @@ use crate::{
     sudoers::{self, Sudoers},
     system::{
         Hostname, User,
+        audit::{SUDOERS_GID, SUDOERS_MODE, SUDOERS_UID},
         file::{FileLock, create_temporary_dir},
         interface::UserId,
         signal::{SignalStream, SignalsState, consts::*, register_handlers},
@@ -100,9 +101,9 @@ fn check(file_arg: Option<&str>, perms: bool, owner: bool) -> io::Result<()> {
         // For some reason, the MSB of the mode is on so we need to mask it.
         let mode = metadata.permissions().mode() & 0o777;
 
-        if mode != 0o440 {
+        if mode != SUDOERS_MODE {
             return Err(io::Error::other(format!(
-                "{}: bad permissions, should be mode 0440, but found {mode:04o}",
+                "{}: bad permissions, should be mode {SUDOERS_MODE:04o}, but found {mode:04o}",
                 sudoers_path.display()
             )));
         }
@@ -111,18 +112,19 @@ fn check(file_arg: Option<&str>, perms: bool, owner: bool) -> io::Result<()> {
     if file_arg.is_none() || owner {
         let owner = (metadata.uid(), metadata.gid());
 
-        if owner != (0, 0) {
+        if owner != (SUDOERS_UID, SUDOERS_GID) {
             return Err(io::Error::other(format!(
-                "{}: wrong owner (uid, gid) should be (0, 0), but found {owner:?}",
+                "{}: wrong owner (uid, gid) should be ({SUDOERS_UID}, {SUDOERS_GID}), but found {owner:?}",
                 sudoers_path.display()
             )));
         }
     }
 
-    let (_sudoers, errors) = Sudoers::read(&sudoers_file, &sudoers_path)?;
+    let (sudoers, errors) = Sudoers::read(&sudoers_file, &sudoers_path)?;
 
     if errors.is_empty() {
         writeln!(io::stdout(), "{}: parsed OK", sudoers_path.display())?;
+        print_warnings(&sudoers, &sudoers_path);
         return Ok(());
     }
 
@@ -130,6 +132,7 @@ fn check(file_arg: Option<&str>, perms: bool, owner: bool) -> io::Result<()> {
         message,
         source,
         location,
+        ..
     } in errors
     {
         let path = source.as_deref().unwrap_or(&sudoers_path);
@@ -139,6 +142,24 @@ fn check(file_arg: Option<&str>, perms: bool, owner: bool) -> io::Result<()> {
     Err(io::Error::other("invalid sudoers file"))
 }
 
+/// Warnings do not prevent the sudoers file from being used, so they are only reported.
+fn print_warnings(sudoers: &Sudoers, sudoers_path: &Path) {
+    for sudoers::Error {
+        message,
+        source,
+        location,
+        ..
+    } in sudoers.validate_semantics()
+    {
+        let path = source.as_deref().unwrap_or(sudoers_path);
+        if location.is_some() {
+            diagnostic::diagnostic!("warning: {message}", path @ location);
+        } else {
+            eprintln_ignore_io_error!("{}: warning: {message}", path.display());
+        }
+    }
+}
+
 fn run(file_arg: Option<&str>, perms: bool, owner: bool) -> io::Result<()> {
     let sudoers_path = &file_arg
         .map(PathBuf::from)
@@ -185,11 +206,11 @@ fn run(file_arg: Option<&str>, perms: bool, owner: bool) -> io::Result<()> {
     })?;
 
     if perms || file_arg.is_none() {
-        sudoers_file.set_permissions(Permissions::from_mode(0o440))?;
+        sudoers_file.set_permissions(Permissions::from_mode(SUDOERS_MODE))?;
     }
 
     if owner || file_arg.is_none() {
-        fchown(&sudoers_file, Some(0), Some(0))?;
+        fchown(&sudoers_file, Some(SUDOERS_UID), Some(SUDOERS_GID))?;
     }
 
     let signal_stream = SignalStream::init()?;
@@ -315,6 +336,7 @@ fn edit_sudoers_file(
                 message,
                 source,
                 location,
+                ..
             } in errors
             {
                 let path = source.as_deref().unwrap_or(sudoers_path);
@@ -332,6 +354,8 @@ fn edit_sudoers_file(
                 _ => continue,
             }
         } else {
+            print_warnings(&sudoers, sudoers_path);
+
             if sudoers_path == Path::new("/etc/sudoers")
                 && sudo_visudo_is_allowed(sudoers, &host_name) == Some(false)
             {
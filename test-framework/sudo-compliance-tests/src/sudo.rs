@@ -5,6 +5,10 @@ mod cli;
 mod env_reset;
 mod flag_background;
 mod flag_chdir;
+mod flag_chroot;
+mod flag_close_from;
+mod flag_command_timeout;
+mod flag_dry_run;
 mod flag_group;
 mod flag_help;
 mod flag_list;
@@ -39,6 +39,7 @@ dup! {
     child_terminated_by_signal,
     sigtstp_works,
     sigalrm_terminates_command,
+    sigalrm_terminates_command_process_group,
     sigchld_is_ignored,
 }
 
@@ -185,6 +186,44 @@ echo did-not-deadlock
     }
 }
 
+#[test]
+fn terminal_hangup_sends_sighup_to_command() {
+    let inner_sh = "\
+trap 'echo got signal > /tmp/output; exit 0' HUP
+sleep 10 &
+wait
+";
+
+    let env = Env([SUDOERS_ALL_ALL_NOPASSWD, SUDOERS_USE_PTY])
+        .file("/root/inner.sh", inner_sh)
+        .build();
+
+    // `script` runs sudo on a pty of its own; killing it closes the leader side of that pty, which
+    // revokes the terminal that sudo runs on
+    let child = Command::new("script")
+        .args(["-q", "-c", "sudo sh /root/inner.sh", "/dev/null"])
+        .spawn(&env);
+
+    // Wait for inner.sh to install the signal handler
+    std::thread::sleep(Duration::from_secs(1));
+
+    Command::new("sh")
+        .args(["-c", "kill -KILL $(pidof script)"])
+        .output(&env)
+        .assert_success();
+    let _ = child.wait();
+
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(
+            "for _ in $(seq 1 50); do pidof sudo > /dev/null || break; sleep 0.1; done; \
+             if pidof sudo; then echo sudo did not exit; fi; cat /tmp/output",
+        )
+        .output(&env);
+
+    assert_eq!("got signal", output.stdout());
+}
+
 fn sigalrm_terminates_command(tty: bool) {
     let expected = "got signal";
     let expects_signal = "/root/expects-signal.sh";
@@ -214,6 +253,49 @@ fn sigalrm_terminates_command(tty: bool) {
     assert_eq!(expected, actual);
 }
 
+fn sigalrm_terminates_command_process_group(tty: bool) {
+    let command_pid_file = "/tmp/command.pid";
+    let background_pid_file = "/tmp/background.pid";
+    let kill_sudo = "/root/kill-sudo.sh";
+    let env = Env([SUDOERS_USER_ALL_NOPASSWD, SUDOERS_USE_PTY])
+        .user(USERNAME)
+        .file(kill_sudo, include_str!("kill-sudo.sh"))
+        .build();
+
+    // the command starts a child process in the background and then waits for it
+    let child = Command::new("sudo")
+        .args([
+            "sh",
+            "-c",
+            &format!(
+                "echo $$ > {command_pid_file}; sleep 60 & echo $! > {background_pid_file}; wait"
+            ),
+        ])
+        .as_user(USERNAME)
+        .spawn(&env);
+
+    // Wait for the background process to be spawned
+    std::thread::sleep(std::time::Duration::from_secs(1));
+
+    Command::new("sh")
+        .args([kill_sudo, "-ALRM"])
+        .tty(tty)
+        .output(&env)
+        .assert_success();
+
+    assert!(!child.wait().status().success());
+
+    // both the command and the process it started are gone
+    for pid_file in [command_pid_file, background_pid_file] {
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(format!("kill -0 $(cat {pid_file})"))
+            .output(&env);
+
+        assert!(!output.status().success(), "{pid_file}");
+    }
+}
+
 fn sigchld_is_ignored(tty: bool) {
     let expected = "got signal";
     let expects_signal = "/root/expects-signal.sh";
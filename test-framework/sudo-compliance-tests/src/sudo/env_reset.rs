@@ -30,9 +30,7 @@ fn some_vars_are_set() {
     let mut sudo_env = helpers::parse_env_output(&stdout);
 
     // # man sudo
-    if sudo_test::is_original_sudo() {
-        assert_eq!(Some("/var/mail/root"), sudo_env.remove("MAIL"));
-    }
+    assert_eq!(Some("/var/mail/root"), sudo_env.remove("MAIL"));
     // "Set to the home directory of the target user"
     assert_eq!(Some("/root"), sudo_env.remove("HOME"));
 
@@ -133,12 +131,10 @@ fn user_dependent_vars() {
         Some(format!("/home/{USERNAME}")).as_deref(),
         sudo_env.remove("HOME")
     );
-    if sudo_test::is_original_sudo() {
-        assert_eq!(
-            Some(format!("/var/mail/{USERNAME}")).as_deref(),
-            sudo_env.remove("MAIL")
-        );
-    }
+    assert_eq!(
+        Some(format!("/var/mail/{USERNAME}")).as_deref(),
+        sudo_env.remove("MAIL")
+    );
     assert_eq!(Some(shell_path), sudo_env.remove("SHELL"));
     assert_eq!(Some(USERNAME), sudo_env.remove("LOGNAME"));
     assert_eq!(Some(USERNAME), sudo_env.remove("USER"));
@@ -204,9 +200,7 @@ fn some_vars_are_preserved() {
 
     // not preserved
     assert_eq!(Some("/root"), sudo_env.remove("HOME"));
-    if sudo_test::is_original_sudo() {
-        assert_eq!(Some("/var/mail/root"), sudo_env.remove("MAIL"));
-    }
+    assert_eq!(Some("/var/mail/root"), sudo_env.remove("MAIL"));
     assert_eq!(Some("/bin/sh"), sudo_env.remove("SHELL"));
     assert_eq!(Some("root"), sudo_env.remove("LOGNAME"));
     assert_eq!(Some("root"), sudo_env.remove("USER"));
@@ -255,3 +249,36 @@ fn vars_whose_values_start_with_parentheses_are_removed() {
     assert_eq!(Some(SUDO_ENV_DEFAULT_PATH), sudo_env.get("PATH").copied());
     assert_eq!(Some(SUDO_ENV_DEFAULT_TERM), sudo_env.get("TERM").copied());
 }
+
+#[test]
+fn env_file_vars_are_added() {
+    if sudo_test::is_original_sudo() {
+        // ogsudo does not apply env_delete to the env_file
+        return;
+    }
+
+    let env = Env([
+        SUDOERS_ROOT_ALL_NOPASSWD,
+        "Defaults env_file=/etc/sudo.env",
+        "Defaults env_keep += KEPT",
+    ])
+    .file(
+        "/etc/sudo.env",
+        "FROM_FILE=yes\nexport KEPT='from file'\nTERM=from-file\nLD_PRELOAD=/tmp/evil.so\nnot valid",
+    )
+    .build();
+
+    let stdout = Command::new("env")
+        .args(["KEPT=from user", "sudo", "env"])
+        .output(&env)
+        .stdout();
+    let sudo_env = helpers::parse_env_output(&stdout);
+
+    assert_eq!(Some(&"yes"), sudo_env.get("FROM_FILE"));
+    // preserved variables take precedence over the env_file
+    assert_eq!(Some(&"from user"), sudo_env.get("KEPT"));
+    // the env_file takes precedence over default values
+    assert_eq!(Some(&"from-file"), sudo_env.get("TERM"));
+    // env_delete applies to the env_file
+    assert_eq!(None, sudo_env.get("LD_PRELOAD"));
+}
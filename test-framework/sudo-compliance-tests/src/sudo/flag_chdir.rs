@@ -91,6 +91,46 @@ fn cwd_set_to_non_glob_value_then_cannot_use_chdir_flag() {
     assert_contains!(output.stderr(), diagnostic);
 }
 
+#[test]
+fn rejected_chdir_names_the_allowed_directory() {
+    if sudo_test::is_original_sudo() {
+        // naming the directory from CWD= is a sudo-rs extension
+        return;
+    }
+
+    let env = Env(TextFile("ALL ALL=(ALL:ALL) CWD=/var/www NOPASSWD: ALL")).build();
+    let output = Command::new("sudo")
+        .args(["--chdir", "/tmp", "pwd"])
+        .output(&env);
+
+    output.assert_exit_code(1);
+    assert_contains!(
+        output.stderr(),
+        format!(
+            "you are not allowed to use '--chdir /tmp' with '{BIN_PWD}'; it can only be run in '/var/www'"
+        )
+    );
+}
+
+#[test]
+fn rejected_chdir_without_cwd_explains_why() {
+    if sudo_test::is_original_sudo() {
+        // the explanation is a sudo-rs extension
+        return;
+    }
+
+    let env = Env(TextFile(SUDOERS_ALL_ALL_NOPASSWD)).build();
+    let output = Command::new("sudo")
+        .args(["--chdir", "/tmp", "pwd"])
+        .output(&env);
+
+    output.assert_exit_code(1);
+    assert_contains!(
+        output.stderr(),
+        "the sudoers policy does not allow choosing its working directory"
+    );
+}
+
 #[test]
 fn cwd_set_to_non_glob_value_then_cannot_use_that_path_with_chdir_flag() {
     let path = "/root";
@@ -0,0 +1,135 @@
+use crate::SUDOERS_ALL_ALL_NOPASSWD;
+use sudo_test::{Command, Env, TextFile};
+
+/// A root directory that contains a copy of the system's programs and libraries.
+fn jail(env: &Env) {
+    Command::new("sh")
+        .args([
+            "-c",
+            "mkdir -p /srv/jail/tmp && for dir in bin lib lib64 usr; do \
+             if [ -e /$dir ]; then cp -a /$dir /srv/jail/; fi; done && \
+             echo inside > /srv/jail/tmp/marker",
+        ])
+        .output(env)
+        .assert_success();
+}
+
+#[test]
+fn runs_command_in_root_directory() {
+    let env = Env(TextFile("ALL ALL=(ALL:ALL) CHROOT=* NOPASSWD: ALL")).build();
+    jail(&env);
+
+    let output = Command::new("sudo")
+        .args(["-R", "/srv/jail", "cat", "/tmp/marker"])
+        .output(&env);
+
+    output.assert_success();
+    assert_eq!(output.stdout(), "inside");
+}
+
+#[test]
+fn chdir_is_relative_to_root_directory() {
+    let env = Env(TextFile("ALL ALL=(ALL:ALL) CHROOT=* CWD=* NOPASSWD: ALL")).build();
+    jail(&env);
+
+    let output = Command::new("sudo")
+        .args(["-R", "/srv/jail", "-D", "/tmp", "cat", "marker"])
+        .output(&env);
+
+    output.assert_success();
+    assert_eq!(output.stdout(), "inside");
+}
+
+#[test]
+fn chroot_not_set_cannot_change_root() {
+    let env = Env(TextFile(SUDOERS_ALL_ALL_NOPASSWD)).build();
+    jail(&env);
+
+    let output = Command::new("sudo")
+        .args(["-R", "/srv/jail", "true"])
+        .output(&env);
+
+    output.assert_exit_code(1);
+    if sudo_test::is_original_sudo() {
+        assert_contains!(
+            output.stderr(),
+            "you are not permitted to use the -R option"
+        );
+    } else {
+        assert_contains!(
+            output.stderr(),
+            "you are not allowed to use '--chroot /srv/jail'"
+        );
+    }
+}
+
+#[test]
+fn chroot_set_to_path_requires_that_path() {
+    if sudo_test::is_original_sudo() {
+        // ogsudo looks up the command again after the policy check
+        return;
+    }
+
+    let env = Env(TextFile("ALL ALL=(ALL:ALL) CHROOT=/srv/jail NOPASSWD: ALL")).build();
+    jail(&env);
+
+    let output = Command::new("sudo")
+        .args(["-R", "/srv/jail", "cat", "/tmp/marker"])
+        .output(&env);
+    output.assert_success();
+    assert_eq!(output.stdout(), "inside");
+
+    let output = Command::new("sudo").args(["true"]).output(&env);
+    output.assert_exit_code(1);
+    assert_contains!(output.stderr(), "must be run with '--chroot /srv/jail'");
+}
+
+#[test]
+fn fails_for_non_existent_root_directory() {
+    if sudo_test::is_original_sudo() {
+        // the diagnostic differs
+        return;
+    }
+
+    let env = Env(TextFile("ALL ALL=(ALL:ALL) CHROOT=* NOPASSWD: ALL")).build();
+
+    let output = Command::new("sudo")
+        .args(["-R", "/path/to/nowhere", "true"])
+        .output(&env);
+
+    output.assert_exit_code(1);
+    assert_contains!(
+        output.stderr(),
+        "unable to change root directory to /path/to/nowhere: no such directory"
+    );
+}
+
+#[test]
+fn non_existent_root_directory_is_not_revealed_without_permission() {
+    let env = Env(TextFile(SUDOERS_ALL_ALL_NOPASSWD)).build();
+
+    let output = Command::new("sudo")
+        .args(["-R", "/path/to/nowhere", "true"])
+        .output(&env);
+
+    output.assert_exit_code(1);
+    assert_not_contains!(output.stderr(), "no such directory");
+}
+
+#[test]
+fn command_outside_root_directory_is_not_found() {
+    let env = Env(TextFile("ALL ALL=(ALL:ALL) CHROOT=* NOPASSWD: ALL")).build();
+    jail(&env);
+    Command::new("sh")
+        .args(["-c", "touch /only-outside && chmod +x /only-outside"])
+        .output(&env)
+        .assert_success();
+
+    let output = Command::new("sudo")
+        .args(["-R", "/srv/jail", "/only-outside"])
+        .output(&env);
+
+    output.assert_exit_code(1);
+    assert_contains!(output.stderr(), "/only-outside");
+    assert_contains!(output.stderr(), "command not found");
+}
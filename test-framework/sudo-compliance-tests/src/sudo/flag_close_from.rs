@@ -0,0 +1,60 @@
+use crate::SUDOERS_ALL_ALL_NOPASSWD;
+use sudo_test::{Command, Env};
+
+const SUDOERS_CLOSEFROM_OVERRIDE: &str = "Defaults closefrom_override";
+
+/// A script that prints which of the file descriptors 3 and 4 are still open in the command
+fn list_fds(sudo_flags: &str) -> String {
+    format!(
+        "exec 3</dev/null 4</dev/null; \
+         sudo {sudo_flags} sh -c 'for fd in 3 4; do [ -e /proc/$$/fd/$fd ] && echo $fd; done; true'"
+    )
+}
+
+#[test]
+fn closes_all_descriptors_by_default() {
+    let env = Env([SUDOERS_CLOSEFROM_OVERRIDE, SUDOERS_ALL_ALL_NOPASSWD]).build();
+
+    let output = Command::new("sh").args(["-c", &list_fds("")]).output(&env);
+    output.assert_success();
+    assert_eq!(output.stdout(), "");
+}
+
+#[test]
+fn keeps_descriptors_below_the_given_number() {
+    let env = Env([SUDOERS_CLOSEFROM_OVERRIDE, SUDOERS_ALL_ALL_NOPASSWD]).build();
+
+    let output = Command::new("sh")
+        .args(["-c", &list_fds("-C 4")])
+        .output(&env);
+    output.assert_success();
+    assert_eq!(output.stdout(), "3");
+}
+
+#[test]
+fn requires_closefrom_override() {
+    let env = Env(SUDOERS_ALL_ALL_NOPASSWD).build();
+
+    let output = Command::new("sudo").args(["-C", "4", "true"]).output(&env);
+    output.assert_exit_code(1);
+    let diagnostic = if sudo_test::is_original_sudo() {
+        "you are not permitted to use the -C option"
+    } else {
+        "you are not allowed to use the '--close-from' option"
+    };
+    assert_contains!(output.stderr(), diagnostic);
+}
+
+#[test]
+fn rejects_the_io_streams() {
+    let env = Env([SUDOERS_CLOSEFROM_OVERRIDE, SUDOERS_ALL_ALL_NOPASSWD]).build();
+
+    let output = Command::new("sudo").args(["-C", "2", "true"]).output(&env);
+    output.assert_exit_code(1);
+    let diagnostic = if sudo_test::is_original_sudo() {
+        "the argument to -C must be a number greater than or equal to 3"
+    } else {
+        "the argument to '-C' must be a number greater than or equal to 3"
+    };
+    assert_contains!(output.stderr(), diagnostic);
+}
@@ -0,0 +1,133 @@
+use crate::{SUDOERS_ALL_ALL_NOPASSWD, SUDOERS_NOT_USE_PTY, SUDOERS_USE_PTY};
+use sudo_test::{Command, Env};
+
+const SUDOERS_USER_COMMAND_TIMEOUTS: &str = "Defaults user_command_timeouts";
+
+/// A script that runs a command with sudo and prints how many seconds it took
+fn timed(sudo_args: &str) -> String {
+    format!("start=$(date +%s); sudo {sudo_args}; echo $(( $(date +%s) - start ))")
+}
+
+fn elapsed(stdout: &str) -> u64 {
+    stdout.lines().last().unwrap().parse().unwrap()
+}
+
+#[test]
+fn terminates_command_after_timeout() {
+    let env = Env([SUDOERS_USER_COMMAND_TIMEOUTS, SUDOERS_ALL_ALL_NOPASSWD]).build();
+
+    let output = Command::new("sh")
+        .args(["-c", &timed("-T 1 sleep 30")])
+        .output(&env);
+
+    output.assert_success();
+    assert!(elapsed(&output.stdout()) < 10);
+}
+
+#[test]
+fn terminates_command_in_a_pty_after_timeout() {
+    let env = Env([SUDOERS_USER_COMMAND_TIMEOUTS, SUDOERS_ALL_ALL_NOPASSWD]).build();
+
+    let output = Command::new("sh")
+        .args(["-c", &timed("-T 1 sleep 30")])
+        .tty(true)
+        .output(&env);
+
+    output.assert_success();
+    assert!(elapsed(&output.stdout()) < 10);
+}
+
+/// Runs a command that starts a background process and checks that the timeout terminates both
+fn terminates_processes_started_by_the_command(use_pty: &str) {
+    let pid_file = "/tmp/background.pid";
+    let env = Env([
+        use_pty,
+        SUDOERS_USER_COMMAND_TIMEOUTS,
+        SUDOERS_ALL_ALL_NOPASSWD,
+    ])
+    .build();
+
+    let script = format!("sleep 60 & echo $! > {pid_file}; wait");
+    let output = Command::new("sh")
+        .args(["-c", &timed(&format!("-T 1 sh -c '{script}'"))])
+        .output(&env);
+
+    output.assert_success();
+    assert!(elapsed(&output.stdout()) < 10);
+
+    let output = Command::new("sh")
+        .args(["-c", &format!("kill -0 $(cat {pid_file})")])
+        .output(&env);
+
+    assert!(!output.status().success());
+}
+
+#[test]
+fn terminates_processes_started_by_the_command_without_pty() {
+    terminates_processes_started_by_the_command(SUDOERS_NOT_USE_PTY);
+}
+
+#[test]
+fn terminates_processes_started_by_the_command_in_a_pty() {
+    terminates_processes_started_by_the_command(SUDOERS_USE_PTY);
+}
+
+#[test]
+fn command_that_finishes_in_time_is_not_affected() {
+    let env = Env([SUDOERS_USER_COMMAND_TIMEOUTS, SUDOERS_ALL_ALL_NOPASSWD]).build();
+
+    let output = Command::new("sudo")
+        .args(["-T", "30", "sh", "-c", "echo done"])
+        .output(&env);
+
+    output.assert_success();
+    assert_eq!(output.stdout(), "done");
+}
+
+#[test]
+fn command_timeout_default_applies_without_flag() {
+    let env = Env(["Defaults command_timeout=1", SUDOERS_ALL_ALL_NOPASSWD]).build();
+
+    let output = Command::new("sh")
+        .args(["-c", &timed("sleep 30")])
+        .output(&env);
+
+    output.assert_success();
+    assert!(elapsed(&output.stdout()) < 10);
+}
+
+#[test]
+fn requires_user_command_timeouts() {
+    let env = Env(SUDOERS_ALL_ALL_NOPASSWD).build();
+
+    let output = Command::new("sudo").args(["-T", "30", "true"]).output(&env);
+
+    output.assert_exit_code(1);
+    assert_contains!(
+        output.stderr(),
+        "you are not allowed to set a command timeout"
+    );
+}
+
+#[test]
+fn user_cannot_extend_command_timeout() {
+    if sudo_test::is_original_sudo() {
+        // the diagnostic differs
+        return;
+    }
+
+    let env = Env([
+        "Defaults command_timeout=10",
+        SUDOERS_USER_COMMAND_TIMEOUTS,
+        SUDOERS_ALL_ALL_NOPASSWD,
+    ])
+    .build();
+
+    let output = Command::new("sudo").args(["-T", "30", "true"]).output(&env);
+
+    output.assert_exit_code(1);
+    assert_contains!(
+        output.stderr(),
+        "the command timeout may not exceed 10 seconds"
+    );
+}
@@ -0,0 +1,65 @@
+use sudo_test::{Command, Env, User};
+
+use crate::{SUDOERS_ALL_ALL_NOPASSWD, USERNAME};
+
+#[test]
+fn shows_command_and_target_without_running_it() {
+    if sudo_test::is_original_sudo() {
+        // --dry-run is a sudo-rs extension
+        return;
+    }
+
+    let env = Env(SUDOERS_ALL_ALL_NOPASSWD).user(USERNAME).build();
+
+    let stdout = Command::new("sudo")
+        .args(["--dry-run", "-u", USERNAME, "touch", "/tmp/ran"])
+        .output(&env)
+        .stdout();
+
+    assert_contains!(stdout, "Command: /");
+    assert_contains!(stdout, "bin/touch");
+    assert_contains!(stdout, "Arguments: /tmp/ran");
+    assert_contains!(stdout, format!("User: {USERNAME} (uid "));
+    assert_contains!(stdout, "Environment: ");
+
+    Command::new("sh")
+        .args(["-c", "test ! -e /tmp/ran"])
+        .output(&env)
+        .assert_success();
+}
+
+#[test]
+fn does_not_show_environment_values() {
+    if sudo_test::is_original_sudo() {
+        // --dry-run is a sudo-rs extension
+        return;
+    }
+
+    let env = Env([SUDOERS_ALL_ALL_NOPASSWD, "Defaults env_keep += SECRET"]).build();
+
+    let stdout = Command::new("env")
+        .args(["SECRET=hunter2", "sudo", "--dry-run", "true"])
+        .output(&env)
+        .stdout();
+
+    assert_contains!(stdout, "SECRET");
+    assert_not_contains!(stdout, "hunter2");
+}
+
+#[test]
+fn requires_authorization() {
+    if sudo_test::is_original_sudo() {
+        // --dry-run is a sudo-rs extension
+        return;
+    }
+
+    let env = Env("").user(User(USERNAME)).build();
+
+    let output = Command::new("sudo")
+        .args(["--dry-run", "-n", "true"])
+        .as_user(USERNAME)
+        .output(&env);
+
+    output.assert_exit_code(1);
+    assert_not_contains!(output.stdout_unchecked(), "Command:");
+}
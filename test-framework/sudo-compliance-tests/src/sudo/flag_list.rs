@@ -345,6 +345,36 @@ fn when_several_commands_specified_only_first_displayed_with_fully_qualified_pat
     assert_eq!(actual, expected);
 }
 
+#[test]
+fn when_command_is_specified_long_format_shows_the_matching_options() {
+    if sudo_test::is_original_sudo() {
+        // describing the matching rule is a sudo-rs extension
+        return;
+    }
+
+    let env = Env("ALL ALL=(ALL:ALL) NOPASSWD: /usr/bin/true")
+        .user(USERNAME)
+        .build();
+
+    let output = Command::new("sudo")
+        .args(["-l", "-l", "-u", USERNAME, "true"])
+        .as_user(USERNAME)
+        .output(&env);
+
+    output.assert_success();
+
+    let expected = format!(
+        "Sudoers entry:
+    RunAsUsers: {USERNAME}
+    Options: !authenticate
+    Commands:
+\t{BIN_TRUE}"
+    );
+    let actual = output.stdout();
+
+    assert_eq!(actual, expected);
+}
+
 #[test]
 fn when_command_is_forbidden_exit_with_status_1_no_stderr() {
     let env = Env(format!("ALL ALL=(ALL:ALL) NOPASSWD: {BIN_FALSE}"))
@@ -360,6 +390,49 @@ fn when_command_is_forbidden_exit_with_status_1_no_stderr() {
     assert!(output.stderr().is_empty());
 }
 
+#[test]
+fn pseudo_commands_are_listed_without_a_path() {
+    let env = Env(format!(
+        "ALL ALL=(ALL:ALL) NOPASSWD: {BIN_TRUE}, list, sudoedit /etc/hosts"
+    ))
+    .user(USERNAME)
+    .build();
+
+    let output = Command::new("sudo")
+        .arg("-l")
+        .as_user(USERNAME)
+        .output(&env);
+
+    output.assert_success();
+    assert_contains!(
+        output.stdout(),
+        format!("(ALL : ALL) NOPASSWD: {BIN_TRUE}, list, sudoedit /etc/hosts")
+    );
+}
+
+#[test]
+fn sudoedit_is_checked_as_a_pseudo_command() {
+    let env = Env("ALL ALL=(ALL:ALL) NOPASSWD: sudoedit /etc/hosts")
+        .user(USERNAME)
+        .build();
+
+    let output = Command::new("sudo")
+        .args(["-l", "sudoedit", "/etc/hosts"])
+        .as_user(USERNAME)
+        .output(&env);
+
+    output.assert_success();
+    assert_eq!(output.stdout(), "sudoedit /etc/hosts");
+
+    let output = Command::new("sudo")
+        .args(["-l", "sudoedit", "/etc/motd"])
+        .as_user(USERNAME)
+        .output(&env);
+
+    output.assert_exit_code(1);
+    assert!(output.stderr().is_empty());
+}
+
 #[test]
 fn uppercase_u_flag_matches_on_first_component_of_sudoers_rules() {
     let hostname = "container";
@@ -48,3 +48,57 @@ fn current_user_is_root() {
 
     output.assert_success();
 }
+
+#[test]
+fn lists_the_privileges_of_the_other_user() {
+    let other_user = "ghost";
+    let hostname = "container";
+    let env = Env(format!(
+        "{USERNAME} ALL=({other_user}) NOPASSWD: list
+{other_user} ALL=(ALL:ALL) /usr/bin/true"
+    ))
+    .user(USERNAME)
+    .user(other_user)
+    .hostname(hostname)
+    .build();
+
+    let output = Command::new("sudo")
+        .args(["-l", "-U", other_user])
+        .as_user(USERNAME)
+        .output(&env);
+
+    output.assert_success();
+    let stdout = output.stdout();
+    assert_contains!(
+        stdout,
+        format!("User {other_user} may run the following commands on {hostname}:")
+    );
+    assert_contains!(stdout, "(ALL : ALL) /usr/bin/true");
+    assert_not_contains!(stdout, "list");
+}
+
+#[test]
+fn list_privilege_does_not_extend_to_other_users() {
+    let other_user = "ghost";
+    let third_user = "casper";
+    let hostname = "container";
+    let env = Env(format!("{USERNAME} ALL=({other_user}) NOPASSWD: list"))
+        .user(USERNAME)
+        .user(other_user)
+        .user(third_user)
+        .hostname(hostname)
+        .build();
+
+    let output = Command::new("sudo")
+        .args(["-l", "-U", third_user])
+        .as_user(USERNAME)
+        .output(&env);
+
+    output.assert_exit_code(1);
+    assert_contains!(
+        output.stderr(),
+        format!(
+            "Sorry, user {USERNAME} is not allowed to execute 'list' as {third_user} on {hostname}."
+        )
+    );
+}
@@ -1,6 +1,11 @@
+use std::collections::HashMap;
+
 use sudo_test::{Command, Env, TextFile, User};
 
-use crate::{SUDOERS_ALL_ALL_NOPASSWD, USERNAME, helpers};
+use crate::{
+    SUDO_ENV_DEFAULT_PATH, SUDO_ENV_DEFAULT_TERM, SUDO_RS_IS_UNSTABLE, SUDOERS_ALL_ALL_NOPASSWD,
+    USERNAME, helpers,
+};
 
 macro_rules! assert_snapshot {
     ($($tt:tt)*) => {
@@ -38,6 +43,78 @@ fn if_home_directory_does_not_exist_executes_program_without_changing_the_workin
     }
 }
 
+#[test]
+fn if_home_directory_does_not_exist_and_inaccessible_home_is_root_uses_root_directory() {
+    if sudo_test::is_original_sudo() {
+        // `inaccessible_home` is a sudo-rs extension
+        return;
+    }
+
+    let env = Env([SUDOERS_ALL_ALL_NOPASSWD, "Defaults inaccessible_home=root"])
+        .user(USERNAME)
+        .build();
+
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(format!(
+            "cd /tmp; sudo -u {USERNAME} -i pwd; sudo -u {USERNAME} -i printenv HOME"
+        ))
+        .output(&env);
+
+    output.assert_success();
+    assert_contains!(output.stderr(), "unable to change directory");
+    assert_eq!(output.stdout(), format!("/\n/home/{USERNAME}"));
+}
+
+#[test]
+fn if_home_directory_does_not_exist_and_inaccessible_home_is_error_does_not_execute_program() {
+    if sudo_test::is_original_sudo() {
+        // `inaccessible_home` is a sudo-rs extension
+        return;
+    }
+
+    let env = Env([SUDOERS_ALL_ALL_NOPASSWD, "Defaults inaccessible_home=error"])
+        .user(USERNAME)
+        .build();
+
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(format!("cd /tmp; sudo -u {USERNAME} -i echo executed"))
+        .output(&env);
+
+    output.assert_exit_code(1);
+    assert_contains!(
+        output.stderr(),
+        format!("unable to change directory to /home/{USERNAME}")
+    );
+    assert_eq!(output.stdout_unchecked(), "");
+}
+
+#[test]
+fn missing_home_directory_names_the_target_user() {
+    if sudo_test::is_original_sudo() {
+        // `inaccessible_home` is a sudo-rs extension
+        return;
+    }
+
+    let env = Env([SUDOERS_ALL_ALL_NOPASSWD, "Defaults inaccessible_home=error"])
+        .user(USERNAME)
+        .build();
+
+    let output = Command::new("sudo")
+        .args(["-u", USERNAME, "-i", "echo", "executed"])
+        .output(&env);
+
+    output.assert_exit_code(1);
+    assert_contains!(
+        output.stderr(),
+        format!(
+            "unable to change directory to /home/{USERNAME}: home directory of user '{USERNAME}' does not exist"
+        )
+    );
+    assert_eq!(output.stdout_unchecked(), "");
+}
+
 #[test]
 fn sets_home_directory_as_working_directory() {
     let expected = format!("/home/{USERNAME}");
@@ -201,7 +278,10 @@ fn shell_does_not_exist() {
     if sudo_test::is_original_sudo() {
         assert_snapshot!(stderr);
     } else {
-        assert_contains!(stderr, "'/tmp/my-shell': command not found");
+        assert_contains!(
+            stderr,
+            format!("'/tmp/my-shell': login shell of user '{USERNAME}' is not an executable file")
+        );
     }
 }
 
@@ -227,6 +307,29 @@ fn insufficient_permissions_to_execute_shell() {
     }
 }
 
+#[test]
+fn empty_shell_field_uses_bin_sh() {
+    let env = Env(SUDOERS_ALL_ALL_NOPASSWD)
+        .user(User(USERNAME).create_home_directory())
+        .build();
+
+    // clear the last (shell) field of the passwd entry
+    Command::new("sed")
+        .args([
+            "-i",
+            &format!("s/^\\({USERNAME}:.*:\\)[^:]*$/\\1/"),
+            "/etc/passwd",
+        ])
+        .output(&env)
+        .assert_success();
+
+    let output = Command::new("sudo")
+        .args(["-u", USERNAME, "-i", "echo", "$0", "$SHELL"])
+        .output(&env);
+
+    assert_eq!(output.stdout(), "-sh /bin/sh");
+}
+
 #[test]
 fn shell_with_open_permissions_is_accepted() {
     let shell_path = "/tmp/my-shell";
@@ -241,6 +344,54 @@ fn shell_with_open_permissions_is_accepted() {
         .assert_success();
 }
 
+#[test]
+fn login_env_only_contains_minimal_set_of_vars() {
+    // with `env` as the login shell, the environment that sudo set up is printed as is, without
+    // any changes made by the profile of a real shell
+    let env = Env(SUDOERS_ALL_ALL_NOPASSWD)
+        .user(User(USERNAME).shell("/usr/bin/env").create_home_directory())
+        .build();
+
+    let sudo_abs_path = Command::new("which").arg("sudo").output(&env).stdout();
+
+    let stdout = Command::new("env")
+        .args([
+            "-i",
+            SUDO_RS_IS_UNSTABLE,
+            "SHOULD_BE_REMOVED=1",
+            "DISPLAY=:0",
+            "HOME=/tmp",
+            "MAIL=/tmp/mail",
+            &sudo_abs_path,
+            "-u",
+            USERNAME,
+            "-i",
+        ])
+        .output(&env)
+        .stdout();
+    let mut sudo_env = helpers::parse_env_output(&stdout);
+
+    let home = format!("/home/{USERNAME}");
+    let mail = format!("/var/mail/{USERNAME}");
+    assert_eq!(Some(home.as_str()), sudo_env.remove("HOME"));
+    assert_eq!(Some(mail.as_str()), sudo_env.remove("MAIL"));
+    assert_eq!(Some("/usr/bin/env"), sudo_env.remove("SHELL"));
+    assert_eq!(Some(USERNAME), sudo_env.remove("USER"));
+    assert_eq!(Some(USERNAME), sudo_env.remove("LOGNAME"));
+    assert_eq!(Some(SUDO_ENV_DEFAULT_PATH), sudo_env.remove("PATH"));
+    assert_eq!(Some(SUDO_ENV_DEFAULT_TERM), sudo_env.remove("TERM"));
+
+    // in the default `env_keep` list
+    assert_eq!(Some(":0"), sudo_env.remove("DISPLAY"));
+
+    for var in ["SUDO_COMMAND", "SUDO_GID", "SUDO_UID", "SUDO_USER"] {
+        assert!(sudo_env.remove(var).is_some(), "{var} is not set");
+    }
+    sudo_env.remove("SUDO_HOME");
+
+    assert_eq!(HashMap::new(), sudo_env);
+}
+
 #[test]
 fn login_env_overrides_env_keep() {
     let env = Env("
@@ -302,3 +453,27 @@ Defaults env_keep = \"PATH HOME SHELL USER LOGNAME\"
         &"/usr/local/bin:/usr/bin:/bin:/usr/local/games:/usr/games".to_string()
     );
 }
+
+#[test]
+fn login_env_ignores_preserve_environment() {
+    let env = Env("ALL ALL=(ALL:ALL) NOPASSWD:SETENV: ALL")
+        .user(User(USERNAME).shell("/usr/bin/env").create_home_directory())
+        .build();
+
+    let stdout = Command::new("env")
+        .args([
+            "SHOULD_BE_REMOVED=1",
+            "SHELL=/bin/true",
+            "sudo",
+            "-E",
+            "-u",
+            USERNAME,
+            "-i",
+        ])
+        .output(&env)
+        .stdout();
+    let sudo_env = helpers::parse_env_output(&stdout);
+
+    assert!(!sudo_env.contains_key("SHOULD_BE_REMOVED"));
+    assert_eq!(Some(&"/usr/bin/env"), sudo_env.get("SHELL"));
+}
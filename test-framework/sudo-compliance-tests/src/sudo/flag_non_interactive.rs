@@ -122,6 +122,33 @@ fn no_noninteractive_auth() {
     }
 }
 
+#[test]
+fn noninteractive_auth_fails_when_pam_prompts() {
+    let env = Env("Defaults noninteractive_auth\nALL ALL=(ALL:ALL) ALL")
+        .user(User(USERNAME).password(PASSWORD))
+        .build();
+
+    // with a terminal, a blocking read would make this test hang
+    let output = Command::new("sudo")
+        .args(["-n", "true"])
+        .as_user(USERNAME)
+        .tty(true)
+        .output(&env);
+
+    output.assert_exit_code(1);
+
+    let stdout = output.stdout_unchecked();
+    let password_prompt = if sudo_test::is_original_sudo() {
+        "password for ferris"
+    } else {
+        "Password:"
+    };
+    assert_not_contains!(stdout, password_prompt);
+    if !sudo_test::is_original_sudo() {
+        assert_contains!(stdout, "interactive authentication is required");
+    }
+}
+
 #[test]
 fn noninteractive_auth() {
     let env = Env("Defaults noninteractive_auth\nALL ALL=(ALL:ALL) ALL")
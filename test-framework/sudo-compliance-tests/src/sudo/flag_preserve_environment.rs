@@ -85,3 +85,176 @@ fn preserve_overrides_env_var() {
 
     assert_eq!(Some(value), sudo_env.get(name).copied());
 }
+
+#[test]
+fn listed_var_requires_setenv() {
+    let env = Env("ALL ALL=(ALL:ALL) NOPASSWD: /usr/bin/env").build();
+
+    let output = Command::new("env")
+        .arg("LISTED=42")
+        .args(["sudo", "--preserve-env=LISTED", "/usr/bin/env"])
+        .output(&env);
+
+    output.assert_exit_code(1);
+    assert_contains!(
+        output.stderr(),
+        "you are not allowed to set the following environment variables: LISTED"
+    );
+}
+
+#[test]
+fn listed_var_in_env_delete_is_rejected_without_setenv() {
+    let env = Env("ALL ALL=(ALL:ALL) NOPASSWD: /usr/bin/env").build();
+
+    let output = Command::new("env")
+        .arg("LD_PRELOAD=/tmp/evil.so")
+        .args(["sudo", "--preserve-env=LD_PRELOAD", "/usr/bin/env"])
+        .output(&env);
+
+    output.assert_exit_code(1);
+    assert_contains!(
+        output.stderr(),
+        "you are not allowed to set the following environment variables: LD_PRELOAD"
+    );
+}
+
+#[test]
+fn listed_var_in_env_keep_is_preserved_without_setenv() {
+    let env = Env([
+        "ALL ALL=(ALL:ALL) NOPASSWD: /usr/bin/env",
+        "Defaults env_keep += LISTED",
+    ])
+    .build();
+
+    let stdout = Command::new("env")
+        .args(["LISTED=42", "UNLISTED=37"])
+        .args(["sudo", "--preserve-env=LISTED", "/usr/bin/env"])
+        .output(&env)
+        .stdout();
+    let sudo_env = helpers::parse_env_output(&stdout);
+
+    assert_eq!(Some("42"), sudo_env.get("LISTED").copied());
+    assert_eq!(None, sudo_env.get("UNLISTED"));
+}
+
+#[test]
+fn listed_var_is_added_to_env_keep() {
+    let env = Env([SUDOERS_ALL_ALL_NOPASSWD, "Defaults env_keep = KEPT"]).build();
+
+    let stdout = Command::new("env")
+        .args(["KEPT=1", "LISTED=2"])
+        .args(["sudo", "--preserve-env=LISTED", "env"])
+        .output(&env)
+        .stdout();
+    let sudo_env = helpers::parse_env_output(&stdout);
+
+    assert_eq!(Some("1"), sudo_env.get("KEPT").copied());
+    assert_eq!(Some("2"), sudo_env.get("LISTED").copied());
+}
+
+#[test]
+fn flag_preserves_entire_environment_with_setenv() {
+    let env = Env("ALL ALL=(ALL:ALL) NOPASSWD:SETENV: /usr/bin/env").build();
+
+    let stdout = Command::new("env")
+        .args(["FIRST=1", "SECOND=2", "LD_PRELOAD=/tmp/evil.so"])
+        .args(["sudo", "-E", "/usr/bin/env"])
+        .output(&env)
+        .stdout();
+    let sudo_env = helpers::parse_env_output(&stdout);
+
+    assert_eq!(Some("1"), sudo_env.get("FIRST").copied());
+    assert_eq!(Some("2"), sudo_env.get("SECOND").copied());
+    assert_eq!(None, sudo_env.get("LD_PRELOAD"));
+}
+
+#[test]
+fn flag_is_implied_by_all() {
+    let env = Env(SUDOERS_ALL_ALL_NOPASSWD).build();
+
+    let stdout = Command::new("env")
+        .arg("FIRST=1")
+        .args(["sudo", "--preserve-env", "env"])
+        .output(&env)
+        .stdout();
+    let sudo_env = helpers::parse_env_output(&stdout);
+
+    assert_eq!(Some("1"), sudo_env.get("FIRST").copied());
+}
+
+#[test]
+fn flag_requires_setenv() {
+    let env = Env("ALL ALL=(ALL:ALL) NOPASSWD: /usr/bin/env").build();
+
+    let output = Command::new("env")
+        .arg("FIRST=1")
+        .args(["sudo", "-E", "/usr/bin/env"])
+        .output(&env);
+
+    output.assert_exit_code(1);
+    assert_contains!(
+        output.stderr(),
+        "sorry, you are not allowed to preserve the environment"
+    );
+}
+
+#[test]
+fn flag_keeps_home_of_invoking_user() {
+    let env = Env(SUDOERS_ALL_ALL_NOPASSWD).build();
+
+    let stdout = Command::new("env")
+        .arg("HOME=/tmp/invoking-home")
+        .args(["sudo", "-E", "env"])
+        .output(&env)
+        .stdout();
+    let sudo_env = helpers::parse_env_output(&stdout);
+
+    assert_eq!(Some("/tmp/invoking-home"), sudo_env.get("HOME").copied());
+}
+
+#[test]
+fn set_home_flag_overrides_preserved_home() {
+    let env = Env(SUDOERS_ALL_ALL_NOPASSWD).build();
+
+    let stdout = Command::new("env")
+        .arg("HOME=/tmp/invoking-home")
+        .args(["sudo", "-E", "-H", "env"])
+        .output(&env)
+        .stdout();
+    let sudo_env = helpers::parse_env_output(&stdout);
+
+    assert_eq!(Some("/root"), sudo_env.get("HOME").copied());
+}
+
+#[test]
+fn always_set_home_overrides_preserved_home() {
+    let env = Env([SUDOERS_ALL_ALL_NOPASSWD, "Defaults always_set_home"]).build();
+
+    let stdout = Command::new("env")
+        .arg("HOME=/tmp/invoking-home")
+        .args(["sudo", "-E", "env"])
+        .output(&env)
+        .stdout();
+    let sudo_env = helpers::parse_env_output(&stdout);
+
+    assert_eq!(Some("/root"), sudo_env.get("HOME").copied());
+}
+
+#[test]
+fn always_set_home_overrides_home_in_env_keep() {
+    let env = Env([
+        SUDOERS_ALL_ALL_NOPASSWD,
+        "Defaults always_set_home",
+        "Defaults env_keep += HOME",
+    ])
+    .build();
+
+    let stdout = Command::new("env")
+        .arg("HOME=/tmp/invoking-home")
+        .args(["sudo", "env"])
+        .output(&env)
+        .stdout();
+    let sudo_env = helpers::parse_env_output(&stdout);
+
+    assert_eq!(Some("/root"), sudo_env.get("HOME").copied());
+}
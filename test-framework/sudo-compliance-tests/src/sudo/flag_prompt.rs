@@ -111,3 +111,23 @@ fn percent_escape() {
 
     test_prompt(&env, "%%u", "%u")
 }
+
+#[test]
+fn passprompt_override_replaces_pam_prompt() {
+    let env = Env([
+        "Defaults passprompt_override",
+        &format!("{USERNAME}    ALL=(ALL:ALL) ALL"),
+    ])
+    .user(User(USERNAME).password(PASSWORD))
+    .build();
+
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(format!("echo {PASSWORD} | sudo -S -p 'my prompt: ' true"))
+        .as_user(USERNAME)
+        .output(&env);
+
+    output.assert_success();
+
+    assert_eq!(output.stderr(), "my prompt: ");
+}
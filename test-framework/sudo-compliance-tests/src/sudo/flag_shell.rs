@@ -265,6 +265,60 @@ fn shell_with_open_permissions_is_accepted() {
     output.assert_success();
 }
 
+#[test]
+fn shell_must_be_allowed_by_the_policy() {
+    let shell_path = "/root/my-shell";
+    let env = Env("ALL ALL=(ALL:ALL) NOPASSWD: /usr/bin/true")
+        .file(shell_path, TextFile("#!/bin/sh").chmod("100"))
+        .build();
+
+    let output = Command::new("env")
+        .arg(format!("SHELL={shell_path}"))
+        .args(["sudo", "-s"])
+        .output(&env);
+
+    output.assert_exit_code(1);
+
+    let diagnostic = if sudo_test::is_original_sudo() {
+        format!("user root is not allowed to execute '{shell_path}' as root")
+    } else {
+        "I'm sorry root. I'm afraid I can't do that".to_owned()
+    };
+    assert_contains!(output.stderr(), diagnostic);
+}
+
+#[test]
+fn policy_is_applied_to_the_shell_invocation() {
+    let shell_path = "/root/my-shell";
+    let my_shell = "#!/bin/sh
+echo \"$@\"";
+    let env = Env(format!("ALL ALL=(ALL:ALL) NOPASSWD: {shell_path} -c true"))
+        .file(shell_path, TextFile(my_shell).chmod("100"))
+        .build();
+
+    let output = Command::new("env")
+        .arg(format!("SHELL={shell_path}"))
+        .args(["sudo", "-s", "true"])
+        .output(&env)
+        .stdout();
+
+    assert_eq!("-c true", output);
+
+    let output = Command::new("env")
+        .arg(format!("SHELL={shell_path}"))
+        .args(["sudo", "-s", "false"])
+        .output(&env);
+
+    output.assert_exit_code(1);
+
+    let diagnostic = if sudo_test::is_original_sudo() {
+        "is not allowed to execute"
+    } else {
+        "I'm sorry root. I'm afraid I can't do that"
+    };
+    assert_contains!(output.stderr(), diagnostic);
+}
+
 type UserToShell<'a> = HashMap<&'a str, &'a str>;
 
 fn parse_getent_passwd_output(passwd: &str) -> UserToShell<'_> {
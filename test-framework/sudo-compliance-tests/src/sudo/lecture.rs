@@ -6,7 +6,6 @@ use crate::{
 use sudo_test::{Command, Env, User};
 
 #[test]
-#[ignore = "gh399"]
 fn default_lecture_shown_once() {
     let env = Env([SUDOERS_ROOT_ALL, SUDOERS_ONCE_LECTURE, SUDOERS_USER_ALL_ALL])
         .user(User(USERNAME).password(PASSWORD))
@@ -32,7 +31,6 @@ fn default_lecture_shown_once() {
 }
 
 #[test]
-#[ignore = "gh399"]
 fn lecture_in_stderr() {
     let env = Env([SUDOERS_ROOT_ALL, SUDOERS_ONCE_LECTURE, SUDOERS_USER_ALL_ALL])
         .user(User(USERNAME).password(PASSWORD))
@@ -51,12 +49,60 @@ fn lecture_in_stderr() {
 }
 
 #[test]
-#[ignore = "gh399"]
+fn lecture_on_tty_not_in_redirected_stderr() {
+    let env = Env([SUDOERS_ROOT_ALL, SUDOERS_ONCE_LECTURE, SUDOERS_USER_ALL_ALL])
+        .user(User(USERNAME).password(PASSWORD))
+        .build();
+
+    let output = Command::new("sshpass")
+        .args(["-p", PASSWORD, "sh", "-c", "sudo true 2>/tmp/stderr"])
+        .as_user(USERNAME)
+        .output(&env);
+    output.assert_success();
+
+    assert_contains!(output.stdout(), OG_SUDO_STANDARD_LECTURE);
+
+    let stderr = Command::new("cat").arg("/tmp/stderr").output(&env).stdout();
+    assert_not_contains!(stderr, OG_SUDO_STANDARD_LECTURE);
+}
+
+#[test]
+fn stdin_password_prompts_on_tty_when_there_is_one() {
+    if sudo_test::is_original_sudo() {
+        // original sudo writes the prompt and lecture to stderr with -S
+        return;
+    }
+
+    let env = Env([SUDOERS_ROOT_ALL, SUDOERS_ONCE_LECTURE, SUDOERS_USER_ALL_ALL])
+        .user(User(USERNAME).password(PASSWORD))
+        .build();
+
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(format!(
+            "echo {PASSWORD} | sudo -S -p 'pw-prompt> ' true 2>/tmp/stderr"
+        ))
+        .as_user(USERNAME)
+        .tty(true)
+        .output(&env);
+    output.assert_success();
+
+    let terminal = output.stdout();
+    assert_contains!(terminal, OG_SUDO_STANDARD_LECTURE);
+    assert_contains!(terminal, "pw-prompt>");
+
+    let stderr = Command::new("cat").arg("/tmp/stderr").output(&env).stdout();
+    assert_not_contains!(stderr, OG_SUDO_STANDARD_LECTURE);
+    assert_not_contains!(stderr, "pw-prompt>");
+}
+
+#[test]
 fn lecture_always_shown() {
     let env = Env([
-        SUDOERS_ROOT_ALL,
+        SUDOERS_USER_ALL_ALL,
         SUDOERS_ONCE_LECTURE,
         SUDOERS_ALWAYS_LECTURE,
+        "Defaults timestamp_timeout=0",
     ])
     .user(User(USERNAME).password(PASSWORD))
     .build();
@@ -66,7 +112,7 @@ fn lecture_always_shown() {
         .stdin(PASSWORD)
         .args(["-S", "true"])
         .output(&env);
-    assert!(!output.status().success());
+    output.assert_success();
 
     assert_contains!(output.stderr(), OG_SUDO_STANDARD_LECTURE);
 
@@ -75,11 +121,33 @@ fn lecture_always_shown() {
         .stdin(PASSWORD)
         .args(["-S", "ls"])
         .output(&env);
-    assert!(!output.status().success());
+    second_sudo.assert_success();
 
     assert_contains!(second_sudo.stderr(), OG_SUDO_STANDARD_LECTURE);
 }
 
+#[test]
+fn lecture_always_shown_on_every_prompt() {
+    let env = Env([
+        SUDOERS_USER_ALL_ALL,
+        SUDOERS_ALWAYS_LECTURE,
+        "Defaults timestamp_timeout=0",
+    ])
+    .user(User(USERNAME).password(PASSWORD))
+    .build();
+
+    for _ in 0..2 {
+        let output = Command::new("sudo")
+            .as_user(USERNAME)
+            .stdin(PASSWORD)
+            .args(["-S", "true"])
+            .output(&env);
+
+        output.assert_success();
+        assert_contains!(output.stderr(), OG_SUDO_STANDARD_LECTURE);
+    }
+}
+
 #[test]
 fn lecture_never_shown() {
     let env = Env([SUDOERS_ROOT_ALL, SUDOERS_USER_ALL_ALL, SUDOERS_NO_LECTURE])
@@ -142,3 +210,37 @@ fn nopasswd_lecture_not_shown() {
     output.assert_success();
     assert_not_contains!(output.stderr(), OG_SUDO_STANDARD_LECTURE);
 }
+
+#[test]
+fn lecture_status_dir_records_that_the_lecture_was_shown() {
+    let env = Env([
+        SUDOERS_ROOT_ALL,
+        SUDOERS_ONCE_LECTURE,
+        "Defaults lecture_status_dir=/var/lib/sudo-lectured",
+        SUDOERS_USER_ALL_ALL,
+    ])
+    .user(User(USERNAME).password(PASSWORD))
+    .build();
+
+    let output = Command::new("sudo")
+        .args(["-S", "true"])
+        .as_user(USERNAME)
+        .stdin(PASSWORD)
+        .output(&env);
+    output.assert_success();
+    assert_contains!(output.stderr(), OG_SUDO_STANDARD_LECTURE);
+
+    let output = Command::new("ls")
+        .arg("/var/lib/sudo-lectured")
+        .output(&env);
+    output.assert_success();
+    assert!(!output.stdout().is_empty());
+
+    let second_sudo = Command::new("sudo")
+        .args(["-k", "-S", "true"])
+        .as_user(USERNAME)
+        .stdin(PASSWORD)
+        .output(&env);
+    second_sudo.assert_success();
+    assert_not_contains!(second_sudo.stderr(), OG_SUDO_STANDARD_LECTURE);
+}
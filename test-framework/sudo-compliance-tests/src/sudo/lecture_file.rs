@@ -1,13 +1,12 @@
 use crate::{
     OG_SUDO_STANDARD_LECTURE, PASSWORD, SUDOERS_NEW_LECTURE, SUDOERS_NEW_LECTURE_USER,
-    SUDOERS_ONCE_LECTURE, SUDOERS_ROOT_ALL, USERNAME,
+    SUDOERS_ONCE_LECTURE, SUDOERS_USER_ALL_ALL, USERNAME,
 };
 use sudo_test::{Command, Env, User};
 
-#[ignore = "gh399"]
 #[test]
 fn default_lecture_message() {
-    let env = Env([SUDOERS_ROOT_ALL, SUDOERS_ONCE_LECTURE])
+    let env = Env([SUDOERS_USER_ALL_ALL, SUDOERS_ONCE_LECTURE])
         .user(User(USERNAME).password(PASSWORD))
         .build();
 
@@ -17,33 +16,36 @@ fn default_lecture_message() {
         .stdin(PASSWORD)
         .output(&env);
 
+    output.assert_success();
     assert_contains!(output.stderr(), OG_SUDO_STANDARD_LECTURE);
 }
 
-#[ignore = "gh400"]
 #[test]
 fn new_lecture_message() {
     let new_lecture = "I <3 sudo";
-    let env = Env([SUDOERS_ROOT_ALL, SUDOERS_ONCE_LECTURE, SUDOERS_NEW_LECTURE])
-        .file("/etc/sudo_lecture", new_lecture)
-        .user(User(USERNAME).password(PASSWORD))
-        .build();
+    let env = Env([
+        SUDOERS_USER_ALL_ALL,
+        SUDOERS_ONCE_LECTURE,
+        SUDOERS_NEW_LECTURE,
+    ])
+    .file("/etc/sudo_lecture", new_lecture)
+    .user(User(USERNAME).password(PASSWORD))
+    .build();
 
     let output = Command::new("sudo")
         .as_user(USERNAME)
         .stdin(PASSWORD)
         .args(["-S", "true"])
         .output(&env);
-    assert!(!output.status().success());
+    output.assert_success();
     assert_contains!(output.stderr(), "I <3 sudo");
 }
 
 #[test]
-#[ignore = "gh400"]
 fn new_lecture_for_specific_user() {
     let new_lecture = "I <3 sudo";
     let env = Env([
-        SUDOERS_ROOT_ALL,
+        SUDOERS_USER_ALL_ALL,
         SUDOERS_ONCE_LECTURE,
         SUDOERS_NEW_LECTURE_USER,
     ])
@@ -56,16 +58,15 @@ fn new_lecture_for_specific_user() {
         .stdin(PASSWORD)
         .args(["-S", "true"])
         .output(&env);
-    assert!(!output.status().success());
+    output.assert_success();
     assert_contains!(output.stderr(), "I <3 sudo");
 }
 
-#[ignore = "gh400"]
 #[test]
 fn default_lecture_for_unspecified_user() {
     let new_lecture = "I <3 sudo";
     let env = Env([
-        SUDOERS_ROOT_ALL,
+        "other_user ALL=(ALL:ALL) ALL",
         SUDOERS_ONCE_LECTURE,
         SUDOERS_NEW_LECTURE_USER,
     ])
@@ -79,6 +80,83 @@ fn default_lecture_for_unspecified_user() {
         .stdin("other_password")
         .args(["-S", "true"])
         .output(&env);
-    assert!(!output.status().success());
+    output.assert_success();
+    assert_contains!(output.stderr(), OG_SUDO_STANDARD_LECTURE);
+    assert_not_contains!(output.stderr(), new_lecture);
+}
+
+#[test]
+fn new_lecture_before_password_prompt() {
+    let new_lecture = "I <3 sudo";
+    let env = Env([
+        SUDOERS_USER_ALL_ALL,
+        SUDOERS_ONCE_LECTURE,
+        SUDOERS_NEW_LECTURE,
+    ])
+    .file("/etc/sudo_lecture", new_lecture)
+    .user(User(USERNAME).password(PASSWORD))
+    .build();
+
+    let output = Command::new("sudo")
+        .as_user(USERNAME)
+        .stdin(PASSWORD)
+        .args(["-S", "true"])
+        .output(&env);
+
+    output.assert_success();
+    let stderr = output.stderr();
+    assert_contains!(stderr, new_lecture);
+    assert_not_contains!(stderr, OG_SUDO_STANDARD_LECTURE);
+    assert!(stderr.find(new_lecture) < stderr.find("assword"));
+}
+
+#[test]
+fn missing_lecture_file_shows_default_lecture() {
+    let env = Env([
+        SUDOERS_USER_ALL_ALL,
+        SUDOERS_ONCE_LECTURE,
+        SUDOERS_NEW_LECTURE,
+    ])
+    .user(User(USERNAME).password(PASSWORD))
+    .build();
+
+    let output = Command::new("sudo")
+        .as_user(USERNAME)
+        .stdin(PASSWORD)
+        .args(["-S", "true"])
+        .output(&env);
+
+    output.assert_success();
     assert_contains!(output.stderr(), OG_SUDO_STANDARD_LECTURE);
 }
+
+#[test]
+fn non_utf8_lecture_is_shown_verbatim() {
+    let env = Env([
+        SUDOERS_USER_ALL_ALL,
+        SUDOERS_ONCE_LECTURE,
+        SUDOERS_NEW_LECTURE,
+    ])
+    .user(User(USERNAME).password(PASSWORD))
+    .build();
+
+    // "café" in ISO 8859-1
+    Command::new("sh")
+        .args(["-c", r"printf 'caf\351\n' > /etc/sudo_lecture"])
+        .output(&env)
+        .assert_success();
+
+    Command::new("sh")
+        .arg("-c")
+        .arg(format!("echo {PASSWORD} | sudo -S true 2> /tmp/stderr"))
+        .as_user(USERNAME)
+        .output(&env)
+        .assert_success();
+
+    let stderr = Command::new("sh")
+        .args(["-c", "od -An -tx1 /tmp/stderr | tr -d ' \\n'"])
+        .output(&env)
+        .stdout();
+
+    assert_contains!(stderr, "636166e90a");
+}
@@ -142,6 +142,27 @@ fn nopasswd_in_sudoers_has_precedence_over_pam() {
         .assert_success();
 }
 
+// the login uid is inherited across fork/exec and can not be changed by an unprivileged
+// process, so the command must see the login uid of the invoking user
+#[test]
+#[cfg(target_os = "linux")]
+fn loginuid_is_preserved() {
+    let env = Env("ALL ALL=(ALL:ALL) NOPASSWD: ALL")
+        .user(USERNAME)
+        .build();
+
+    let stdout = Command::new("sh")
+        .arg("-c")
+        .arg("cat /proc/self/loginuid; echo; sudo cat /proc/self/loginuid")
+        .as_user(USERNAME)
+        .output(&env)
+        .stdout();
+
+    let loginuids = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(2, loginuids.len());
+    assert_eq!(loginuids[0], loginuids[1]);
+}
+
 #[test]
 fn sudo_uses_correct_service_file() {
     let env = Env("ALL ALL=(ALL:ALL) ALL")
@@ -122,6 +122,31 @@ fn preserves_pam_env() {
     assert_eq!(Some(override_value), env.get(override_name).copied());
 }
 
+#[test]
+fn pam_env_survives_env_reset() {
+    let env = Env(SUDOERS_ALL_ALL_NOPASSWD)
+        .file(PAM_D_SUDO_PATH, [STOCK_PAM_D_SUDO, PAM_D_SUDO_READENV])
+        .file(
+            SECURITY_PAM_ENV_PATH,
+            [
+                STOCK_SECURITY_PAM_ENV,
+                "KRB5CCNAME DEFAULT=FILE:/tmp/krb5cc_pam",
+            ],
+        )
+        .build();
+
+    // KRB5_CONFIG is not set by PAM, and not kept from the caller's environment
+    let stdout = Command::new("env")
+        .arg("KRB5_CONFIG=/tmp/krb5.conf")
+        .args(["sudo", "env"])
+        .output(&env)
+        .stdout();
+    let env = helpers::parse_env_output(&stdout);
+
+    assert_eq!(Some("FILE:/tmp/krb5cc_pam"), env.get("KRB5CCNAME").copied());
+    assert_eq!(None, env.get("KRB5_CONFIG").copied());
+}
+
 #[test]
 fn pam_env_has_precedence_over_callers_env() {
     let set_name = "SET_VAR";
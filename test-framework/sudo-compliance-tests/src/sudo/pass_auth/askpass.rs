@@ -204,7 +204,7 @@ fn askpass_not_executable() {
 }
 
 #[test]
-fn askpass_exit_code_ignored() {
+fn askpass_exit_code() {
     let env = Env("ALL ALL=(ALL:ALL) ALL")
         .file(
             "/bin/askpass",
@@ -213,6 +213,53 @@ fn askpass_exit_code_ignored() {
         .user(User(USERNAME).password(PASSWORD))
         .build();
 
+    let output = Command::new("sh")
+        .args(["-c", "SUDO_ASKPASS=/bin/askpass sudo -A true"])
+        .as_user(USERNAME)
+        .output(&env);
+
+    if sudo_test::is_original_sudo() {
+        // the exit code of the helper is ignored
+        output.assert_success();
+    } else {
+        // a failing helper cancels the authentication
+        output.assert_exit_code(1);
+        assert_contains!(output.stderr(), "Authentication required but not attempted");
+    }
+}
+
+#[test]
+fn askpass_from_sudoers() {
+    if sudo_test::is_original_sudo() {
+        // the askpass setting is a sudo-rs extension
+        return;
+    }
+
+    let env = Env("Defaults askpass=/bin/askpass\nALL ALL=(ALL:ALL) ALL")
+        .file("/bin/askpass", generate_askpass(PASSWORD))
+        .user(User(USERNAME).password(PASSWORD))
+        .build();
+
+    Command::new("sudo")
+        .args(["-A", "true"])
+        .as_user(USERNAME)
+        .output(&env)
+        .assert_success();
+}
+
+#[test]
+fn sudo_askpass_overrides_sudoers() {
+    if sudo_test::is_original_sudo() {
+        // the askpass setting is a sudo-rs extension
+        return;
+    }
+
+    let env = Env("Defaults askpass=/bin/wrong-askpass\nALL ALL=(ALL:ALL) ALL")
+        .file("/bin/askpass", generate_askpass(PASSWORD))
+        .file("/bin/wrong-askpass", generate_askpass("incorrect-password"))
+        .user(User(USERNAME).password(PASSWORD))
+        .build();
+
     Command::new("sh")
         .args(["-c", "SUDO_ASKPASS=/bin/askpass sudo -A true"])
         .as_user(USERNAME)
@@ -220,6 +267,25 @@ fn askpass_exit_code_ignored() {
         .assert_success();
 }
 
+#[test]
+fn askpass_used_without_terminal() {
+    if sudo_test::is_original_sudo() {
+        // original sudo only does this if DISPLAY is set as well
+        return;
+    }
+
+    let env = Env("ALL ALL=(ALL:ALL) ALL")
+        .file("/bin/askpass", generate_askpass(PASSWORD))
+        .user(User(USERNAME).password(PASSWORD))
+        .build();
+
+    Command::new("sh")
+        .args(["-c", "SUDO_ASKPASS=/bin/askpass sudo true"])
+        .as_user(USERNAME)
+        .output(&env)
+        .assert_success();
+}
+
 #[test]
 fn prompt_given_as_argument() {
     let env = Env("ALL ALL=(ALL:ALL) ALL")
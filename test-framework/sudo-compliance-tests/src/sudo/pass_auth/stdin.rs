@@ -18,6 +18,24 @@ fn correct_password() {
         .assert_success();
 }
 
+#[test]
+fn pwfeedback_is_not_shown_for_piped_password() {
+    let env = Env(format!(
+        "Defaults pwfeedback\n{USERNAME}    ALL=(ALL:ALL) ALL"
+    ))
+    .user(User(USERNAME).password(PASSWORD))
+    .build();
+
+    let output = Command::new("sudo")
+        .args(["-S", "true"])
+        .as_user(USERNAME)
+        .stdin(PASSWORD)
+        .output(&env);
+    output.assert_success();
+
+    assert_not_contains!(output.stderr(), "*");
+}
+
 #[test]
 fn incorrect_password() {
     let env = Env(format!("{USERNAME}    ALL=(ALL:ALL) ALL"))
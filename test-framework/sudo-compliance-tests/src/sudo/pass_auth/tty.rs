@@ -37,6 +37,23 @@ fn incorrect_password() {
     }
 }
 
+#[test]
+fn prompt_is_written_to_tty_not_redirected_stderr() {
+    let env = Env(format!("{USERNAME}    ALL=(ALL:ALL) ALL"))
+        .user(User(USERNAME).password(PASSWORD))
+        .build();
+
+    // `sshpass` only answers prompts that appear on the terminal
+    Command::new("sshpass")
+        .args(["-p", PASSWORD, "sh", "-c", "sudo true 2>/tmp/stderr"])
+        .as_user(USERNAME)
+        .output(&env)
+        .assert_success();
+
+    let stderr = Command::new("cat").arg("/tmp/stderr").output(&env).stdout();
+    assert_not_contains!(stderr, "assword");
+}
+
 #[test]
 fn no_tty() {
     let env = Env(format!("{USERNAME}    ALL=(ALL:ALL) ALL"))
@@ -101,3 +118,37 @@ fn input_longer_than_password_should_not_be_accepted_as_correct_password() {
         assert_contains!(stderr, diagnostic);
     }
 }
+
+#[test]
+fn pwfeedback_echoes_asterisks() {
+    let env = Env(format!(
+        "Defaults pwfeedback\n{USERNAME}    ALL=(ALL:ALL) ALL"
+    ))
+    .user(User(USERNAME).password(PASSWORD))
+    .build();
+
+    let output = Command::new("sshpass")
+        .args(["-p", PASSWORD, "sudo", "true"])
+        .as_user(USERNAME)
+        .output(&env);
+    output.assert_success();
+
+    assert_contains!(output.stdout(), "*".repeat(PASSWORD.len()));
+}
+
+#[test]
+fn no_pwfeedback_echoes_nothing() {
+    let env = Env(format!(
+        "Defaults !pwfeedback\n{USERNAME}    ALL=(ALL:ALL) ALL"
+    ))
+    .user(User(USERNAME).password(PASSWORD))
+    .build();
+
+    let output = Command::new("sshpass")
+        .args(["-p", PASSWORD, "sudo", "true"])
+        .as_user(USERNAME)
+        .output(&env);
+    output.assert_success();
+
+    assert_not_contains!(output.stdout(), "*");
+}
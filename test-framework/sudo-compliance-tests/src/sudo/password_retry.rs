@@ -98,6 +98,84 @@ Defaults passwd_tries=2"
     assert_eq!(2, num_password_prompts);
 }
 
+#[test]
+fn passwd_tries_zero_does_not_prompt() {
+    let env = Env(format!(
+        "{USERNAME} ALL=(ALL:ALL) ALL
+Defaults passwd_tries=0"
+    ))
+    .user(User(USERNAME).password(PASSWORD))
+    .build();
+
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(format!("echo {PASSWORD} | sudo -S true"))
+        .as_user(USERNAME)
+        .output(&env);
+
+    output.assert_exit_code(1);
+
+    let stderr = output.stderr();
+    assert_not_contains!(stderr, "password for ferris:");
+    assert_not_contains!(stderr, "Password:");
+    if !sudo_test::is_original_sudo() {
+        assert_contains!(stderr, "0 incorrect authentication attempts");
+    }
+}
+
+#[test]
+fn passwd_tries_one_allows_a_single_attempt() {
+    let env = Env(format!(
+        "{USERNAME} ALL=(ALL:ALL) ALL
+Defaults passwd_tries=1"
+    ))
+    .user(User(USERNAME).password(PASSWORD))
+    .build();
+
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(format!(
+            "(echo wrong-password; echo {PASSWORD}) | sudo -S true"
+        ))
+        .as_user(USERNAME)
+        .output(&env);
+
+    output.assert_exit_code(1);
+
+    let diagnostic = if sudo_test::is_original_sudo() {
+        "1 incorrect password attempt"
+    } else {
+        "1 incorrect authentication attempt"
+    };
+    assert_contains!(output.stderr(), diagnostic);
+
+    Command::new("sh")
+        .arg("-c")
+        .arg(format!("echo {PASSWORD} | sudo -S true"))
+        .as_user(USERNAME)
+        .output(&env)
+        .assert_success();
+}
+
+#[test]
+fn passwd_tries_large_value() {
+    let env = Env(format!(
+        "{USERNAME} ALL=(ALL:ALL) ALL
+Defaults passwd_tries=1000"
+    ))
+    .user(User(USERNAME).password(PASSWORD))
+    .build();
+
+    Command::new("sh")
+        .arg("-c")
+        .arg(format!(
+            "(for i in $(seq 1 5); do echo wrong-password; done; echo {PASSWORD}) | sudo -S true"
+        ))
+        .as_user(USERNAME)
+        .output(&env)
+        .assert_success();
+}
+
 // this is a PAM security feature
 #[test]
 #[cfg_attr(
@@ -231,3 +309,93 @@ sudo: a password is required"
         );
     }
 }
+
+#[test]
+fn passwd_tries_per_command() {
+    let env = Env(format!(
+        "{USERNAME} ALL=(ALL:ALL) ALL
+Defaults!/usr/bin/sensitive passwd_tries=1
+Defaults!/usr/bin/unguarded passwd_tries=0"
+    ))
+    .user(User(USERNAME).password(PASSWORD))
+    .file("/usr/bin/sensitive", TextFile("#!/bin/sh").chmod("755"))
+    .file("/usr/bin/unguarded", TextFile("#!/bin/sh").chmod("755"))
+    .build();
+
+    let attempt = |command: &str| {
+        Command::new("sh")
+            .arg("-c")
+            .arg(format!(
+                "(echo wrong-password; echo {PASSWORD}) | sudo -S {command}"
+            ))
+            .as_user(USERNAME)
+            .output(&env)
+    };
+
+    // a single wrong password is enough to be refused
+    let output = attempt("/usr/bin/sensitive");
+    output.assert_exit_code(1);
+    let diagnostic = if sudo_test::is_original_sudo() {
+        "1 incorrect password attempt"
+    } else {
+        "1 incorrect authentication attempt"
+    };
+    assert_contains!(output.stderr(), diagnostic);
+
+    // no password is asked for at all
+    let output = attempt("/usr/bin/unguarded");
+    output.assert_exit_code(1);
+    let stderr = output.stderr();
+    assert_not_contains!(stderr, "password for ferris:");
+    assert_not_contains!(stderr, "Password:");
+
+    // other commands still allow the default number of attempts
+    attempt("/usr/bin/true").assert_success();
+}
+
+/// The built-in insults of sudo-rs are string literals in this file
+const INSULTS_SOURCE: &str = include_str!("../../../../src/sudo/insults.rs");
+
+#[test]
+fn defaults_insults() {
+    if sudo_test::is_original_sudo() {
+        // whether original sudo has insults at all depends on how it was built
+        return;
+    }
+
+    let env = Env(format!(
+        "{USERNAME} ALL=(ALL:ALL) ALL
+Defaults insults"
+    ))
+    .user(User(USERNAME).password(PASSWORD))
+    .build();
+
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(format!(
+            "(echo wrong-password; echo {PASSWORD}) | sudo -S true"
+        ))
+        .as_user(USERNAME)
+        .output(&env);
+
+    output.assert_success();
+
+    let stderr = output.stderr();
+    assert_not_contains!(stderr, "try again");
+
+    // the insult takes the place of the usual message, and is one of the built-in insults
+    let insults = stderr
+        .replace("[sudo: authenticate] Password:", "")
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    let [insult] = &insults[..] else {
+        panic!("expected exactly one insult: {stderr}")
+    };
+    assert!(
+        INSULTS_SOURCE.contains(&format!("\"{insult}\"")),
+        "not a built-in insult: {insult}"
+    );
+}
@@ -213,3 +213,65 @@ fn arg0_script_is_resolved_from_commandline() {
     let stdout = output.stdout();
     assert_eq!(stdout, "/usr/bin/foo");
 }
+
+#[test]
+fn require_absolute_command_rejects_command_names() {
+    if sudo_test::is_original_sudo() {
+        // `require_absolute_command` is a sudo-rs extension
+        return;
+    }
+
+    let env = Env([
+        SUDOERS_ALL_ALL_NOPASSWD,
+        "Defaults require_absolute_command",
+    ])
+    .build();
+
+    let output = Command::new("sudo").arg("true").output(&env);
+
+    output.assert_exit_code(1);
+    assert_contains!(
+        output.stderr(),
+        "'true': the command must be given as an absolute path"
+    );
+
+    Command::new("sudo")
+        .arg("/usr/bin/true")
+        .output(&env)
+        .assert_success();
+}
+
+#[test]
+fn without_require_absolute_command_command_names_are_resolved() {
+    let env = Env(SUDOERS_ALL_ALL_NOPASSWD).build();
+
+    Command::new("sudo")
+        .arg("true")
+        .output(&env)
+        .assert_success();
+}
+
+#[test]
+fn nonexistent_command_is_not_found() {
+    let env = Env(SUDOERS_ALL_ALL_NOPASSWD).build();
+
+    let output = Command::new("sudo").arg("/tmp/does-not-exist").output(&env);
+
+    output.assert_exit_code(1);
+    assert_contains!(output.stderr(), "command not found");
+}
+
+#[test]
+fn non_executable_file_is_not_found() {
+    let path = "/tmp/not-executable";
+    let env = Env(SUDOERS_ALL_ALL_NOPASSWD)
+        .file(path, TextFile("#!/bin/sh").chmod("644"))
+        .build();
+
+    let output = Command::new("sudo").arg(path).output(&env);
+
+    output.assert_exit_code(1);
+    let stderr = output.stderr();
+    assert_contains!(stderr, "command not found");
+    assert_not_contains!(stderr, "Permission denied");
+}
@@ -12,12 +12,16 @@ mod host_alias;
 mod host_list;
 mod include;
 mod includedir;
+mod logfile;
 mod noexec;
 mod passwd_timeout;
+mod preserve_fds;
 mod run_as;
 mod runas_alias;
+mod runas_default;
 mod runcwd;
 mod secure_path;
+mod set_sudo_rule;
 mod specific_defaults;
 mod timestamp_timeout;
 mod timestamp_type;
@@ -279,6 +283,28 @@ fn negated_defaults_errors() {
     assert_contains!(output.stderr(), diagnostic2);
 }
 
+#[test]
+fn unknown_setting_is_reported_and_ignored() {
+    let env = Env(format!(
+        "Defaults pasword_tries=5\n{USERNAME} ALL=(ALL:ALL) NOPASSWD: ALL"
+    ))
+    .user(USERNAME)
+    .build();
+
+    let output = Command::new("sudo")
+        .arg("true")
+        .as_user(USERNAME)
+        .output(&env);
+    output.assert_success();
+
+    let diagnostic = if sudo_test::is_original_sudo() {
+        "unknown defaults entry \"pasword_tries\""
+    } else {
+        "unknown setting: 'pasword_tries'"
+    };
+    assert_contains!(output.stderr(), diagnostic);
+}
+
 #[test]
 fn regex_not_interpreted_literally() {
     let env = Env("ALL ALL=(ALL:ALL) NOPASSWD: /bin/echo ^huk$").build();
@@ -314,6 +314,35 @@ fn arguments_can_be_forbidden() {
     }
 }
 
+#[test]
+fn quoted_arguments_match_a_single_argument() {
+    if sudo_test::is_original_sudo() {
+        // original sudo compares the arguments joined by spaces
+        return;
+    }
+
+    for supplied_arg in ["\"hello world\"", "hello\\ world"] {
+        let env = Env(format!("ALL ALL=(ALL:ALL) {BIN_TRUE} {supplied_arg}")).build();
+
+        Command::new("sudo")
+            .arg("true")
+            .arg("hello world")
+            .output(&env)
+            .assert_success();
+
+        let output = Command::new("sudo")
+            .arg("true")
+            .args(["hello", "world"])
+            .output(&env);
+
+        output.assert_exit_code(1);
+        assert_contains!(
+            output.stderr(),
+            "I'm sorry root. I'm afraid I can't do that"
+        );
+    }
+}
+
 #[test]
 fn wildcards_dont_cross_directory_boundaries() {
     let env = Env("ALL ALL=(ALL:ALL) /usr/*/foo")
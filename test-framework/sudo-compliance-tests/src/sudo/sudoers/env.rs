@@ -63,46 +63,68 @@ fn var_in_both_lists_is_preserved() {
 }
 
 #[test]
-fn checks_applied_if_in_both_lists() {
-    let name = "SHOULD_BE_REMOVED";
+fn unsafe_value_in_both_lists() {
+    let name = "SUSPICIOUS_VALUE";
     let value = "4%2";
-    let env = Env([
-        SUDOERS_ALL_ALL_NOPASSWD,
-        &format!("Defaults env_keep = {name}"),
-        &format!("Defaults env_check = {name}"),
-    ])
-    .build();
 
-    let stdout = Command::new("env")
-        .arg(format!("{name}={value}"))
-        .args(["sudo", "env"])
-        .output(&env)
-        .stdout();
-    let sudo_env = helpers::parse_env_output(&stdout);
+    // original sudo applies env_check; in sudo-rs, env_keep takes precedence
+    let expected = if is_original_sudo() {
+        None
+    } else {
+        Some(value)
+    };
+
+    for sudoers in [
+        [
+            format!("Defaults env_keep = {name}"),
+            format!("Defaults env_check = {name}"),
+        ],
+        [
+            format!("Defaults env_check = {name}"),
+            format!("Defaults env_keep = {name}"),
+        ],
+    ] {
+        let env = Env([SUDOERS_ALL_ALL_NOPASSWD, &sudoers[0], &sudoers[1]]).build();
 
-    assert_eq!(None, sudo_env.get(name).copied());
+        let stdout = Command::new("env")
+            .arg(format!("{name}={value}"))
+            .args(["sudo", "env"])
+            .output(&env)
+            .stdout();
+        let sudo_env = helpers::parse_env_output(&stdout);
 
-    drop(env);
+        assert_eq!(expected, sudo_env.get(name).copied());
+    }
+}
+
+#[test]
+fn tz_in_both_lists() {
+    const TZ: &str = "TZ";
 
-    // test sudoers statements in reverse order
     let env = Env([
         SUDOERS_ALL_ALL_NOPASSWD,
-        &format!("Defaults env_check = {name}"),
-        &format!("Defaults env_keep = {name}"),
+        &format!("Defaults env_keep += {TZ}"),
     ])
     .build();
 
-    let stdout = Command::new("env")
-        .arg(format!("{name}={value}"))
-        .args(["sudo", "env"])
-        .output(&env)
-        .stdout();
-    let sudo_env = helpers::parse_env_output(&stdout);
+    for &value in BAD_TZ_VALUES {
+        let stdout = Command::new("env")
+            .arg(format!("{TZ}={value}"))
+            .args(["sudo", "env"])
+            .output(&env)
+            .stdout();
+        let sudo_env = helpers::parse_env_output(&stdout);
 
-    assert_eq!(None, sudo_env.get(name).copied());
+        let expected = if is_original_sudo() {
+            None
+        } else {
+            Some(value)
+        };
+        assert_eq!(expected, sudo_env.get(TZ).copied());
+    }
 }
 
-// adding TZ to env_keep is insufficient to avoid checks (see previous test)
+// in original sudo, adding TZ to env_keep is insufficient to avoid checks (see previous test)
 // it's necessary to remove TZ from env_check first
 // this applies to all env vars that are in the default env_check list
 #[test]
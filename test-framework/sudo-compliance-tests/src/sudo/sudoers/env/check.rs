@@ -248,6 +248,80 @@ fn vars_not_preserved_if_they_fail_checks() {
     assert_eq!(None, sudo_env.get(env_name2).copied());
 }
 
+#[test]
+fn checks_do_not_apply_to_env_keep() {
+    let checked_name = "CHECKED_VAR";
+    let kept_name = "KEPT_VAR";
+    let value = "/tmp/50%";
+    let env = Env([
+        SUDOERS_ALL_ALL_NOPASSWD,
+        &format!("Defaults env_check = {checked_name}"),
+        &format!("Defaults env_keep = {kept_name}"),
+    ])
+    .build();
+
+    let stdout = Command::new("env")
+        .arg(format!("{checked_name}={value}"))
+        .arg(format!("{kept_name}={value}"))
+        .args(["sudo", "env"])
+        .output(&env)
+        .stdout();
+    let sudo_env = helpers::parse_env_output(&stdout);
+
+    assert_eq!(None, sudo_env.get(checked_name).copied());
+    assert_eq!(Some(value), sudo_env.get(kept_name).copied());
+}
+
+#[test]
+fn locale_vars_are_in_default_list() {
+    let env = Env(SUDOERS_ALL_ALL_NOPASSWD).build();
+
+    let stdout = Command::new("env")
+        .args(["LANG=C.UTF-8", "LC_CTYPE=C.UTF-8"])
+        .args(["sudo", "env"])
+        .output(&env)
+        .stdout();
+    let sudo_env = helpers::parse_env_output(&stdout);
+
+    assert_eq!(Some("C.UTF-8"), sudo_env.get("LANG").copied());
+    assert_eq!(Some("C.UTF-8"), sudo_env.get("LC_CTYPE").copied());
+}
+
+#[test]
+fn locale_vars_that_fail_checks_are_dropped() {
+    let env = Env(SUDOERS_ALL_ALL_NOPASSWD).build();
+
+    let stdout = Command::new("env")
+        .args(["LANG=C.UTF-8", "LC_CTYPE=/tmp/evil-locale"])
+        .args(["sudo", "env"])
+        .output(&env)
+        .stdout();
+    let sudo_env = helpers::parse_env_output(&stdout);
+
+    assert_eq!(Some("C.UTF-8"), sudo_env.get("LANG").copied());
+    assert_eq!(None, sudo_env.get("LC_CTYPE").copied());
+}
+
+#[test]
+fn locale_vars_removed_from_list_are_dropped_unless_kept() {
+    let env = Env([
+        SUDOERS_ALL_ALL_NOPASSWD,
+        "Defaults env_check -= \"LANG LC_*\"",
+        "Defaults env_keep += LANG",
+    ])
+    .build();
+
+    let stdout = Command::new("env")
+        .args(["LANG=C.UTF-8", "LC_CTYPE=C.UTF-8"])
+        .args(["sudo", "env"])
+        .output(&env)
+        .stdout();
+    let sudo_env = helpers::parse_env_output(&stdout);
+
+    assert_eq!(Some("C.UTF-8"), sudo_env.get("LANG").copied());
+    assert_eq!(None, sudo_env.get("LC_CTYPE").copied());
+}
+
 const TZ: &str = "TZ";
 
 // the TZ is variable a different set of checks
@@ -1,4 +1,4 @@
-use sudo_test::{BIN_TRUE, Command, Env};
+use sudo_test::{BIN_TRUE, Command, ETC_SUDOERS, Env};
 
 macro_rules! assert_snapshot {
     ($($tt:tt)*) => {
@@ -147,6 +147,88 @@ fn unlisted_host_fails() {
     }
 }
 
+/// Allows everything on the network given by `network`, in which `{address}` is replaced by
+/// the address of the (non-loopback) network interface of the container.
+fn allow_on_network_of_container(network: &str) -> Env {
+    let env = Env("").network().build();
+
+    let address = Command::new("hostname").arg("-i").output(&env).stdout();
+    let network = network.replace("{address}", address.trim());
+    Command::new("sh")
+        .arg("-c")
+        .arg(format!(
+            "printf 'Host_Alias LOCALNET = {network}\\nALL LOCALNET=(ALL:ALL) ALL\\n' >> {ETC_SUDOERS}"
+        ))
+        .output(&env)
+        .assert_success();
+
+    env
+}
+
+#[test]
+fn host_alias_matches_network_of_local_address() {
+    let env = allow_on_network_of_container("192.0.2.0/24, {address}/24");
+
+    Command::new("sudo")
+        .arg("true")
+        .output(&env)
+        .assert_success();
+}
+
+#[test]
+fn host_alias_matches_network_given_by_netmask() {
+    let env = allow_on_network_of_container("{address}/255.255.255.0");
+
+    Command::new("sudo")
+        .arg("true")
+        .output(&env)
+        .assert_success();
+}
+
+#[test]
+fn host_alias_does_not_match_loopback_network() {
+    let env = Env([
+        "Host_Alias LOCALNET = 127.0.0.0/8",
+        "ALL LOCALNET=(ALL:ALL) ALL",
+    ])
+    .build();
+
+    let output = Command::new("sudo").arg("true").output(&env);
+
+    assert!(!output.status().success());
+}
+
+#[test]
+fn host_alias_with_foreign_network_fails() {
+    let env = Env([
+        "Host_Alias OTHERNET = 192.0.2.0/24",
+        "ALL OTHERNET=(ALL:ALL) ALL",
+    ])
+    .build();
+
+    let output = Command::new("sudo").arg("true").output(&env);
+
+    assert!(!output.status().success());
+}
+
+#[test]
+fn malformed_network_is_a_syntax_error() {
+    if sudo_test::is_original_sudo() {
+        // the diagnostic is specific to sudo-rs
+        return;
+    }
+
+    let env = Env([
+        "Host_Alias BADNET = 10.0.0.0/33",
+        "ALL ALL=(ALL:ALL) NOPASSWD: ALL",
+    ])
+    .build();
+
+    let output = Command::new("sudo").arg("true").output(&env);
+
+    assert_contains!(output.stderr(), "invalid network prefix length '33'");
+}
+
 #[test]
 fn negation_not_order_sensitive() {
     let env = Env([
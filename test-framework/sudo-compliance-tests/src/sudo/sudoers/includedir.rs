@@ -326,6 +326,31 @@ fn no_hostname_expansion() {
     assert_contains!(output.stderr(), diagnostic);
 }
 
+#[test]
+fn hostname_expansion_when_enabled() {
+    if sudo_test::is_original_sudo() {
+        // includedir_host_escape is a sudo-rs extension
+        return;
+    }
+
+    let hostname = "ship";
+    let env = Env(format!(
+        "Defaults includedir_host_escape\n@includedir {ETC_DIR}/sudoers.%h"
+    ))
+    .directory(format!("{ETC_DIR}/sudoers.{hostname}"))
+    .file(
+        format!("{ETC_DIR}/sudoers.{hostname}/a"),
+        SUDOERS_ALL_ALL_NOPASSWD,
+    )
+    .hostname(hostname)
+    .build();
+
+    Command::new("sudo")
+        .arg("true")
+        .output(&env)
+        .assert_success();
+}
+
 #[test]
 fn ignores_directory_with_bad_perms() {
     let env = Env(format!("@includedir {ETC_DIR}/sudoers2.d"))
@@ -0,0 +1,107 @@
+use sudo_test::{Command, Env};
+
+use crate::USERNAME;
+
+const LOGFILE: &str = "/var/log/sudo.log";
+
+fn read_logfile(env: &Env) -> String {
+    Command::new("cat").arg(LOGFILE).output(env).stdout()
+}
+
+#[test]
+fn command_is_logged_to_file() {
+    let env = Env(format!(
+        "Defaults logfile={LOGFILE}\n{USERNAME} ALL=(ALL:ALL) NOPASSWD: ALL"
+    ))
+    .user(USERNAME)
+    .build();
+
+    Command::new("sh")
+        .args(["-c", "cd /tmp && sudo -u root true"])
+        .as_user(USERNAME)
+        .output(&env)
+        .assert_success();
+
+    let log = read_logfile(&env);
+    assert_contains!(log, format!("{USERNAME} : "));
+    assert_contains!(log, "PWD=/tmp");
+    assert_contains!(log, "USER=root");
+    assert_contains!(log, "COMMAND=/usr/bin/true");
+
+    let mode = Command::new("stat")
+        .args(["-c", "%a %U", LOGFILE])
+        .output(&env)
+        .stdout();
+    assert_eq!(mode, "600 root");
+}
+
+#[test]
+fn nothing_is_logged_without_logfile() {
+    let env = Env(format!("{USERNAME} ALL=(ALL:ALL) NOPASSWD: ALL"))
+        .user(USERNAME)
+        .build();
+
+    Command::new("sudo")
+        .arg("true")
+        .as_user(USERNAME)
+        .output(&env)
+        .assert_success();
+
+    Command::new("test")
+        .args(["!", "-e", LOGFILE])
+        .output(&env)
+        .assert_success();
+}
+
+#[test]
+fn log_year() {
+    let env = Env(format!(
+        "Defaults logfile={LOGFILE}, log_year\n{USERNAME} ALL=(ALL:ALL) NOPASSWD: ALL"
+    ))
+    .user(USERNAME)
+    .build();
+
+    Command::new("sudo")
+        .arg("true")
+        .as_user(USERNAME)
+        .output(&env)
+        .assert_success();
+
+    let year = Command::new("date").arg("+%Y").output(&env).stdout();
+    assert_contains!(read_logfile(&env), format!(" {year} : "));
+}
+
+#[test]
+fn log_host() {
+    let env = Env(format!(
+        "Defaults logfile={LOGFILE}, log_host\n{USERNAME} ALL=(ALL:ALL) NOPASSWD: ALL"
+    ))
+    .user(USERNAME)
+    .hostname("container")
+    .build();
+
+    Command::new("sudo")
+        .arg("true")
+        .as_user(USERNAME)
+        .output(&env)
+        .assert_success();
+
+    assert_contains!(read_logfile(&env), "HOST=container");
+}
+
+#[test]
+fn unwritable_logfile_does_not_prevent_running_commands() {
+    let env = Env(format!(
+        "Defaults logfile=/nonexistent/sudo.log\n{USERNAME} ALL=(ALL:ALL) NOPASSWD: ALL"
+    ))
+    .user(USERNAME)
+    .build();
+
+    let output = Command::new("sudo")
+        .args(["echo", "hello"])
+        .as_user(USERNAME)
+        .output(&env);
+
+    output.assert_success();
+    assert_eq!(output.stdout(), "hello");
+}
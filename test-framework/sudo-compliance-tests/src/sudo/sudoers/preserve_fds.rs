@@ -0,0 +1,64 @@
+use sudo_test::{Command, Env};
+
+use crate::SUDOERS_ALL_ALL_NOPASSWD;
+
+const PASSED_FILE: &str = "/tmp/passed.txt";
+
+/// A script that opens the file descriptors 3, 4 and 5 and runs `command` through sudo
+fn with_fds(command: &str) -> String {
+    format!("exec 3<{PASSED_FILE} 4</dev/null 5</dev/null; sudo sh -c '{command}'")
+}
+
+const LIST_FDS: &str = "for fd in 3 4 5; do [ -e /proc/$$/fd/$fd ] && echo $fd; done; true";
+
+#[test]
+fn closed_by_default() {
+    let env = Env(SUDOERS_ALL_ALL_NOPASSWD)
+        .file(PASSED_FILE, "hello")
+        .build();
+
+    let output = Command::new("sh")
+        .args(["-c", &with_fds(LIST_FDS)])
+        .output(&env);
+
+    output.assert_success();
+    assert_eq!(output.stdout(), "");
+}
+
+#[test]
+fn command_can_read_a_preserved_descriptor() {
+    if sudo_test::is_original_sudo() {
+        // `preserve_fds` is a sudo-rs extension
+        return;
+    }
+
+    let env = Env(["Defaults preserve_fds = 3", SUDOERS_ALL_ALL_NOPASSWD])
+        .file(PASSED_FILE, "hello")
+        .build();
+
+    let output = Command::new("sh")
+        .args(["-c", &with_fds("cat <&3")])
+        .output(&env);
+
+    output.assert_success();
+    assert_eq!(output.stdout(), "hello");
+}
+
+#[test]
+fn other_descriptors_are_still_closed() {
+    if sudo_test::is_original_sudo() {
+        // `preserve_fds` is a sudo-rs extension
+        return;
+    }
+
+    let env = Env(["Defaults preserve_fds = \"3 5\"", SUDOERS_ALL_ALL_NOPASSWD])
+        .file(PASSED_FILE, "hello")
+        .build();
+
+    let output = Command::new("sh")
+        .args(["-c", &with_fds(LIST_FDS)])
+        .output(&env);
+
+    output.assert_success();
+    assert_eq!(output.stdout(), "3\n5");
+}
@@ -256,6 +256,36 @@ fn when_both_user_and_group_are_specified_then_as_that_group_is_allowed() {
         .assert_success();
 }
 
+#[test]
+fn when_any_user_and_specific_group_then_group_flag_alone_is_allowed() {
+    let env = Env(format!("ALL ALL=(ALL:{GROUPNAME}) NOPASSWD: ALL"))
+        .user(USERNAME)
+        .group(GROUPNAME)
+        .build();
+
+    let output = Command::new("sudo")
+        .args(["-g", GROUPNAME, "sh", "-c", "whoami; id -gn"])
+        .as_user(USERNAME)
+        .output(&env);
+
+    assert_eq!(output.stdout(), format!("{USERNAME}\n{GROUPNAME}"));
+}
+
+#[test]
+fn group_flag_alone_for_own_group_ignores_user_list() {
+    let env = Env(format!("ALL ALL=(root:{GROUPNAME}) NOPASSWD: ALL"))
+        .user(User(USERNAME).secondary_group(GROUPNAME))
+        .group(GROUPNAME)
+        .build();
+
+    let output = Command::new("sudo")
+        .args(["-g", GROUPNAME, "sh", "-c", "whoami; id -gn"])
+        .as_user(USERNAME)
+        .output(&env);
+
+    assert_eq!(output.stdout(), format!("{USERNAME}\n{GROUPNAME}"));
+}
+
 #[test]
 fn runas_specifiers_distribute() {
     let env = Env(format!(
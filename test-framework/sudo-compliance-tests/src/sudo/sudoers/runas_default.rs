@@ -0,0 +1,46 @@
+use sudo_test::{Command, Env};
+
+use crate::USERNAME;
+
+#[test]
+fn bare_sudo_runs_as_the_default_user() {
+    let env = Env(format!(
+        "Defaults runas_default={USERNAME}\nALL ALL=(root, {USERNAME}) NOPASSWD: ALL"
+    ))
+    .user(USERNAME)
+    .build();
+
+    let output = Command::new("sudo").arg("whoami").output(&env);
+
+    output.assert_success();
+    assert_eq!(output.stdout(), USERNAME);
+}
+
+#[test]
+fn explicit_target_user_still_works() {
+    let env = Env(format!(
+        "Defaults runas_default={USERNAME}\nALL ALL=(root, {USERNAME}) NOPASSWD: ALL"
+    ))
+    .user(USERNAME)
+    .build();
+
+    let output = Command::new("sudo")
+        .args(["-u", "root", "whoami"])
+        .output(&env);
+
+    output.assert_success();
+    assert_eq!(output.stdout(), "root");
+}
+
+#[test]
+fn default_user_must_be_allowed() {
+    let env = Env(format!(
+        "Defaults runas_default={USERNAME}\nALL ALL=(root) NOPASSWD: ALL"
+    ))
+    .user(USERNAME)
+    .build();
+
+    let output = Command::new("sudo").arg("true").output(&env);
+
+    output.assert_exit_code(1);
+}
@@ -1,6 +1,6 @@
 use sudo_test::{BIN_SUDO, Command, Env, TextFile};
 
-use crate::SUDOERS_ALL_ALL_NOPASSWD;
+use crate::{SUDOERS_ALL_ALL_NOPASSWD, USERNAME};
 
 macro_rules! assert_snapshot {
     ($($tt:tt)*) => {
@@ -123,3 +123,30 @@ ALL ALL=(ALL:ALL) NOPASSWD: ALL"
         assert_eq!(secure_path, &path);
     }
 }
+
+#[test]
+fn can_be_disabled_for_a_specific_user() {
+    let secure_path = "/usr/bin:/bin";
+    let env = Env(format!(
+        "Defaults secure_path={secure_path}
+Defaults:{USERNAME} !secure_path
+ALL ALL=(ALL:ALL) NOPASSWD: ALL"
+    ))
+    .user(USERNAME)
+    .build();
+
+    let script = format!("export PATH=/tmp:{secure_path}; cd /; {BIN_SUDO} /usr/bin/printenv PATH");
+
+    let root_path = Command::new("sh")
+        .args(["-c", &script])
+        .output(&env)
+        .stdout();
+    assert_eq!(secure_path, root_path);
+
+    let user_path = Command::new("sh")
+        .args(["-c", &script])
+        .as_user(USERNAME)
+        .output(&env)
+        .stdout();
+    assert_eq!(format!("/tmp:{secure_path}"), user_path);
+}
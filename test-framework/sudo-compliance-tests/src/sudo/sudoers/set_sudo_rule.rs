@@ -0,0 +1,61 @@
+use sudo_test::{Command, ETC_DIR, ETC_SUDOERS, Env};
+
+use crate::SUDOERS_ALL_ALL_NOPASSWD;
+
+#[test]
+fn absent_by_default() {
+    let env = Env(SUDOERS_ALL_ALL_NOPASSWD).build();
+
+    let output = Command::new("sudo")
+        .args(["printenv", "SUDO_RULE"])
+        .output(&env);
+
+    output.assert_exit_code(1);
+    assert_eq!(output.stdout(), "");
+}
+
+#[test]
+fn refers_to_the_matching_rule() {
+    if sudo_test::is_original_sudo() {
+        // `set_sudo_rule` is a sudo-rs extension
+        return;
+    }
+
+    // the test framework adds a line of its own at the start of the sudoers file
+    let env = Env("\
+Defaults set_sudo_rule
+ALL ALL=(ALL:ALL) NOPASSWD: /usr/bin/true
+ALL ALL=(ALL:ALL) NOPASSWD: /usr/bin/printenv")
+    .build();
+
+    let output = Command::new("sudo")
+        .args(["printenv", "SUDO_RULE"])
+        .output(&env);
+
+    output.assert_success();
+    assert_eq!(output.stdout(), format!("{ETC_SUDOERS}:4"));
+}
+
+#[test]
+fn refers_to_the_included_file() {
+    if sudo_test::is_original_sudo() {
+        // `set_sudo_rule` is a sudo-rs extension
+        return;
+    }
+
+    let env = Env(format!(
+        "Defaults set_sudo_rule\n@includedir {ETC_DIR}/sudoers.d"
+    ))
+    .file(
+        format!("{ETC_DIR}/sudoers.d/rules"),
+        "# a comment\nALL ALL=(ALL:ALL) NOPASSWD: ALL",
+    )
+    .build();
+
+    let output = Command::new("sudo")
+        .args(["printenv", "SUDO_RULE"])
+        .output(&env);
+
+    output.assert_success();
+    assert_eq!(output.stdout(), format!("{ETC_DIR}/sudoers.d/rules:2"));
+}
@@ -131,6 +131,31 @@ fn rootpw_can_be_per_runas() {
     output.assert_success();
 }
 
+#[test]
+fn authenticate_can_be_per_runas() {
+    let env = Env(format!(
+        "Defaults>root !authenticate
+        {USERNAME} ALL=(ALL:ALL) ALL"
+    ))
+    .user(User(USERNAME).password("passw0rd"))
+    .user("other")
+    .build();
+
+    // no password is needed to run a command as root
+    Command::new("sudo")
+        .args(["-n", "true"])
+        .as_user(USERNAME)
+        .output(&env)
+        .assert_success();
+
+    // but it is for any other target user
+    let output = Command::new("sudo")
+        .args(["-n", "-u", "other", "true"])
+        .as_user(USERNAME)
+        .output(&env);
+    output.assert_exit_code(1);
+}
+
 #[test]
 fn rootpw_can_be_per_general_command() {
     const PASSWORD: &str = "passw0rd";
@@ -136,3 +136,35 @@ Defaults!/usr/bin/true timestamp_type={mode2}"
         output.assert_exit_code(1);
     }
 }
+
+#[test]
+fn caching_shared_by_all_sessions() {
+    let env = Env("ALL ALL=(ALL:ALL) ALL
+Defaults timestamp_type=global")
+    .user(User(USERNAME).password(PASSWORD))
+    .build();
+
+    // neither the terminal nor the parent process matters
+    for has_tty in [true, false] {
+        for test in [
+            "sudo -S true; sh -c 'sudo -n true' && true",
+            "sh -c 'sudo -S true'; sudo -n true && true",
+            "sh -c 'sudo -S true'; sh -c 'sudo -n true' && true",
+        ] {
+            Command::new("sh")
+                .arg("-c")
+                .arg(format!("echo {PASSWORD} | {test}"))
+                .as_user(USERNAME)
+                .tty(has_tty)
+                .output(&env)
+                .assert_success();
+        }
+    }
+
+    // the credential is even re-used by a separate invocation
+    Command::new("sudo")
+        .args(["-n", "true"])
+        .as_user(USERNAME)
+        .output(&env)
+        .assert_success();
+}
@@ -60,3 +60,22 @@ fn sudo_logs_every_failed_authentication_attempt() {
     let auth_log = rsyslog.auth_log();
     assert_contains!(auth_log, "auth could not identify password");
 }
+
+#[test]
+#[cfg_attr(
+    target_os = "freebsd",
+    ignore = "Logging not really functional on FreeBSD even with og-sudo"
+)]
+fn sudo_logs_sudoers_syntax_errors() {
+    let env = Env(["ALL ALL=(ALL:ALL) NOPASSWD: ALL", "this is fine"]).build();
+    let rsyslog = Rsyslogd::start(&env);
+
+    let auth_log = rsyslog.auth_log();
+    assert_eq!("", auth_log);
+
+    let output = Command::new("sudo").arg("true").output(&env);
+    assert_contains!(output.stderr(), "/etc/sudoers:");
+
+    let auth_log = rsyslog.auth_log();
+    assert_contains!(auth_log, "/etc/sudoers:");
+}
@@ -22,6 +22,43 @@ fn credential_caching_works() {
         .assert_success();
 }
 
+#[test]
+fn zero_timeout_disables_credential_caching() {
+    let env = Env(format!(
+        "{USERNAME} ALL=(ALL:ALL) ALL\nDefaults timestamp_timeout=0"
+    ))
+    .user(User(USERNAME).password(PASSWORD))
+    .build();
+
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(format!(
+            "set -e; echo {PASSWORD} | sudo -S true; sudo -n true"
+        ))
+        .as_user(USERNAME)
+        .output(&env);
+
+    output.assert_exit_code(1);
+}
+
+#[test]
+fn negative_timeout_never_expires() {
+    let env = Env(format!(
+        "{USERNAME} ALL=(ALL:ALL) ALL\nDefaults timestamp_timeout=-1"
+    ))
+    .user(User(USERNAME).password(PASSWORD))
+    .build();
+
+    Command::new("sh")
+        .arg("-c")
+        .arg(format!(
+            "set -e; echo {PASSWORD} | sudo -S true; sudo -n true"
+        ))
+        .as_user(USERNAME)
+        .output(&env)
+        .assert_success();
+}
+
 #[test]
 fn by_default_credential_caching_is_local() {
     let env = Env(format!("{USERNAME} ALL=(ALL:ALL) ALL"))
@@ -245,3 +282,79 @@ fn double_negation_also_equals_never() {
 
     output.assert_exit_code(1);
 }
+
+#[test]
+fn timestamp_dir_holds_the_session_records() {
+    let env = Env([
+        "Defaults timestamp_dir=/var/lib/sudo-ts".to_string(),
+        format!("{USERNAME} ALL=(ALL:ALL) ALL"),
+    ])
+    .user(User(USERNAME).password(PASSWORD))
+    .build();
+
+    Command::new("sh")
+        .arg("-c")
+        .arg(format!(
+            "set -e; echo {PASSWORD} | sudo -S true; sudo -n true"
+        ))
+        .as_user(USERNAME)
+        .output(&env)
+        .assert_success();
+
+    let output = Command::new("ls").arg("/var/lib/sudo-ts").output(&env);
+    output.assert_success();
+    assert!(!output.stdout().is_empty());
+}
+
+#[test]
+fn remove_timestamp_uses_timestamp_dir() {
+    let env = Env([
+        "Defaults timestamp_dir=/var/lib/sudo-ts".to_string(),
+        format!("{USERNAME} ALL=(ALL:ALL) ALL"),
+    ])
+    .user(User(USERNAME).password(PASSWORD))
+    .build();
+
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(format!(
+            "echo {PASSWORD} | sudo -S true; sudo -K; sudo -n true"
+        ))
+        .as_user(USERNAME)
+        .output(&env);
+
+    output.assert_exit_code(1);
+}
+
+#[test]
+fn insecure_timestamp_dir_is_not_used() {
+    if sudo_test::is_original_sudo() {
+        // ogsudo refuses to use the session records instead
+        return;
+    }
+
+    let env = Env([
+        "Defaults timestamp_dir=/tmp/sudo-ts".to_string(),
+        format!("{USERNAME} ALL=(ALL:ALL) ALL"),
+    ])
+    .user(User(USERNAME).password(PASSWORD))
+    .build();
+
+    Command::new("sh")
+        .args(["-c", "mkdir -m 1777 /tmp/sudo-ts"])
+        .output(&env)
+        .assert_success();
+
+    Command::new("sh")
+        .arg("-c")
+        .arg(format!(
+            "set -e; echo {PASSWORD} | sudo -S true; sudo -n true"
+        ))
+        .as_user(USERNAME)
+        .output(&env)
+        .assert_success();
+
+    let output = Command::new("ls").arg("/tmp/sudo-ts").output(&env);
+    output.assert_success();
+    assert_eq!(output.stdout(), "");
+}
@@ -28,6 +28,29 @@ fn is_limited_to_a_single_user() {
     child.wait().assert_success();
 }
 
+#[test]
+fn is_limited_to_the_invoking_user_even_for_root() {
+    let env = Env(format!("{USERNAME} ALL=(ALL:ALL) ALL"))
+        .user(User(USERNAME).password(PASSWORD))
+        .build();
+
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(format!(
+            "echo {PASSWORD} | sudo -S true; touch /tmp/barrier1; until [ -f /tmp/barrier2 ]; do sleep 1; done; sudo -n true"
+        ))
+        .as_user(USERNAME)
+        .spawn(&env);
+
+    Command::new("sh")
+        .arg("-c")
+        .arg("until [ -f /tmp/barrier1 ]; do sleep 1; done; sudo -K && touch /tmp/barrier2")
+        .output(&env)
+        .assert_success();
+
+    child.wait().assert_success();
+}
+
 #[test]
 fn has_a_user_global_effect() {
     let env = Env(format!("{USERNAME} ALL=(ALL:ALL) ALL"))
@@ -44,3 +44,46 @@ fn prompts_for_password() {
     };
     assert_contains!(output.stderr(), diagnostic);
 }
+
+#[test]
+fn fails_without_privileges() {
+    let hostname = "container";
+    let env = Env("")
+        .user(User(USERNAME).password(PASSWORD))
+        .hostname(hostname)
+        .build();
+
+    let output = Command::new("sudo")
+        .args(["-S", "-v"])
+        .as_user(USERNAME)
+        .stdin(PASSWORD)
+        .output(&env);
+
+    output.assert_exit_code(1);
+
+    let diagnostic = format!("Sorry, user {USERNAME} may not run sudo on {hostname}.");
+    assert_contains!(output.stderr(), diagnostic);
+}
+
+#[test]
+fn non_interactive_does_not_prompt() {
+    let env = Env(format!("{USERNAME} ALL=(ALL:ALL) ALL"))
+        .user(User(USERNAME).password(PASSWORD))
+        .build();
+
+    let output = Command::new("sudo")
+        .args(["-S", "-n", "-v"])
+        .as_user(USERNAME)
+        .stdin(PASSWORD)
+        .output(&env);
+
+    output.assert_exit_code(1);
+
+    let diagnostic = if sudo_test::is_original_sudo() {
+        "a password is required"
+    } else {
+        "interactive authentication is required"
+    };
+    assert_contains!(output.stderr(), diagnostic);
+    assert_not_contains!(output.stderr(), "Password:");
+}
@@ -1,6 +1,6 @@
 use sudo_test::{Command, Env};
 
-use crate::SUDOERS_ALL_ALL_NOPASSWD;
+use crate::{SUDOERS_ALL_ALL_NOPASSWD, USERNAME};
 
 fn test_umask(config: &str, user_umask: &str, target_umask: &str) {
     let env = Env([SUDOERS_ALL_ALL_NOPASSWD, config]).build();
@@ -51,3 +51,39 @@ fn umask_override_0777() {
         "0022",
     );
 }
+
+fn test_created_file_mode(config: &str, user_umask: &str, expected_mode: u32) {
+    let env = Env([SUDOERS_ALL_ALL_NOPASSWD, config])
+        .user(USERNAME)
+        .build();
+
+    let path = "/tmp/created-by-sudo";
+    Command::new("sh")
+        .args(["-c", &format!("umask {user_umask}; sudo touch {path}")])
+        .as_user(USERNAME)
+        .output(&env)
+        .assert_success();
+
+    assert_eq!(env.stat(path).mode, expected_mode);
+}
+
+#[test]
+fn created_file_mode_restricted_without_override() {
+    // touch creates files with mode 0666, minus the union of 0027 and 0002
+    test_created_file_mode("Defaults umask=0027", "0002", 0o640);
+    test_created_file_mode("Defaults umask=0002", "0027", 0o640);
+}
+
+#[test]
+fn created_file_mode_replaced_with_override() {
+    test_created_file_mode(
+        "Defaults umask=0002\nDefaults umask_override",
+        "0077",
+        0o664,
+    );
+    test_created_file_mode(
+        "Defaults umask=0077\nDefaults umask_override",
+        "0002",
+        0o600,
+    );
+}
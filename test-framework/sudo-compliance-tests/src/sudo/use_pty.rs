@@ -226,3 +226,27 @@ fn stdout_pipe_tty() {
 
     assert_eq!(output.stdout(), "hello world");
 }
+
+#[test]
+fn exit_status_is_the_same_with_and_without_pty() {
+    let with_pty = Env([SUDOERS_ALL_ALL_NOPASSWD, "Defaults use_pty"]).build();
+    let without_pty = Env([SUDOERS_ALL_ALL_NOPASSWD, "Defaults !use_pty"]).build();
+
+    for script in [
+        "true",
+        "exit 3",
+        "exit 255",
+        "kill -TERM $$",
+        "kill -KILL $$",
+    ] {
+        let run = |env: &Env| {
+            Command::new("sudo")
+                .args(["sh", "-c", script])
+                .tty(true)
+                .output(env)
+                .status()
+        };
+
+        assert_eq!(run(&with_pty), run(&without_pty), "{script}");
+    }
+}
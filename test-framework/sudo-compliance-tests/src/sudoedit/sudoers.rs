@@ -219,3 +219,30 @@ fn passwd() {
         assert_contains!(output.stderr(), "A terminal is required to authenticate");
     }
 }
+
+#[test]
+fn sudoedit_authenticate_ignores_nopasswd() {
+    // sudoedit_authenticate is specific to sudo-rs
+    if sudo_test::is_original_sudo() {
+        return;
+    }
+
+    let env = Env("Defaults sudoedit_authenticate\nALL ALL=(ALL:ALL) NOPASSWD: ALL")
+        .user(USERNAME)
+        .file(DEFAULT_EDITOR, TextFile(EDITOR_DUMMY).chmod(CHMOD_EXEC))
+        .build();
+
+    Command::new("sudo")
+        .as_user(USERNAME)
+        .args(["-n", "true"])
+        .output(&env)
+        .assert_success();
+
+    let output = Command::new("sudoedit")
+        .as_user(USERNAME)
+        .args(["-n", "/foo.txt"])
+        .output(&env);
+
+    output.assert_exit_code(1);
+    assert_contains!(output.stderr(), "interactive authentication is required");
+}
@@ -154,6 +154,27 @@ fn flag_file_bad_syntax() {
     assert_contains!(output.stderr(), "syntax error");
 }
 
+#[test]
+fn flag_file_reports_position_of_syntax_errors() {
+    let file_path = TMP_SUDOERS;
+    let env = Env("")
+        .file(
+            file_path,
+            format!("{SUDOERS_ALL_ALL_NOPASSWD}\nthis is fine"),
+        )
+        .build();
+
+    let output = Command::new("visudo")
+        .args(["--check", "--file", file_path])
+        .output(&env);
+
+    output.assert_exit_code(1);
+    assert_contains!(output.stderr(), "syntax error");
+    if !sudo_test::is_original_sudo() {
+        assert_contains!(output.stderr(), format!("{file_path}:2:"));
+    }
+}
+
 #[test]
 fn flag_file_does_not_check_perms_nor_ownership() {
     let file_path = TMP_SUDOERS;
@@ -175,9 +196,22 @@ fn flag_file_does_not_check_perms_nor_ownership() {
 fn stdin() {
     let env = Env("").build();
 
-    Command::new("visudo")
+    let output = Command::new("visudo")
         .args(["-c", "-"])
         .stdin(SUDOERS_ALL_ALL_NOPASSWD.to_owned() + "\n")
+        .output(&env);
+
+    output.assert_success();
+    assert_contains!(output.stdout(), "stdin: parsed OK");
+}
+
+#[test]
+fn stdin_with_file_flag() {
+    let env = Env("").build();
+
+    Command::new("visudo")
+        .args(["--check", "--file", "-"])
+        .stdin(SUDOERS_ALL_ALL_NOPASSWD.to_owned() + "\n")
         .output(&env)
         .assert_success();
 }
@@ -193,4 +227,25 @@ fn stdin_bad_syntax() {
 
     output.assert_exit_code(1);
     assert_contains!(output.stderr(), "syntax error");
+    if !sudo_test::is_original_sudo() {
+        assert_contains!(output.stderr(), "stdin:1:");
+    }
+}
+
+#[test]
+fn unused_alias_is_a_warning() {
+    let env = Env(
+        TextFile("User_Alias UNUSED = ferris\nALL ALL=(ALL:ALL) NOPASSWD: ALL")
+            .chmod(DEFAULT_CHMOD),
+    )
+    .build();
+
+    let output = Command::new("visudo").arg("-c").output(&env);
+
+    output.assert_success();
+    assert_contains!(output.stdout_unchecked(), "parsed OK");
+    assert_contains!(output.stderr(), "unused User_Alias");
+    if !sudo_test::is_original_sudo() {
+        assert_contains!(output.stderr(), format!("{ETC_SUDOERS}:1:12: warning"));
+    }
 }
@@ -7,9 +7,9 @@ use std::{
     str,
 };
 
-use crate::{ROOT_GROUP, Result, SudoUnderTest, TextFile, base_image};
+use crate::{ROOT_GROUP, Result, SudoUnderTest, TextFile};
 
-pub use self::command::{As, Child, Command, Output};
+pub use self::command::{As, Child, Command, Output, PtyOutput};
 
 mod command;
 
@@ -19,6 +19,21 @@ pub struct Container {
     id: String,
 }
 
+/// ownership and mode of a file inside a container, as reported by `lstat`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileStat {
+    /// permission bits, including the setuid, setgid and sticky bits
+    pub mode: u32,
+    /// user ID of the owner
+    pub uid: u32,
+    /// group ID of the owner
+    pub gid: u32,
+    /// whether the path itself is a symbolic link
+    pub is_symlink: bool,
+    /// the target of the symbolic link, if `is_symlink` is set
+    pub link_target: Option<String>,
+}
+
 fn docker_command() -> StdCommand {
     if cfg!(target_os = "freebsd") {
         let mut cmd = StdCommand::new("sudo");
@@ -32,7 +47,7 @@ fn docker_command() -> StdCommand {
 fn docker_build_command(tag: &str) -> StdCommand {
     if cfg!(target_os = "freebsd") {
         let mut cmd = StdCommand::new("sudo");
-        cmd.args(["podman", "build", "-t", base_image()]);
+        cmd.args(["podman", "build", "-t", tag]);
         cmd
     } else {
         let mut cmd = StdCommand::new("docker");
@@ -55,6 +70,7 @@ impl Container {
         Self::new_with_hostname(
             image,
             None,
+            false,
             #[cfg(feature = "apparmor")]
             None,
         )
@@ -63,6 +79,7 @@ impl Container {
     pub fn new_with_hostname(
         image: &str,
         hostname: Option<&str>,
+        network: bool,
         #[cfg(feature = "apparmor")] apparmor_profile: Option<&str>,
     ) -> Self {
         let mut docker_run = docker_command();
@@ -75,7 +92,7 @@ impl Container {
         // Disable network access for the containers. This removes the overhead
         // of setting up a new network namespace and associated firewall rule
         // adjustments. On FreeBSD it seems to introduce extra overhead however.
-        if cfg!(not(target_os = "freebsd")) {
+        if cfg!(not(target_os = "freebsd")) && !network {
             docker_run.arg("--net=none");
         }
         if let Some(hostname) = hostname {
@@ -91,12 +108,29 @@ impl Container {
     #[track_caller]
     pub fn output(&self, cmd: &Command) -> Output {
         let stdin = cmd.get_stdin().map(str::as_bytes);
-        run(&mut self.docker_exec(cmd), stdin)
+        run(&mut self.docker_exec(cmd, cmd.get_tty()), stdin)
+    }
+
+    #[track_caller]
+    pub fn output_pty(&self, cmd: &Command) -> PtyOutput {
+        assert!(
+            cmd.get_stdin().is_none(),
+            "`stdin` cannot be combined with a pseudo-terminal"
+        );
+
+        let mut docker_exec = self.docker_exec(cmd, true);
+        match docker_exec.output() {
+            Ok(output) => PtyOutput {
+                status: output.status,
+                bytes: output.stdout,
+            },
+            Err(err) => panic!("running `{docker_exec:?}` failed: {err}"),
+        }
     }
 
     #[track_caller]
     pub fn spawn(&self, cmd: &Command) -> Child {
-        let mut docker_exec = self.docker_exec(cmd);
+        let mut docker_exec = self.docker_exec(cmd, cmd.get_tty());
 
         docker_exec.stdout(Stdio::piped()).stderr(Stdio::piped());
 
@@ -116,13 +150,13 @@ impl Container {
         }
     }
 
-    fn docker_exec(&self, cmd: &Command) -> process::Command {
+    fn docker_exec(&self, cmd: &Command, tty: bool) -> process::Command {
         let mut docker_exec = docker_command();
         docker_exec.arg("exec");
         if cmd.get_stdin().is_some() {
             docker_exec.arg("-i");
         }
-        if cmd.get_tty() {
+        if tty {
             docker_exec.arg("--tty");
         }
         if let Some(as_) = cmd.get_as() {
@@ -134,6 +168,38 @@ impl Container {
         docker_exec
     }
 
+    /// inspects the file at `path` without following symbolic links
+    #[track_caller]
+    pub fn stat(&self, path: &str) -> FileStat {
+        let format = if cfg!(target_os = "freebsd") {
+            ["-f", "%Mp%Lp %u %g %HT"]
+        } else {
+            ["-c", "%a %u %g %F"]
+        };
+        let stdout = self
+            .output(Command::new("stat").args(format).arg(path))
+            .stdout();
+
+        let mut parts = stdout.splitn(4, ' ');
+        let (Some(mode), Some(uid), Some(gid), Some(file_type)) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            panic!("unexpected `stat` output for {path}: {stdout}");
+        };
+
+        let is_symlink = file_type.eq_ignore_ascii_case("symbolic link");
+        let link_target =
+            is_symlink.then(|| self.output(Command::new("readlink").arg(path)).stdout());
+
+        FileStat {
+            mode: u32::from_str_radix(mode, 8).unwrap(),
+            uid: uid.parse().unwrap(),
+            gid: gid.parse().unwrap(),
+            is_symlink,
+            link_target,
+        }
+    }
+
     pub fn cp_many<'a>(&self, files: impl IntoIterator<Item = (&'a String, &'a TextFile)>) {
         let mut builder = tar::Builder::new(vec![]);
         let mut file_chown = vec![];
@@ -208,11 +274,11 @@ impl Drop for Container {
     }
 }
 
-pub fn build_base_image() {
+pub fn build_base_image(under_test: SudoUnderTest) {
     let repo_root = repo_root();
-    let mut cmd = docker_build_command(base_image());
+    let mut cmd = docker_build_command(under_test.base_image());
 
-    match SudoUnderTest::from_env() {
+    match under_test {
         SudoUnderTest::Ours => {
             let sudo_build_features: String =
                 env::var("SUDO_BUILD_FEATURES").unwrap_or_else(|_| {
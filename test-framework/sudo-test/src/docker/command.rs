@@ -210,6 +210,42 @@ impl Output {
     }
 }
 
+/// the raw output of a `Command` that ran with a pseudo-terminal as its controlling terminal
+///
+/// unlike `Output`, nothing is removed from or translated in the captured bytes: they include
+/// escape sequences, the `\r\n` line endings produced by the terminal and anything the program
+/// wrote to its standard error
+#[must_use]
+#[derive(Debug)]
+pub struct PtyOutput {
+    pub(super) status: ExitStatus,
+    pub(super) bytes: Vec<u8>,
+}
+
+impl PtyOutput {
+    /// the status (exit code) of the finished `Command`
+    pub fn status(&self) -> ExitStatus {
+        self.status
+    }
+
+    /// the bytes written to the pseudo-terminal, exactly as they were captured
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// helper method that asserts that the program exited successfully
+    #[track_caller]
+    pub fn assert_success(&self) {
+        if !self.status.success() {
+            panic!(
+                "program failed with {}\noutput:\n{}",
+                self.status,
+                String::from_utf8_lossy(&self.bytes).escape_debug()
+            );
+        }
+    }
+}
+
 impl TryFrom<process::Output> for Output {
     type Error = Error;
 
@@ -16,7 +16,7 @@ use std::{
 use docker::{As, Container};
 
 pub use constants::*;
-pub use docker::{Child, Command, Output};
+pub use docker::{Child, Command, FileStat, Output, PtyOutput};
 
 mod constants;
 mod docker;
@@ -26,14 +26,6 @@ pub mod macros;
 type Error = Box<dyn std::error::Error>;
 type Result<T> = core::result::Result<T, Error>;
 
-fn base_image() -> &'static str {
-    if is_original_sudo() {
-        "sudo-test-og"
-    } else {
-        "sudo-test-rs"
-    }
-}
-
 /// are we testing the original sudo?
 pub fn is_original_sudo() -> bool {
     matches!(SudoUnderTest::from_env(), SudoUnderTest::Theirs)
@@ -77,12 +69,20 @@ pub fn sudo_version() -> SudoVersion {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
 enum SudoUnderTest {
     Ours,
     Theirs,
 }
 
 impl SudoUnderTest {
+    fn base_image(self) -> &'static str {
+        match self {
+            Self::Ours => "sudo-test-rs",
+            Self::Theirs => "sudo-test-og",
+        }
+    }
+
     fn from_env() -> Self {
         if let Ok(under_test) = env::var("SUDO_UNDER_TEST") {
             if under_test == "ours" {
@@ -108,6 +108,14 @@ pub struct Env {
     users: HashSet<Username>,
 }
 
+impl Env {
+    /// inspects ownership and mode of the file at `path` without following symbolic links
+    #[track_caller]
+    pub fn stat(&self, path: &str) -> FileStat {
+        self.container.stat(path)
+    }
+}
+
 /// creates a new test environment builder that contains the specified `/etc/sudoers` file
 #[allow(non_snake_case)]
 pub fn Env(sudoers: impl Into<TextFile>) -> EnvBuilder {
@@ -168,6 +176,25 @@ impl Command {
         env.container.output(self)
     }
 
+    /// executes the command in the specified test environment with a pseudo-terminal as its
+    /// controlling terminal, and captures everything written to that terminal as raw bytes
+    ///
+    /// # Panics
+    ///
+    /// like `output`, this method panics if the requested `as_user` does not exist in the test
+    /// environment. it also panics if `stdin` was set, since the pseudo-terminal replaces it
+    #[track_caller]
+    pub fn output_pty(&self, env: &Env) -> PtyOutput {
+        if let Some(As::User(username)) = self.get_as() {
+            assert!(
+                env.users.contains(username),
+                "tried to exec as non-existent user: {username}"
+            );
+        }
+
+        env.container.output_pty(self)
+    }
+
     /// spawns the command in the specified test environment
     #[track_caller]
     pub fn spawn(&self, env: &Env) -> Child {
@@ -180,6 +207,70 @@ impl Command {
 
         env.container.spawn(self)
     }
+
+    /// executes the command with both original sudo and sudo-rs and asserts that the exit code,
+    /// stdout and stderr are the same, except for the `allowed` divergences
+    ///
+    /// NOTE like `output`, this method panics if the requested `as_user` does not exist in the
+    /// test environment
+    #[track_caller]
+    pub fn assert_parity(&self, env: &ParityEnv, allowed: &[Divergence]) {
+        let theirs = self.output(&env.theirs);
+        let ours = self.output(&env.ours);
+
+        let mut mismatches = vec![];
+        if !allowed.contains(&Divergence::ExitCode) && theirs.status() != ours.status() {
+            mismatches.push(format!(
+                "exit status: {} (theirs) vs {} (ours)",
+                theirs.status(),
+                ours.status()
+            ));
+        }
+        if !allowed.contains(&Divergence::Stdout)
+            && theirs.stdout_unchecked() != ours.stdout_unchecked()
+        {
+            mismatches.push(format!(
+                "stdout:\n{}\n(theirs) vs\n{}\n(ours)",
+                theirs.stdout_unchecked(),
+                ours.stdout_unchecked()
+            ));
+        }
+        if !allowed.contains(&Divergence::Stderr) && theirs.stderr() != ours.stderr() {
+            mismatches.push(format!(
+                "stderr:\n{}\n(theirs) vs\n{}\n(ours)",
+                theirs.stderr(),
+                ours.stderr()
+            ));
+        }
+
+        if !mismatches.is_empty() {
+            panic!(
+                "sudo-rs does not behave like original sudo\n\n{}",
+                mismatches.join("\n\n")
+            );
+        }
+    }
+}
+
+/// a pair of test environments built from the same `EnvBuilder`, one with original sudo and one
+/// with sudo-rs
+pub struct ParityEnv {
+    /// the environment with original sudo installed
+    pub theirs: Env,
+    /// the environment with sudo-rs installed
+    pub ours: Env,
+}
+
+/// a known, intentional difference between original sudo and sudo-rs that
+/// `Command::assert_parity` should not report
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Divergence {
+    /// the exit code may differ
+    ExitCode,
+    /// the standard output may differ
+    Stdout,
+    /// the standard error may differ, e.g. because of differently worded diagnostics
+    Stderr,
 }
 
 /// test environment builder
@@ -190,6 +281,7 @@ pub struct EnvBuilder {
     default_files: HashMap<AbsolutePath, TextFile>,
     groups: HashMap<Groupname, Group>,
     hostname: Option<String>,
+    network: bool,
     users: HashMap<Username, User>,
     user_passwords: HashMap<String, String>,
     #[cfg(feature = "apparmor")]
@@ -320,6 +412,13 @@ impl EnvBuilder {
         self
     }
 
+    /// Gives the container a network interface besides the loopback interface; by default,
+    /// containers have no network access
+    pub fn network(&mut self) -> &mut Self {
+        self.network = true;
+        self
+    }
+
     /// builds the test environment
     ///
     /// # Panics
@@ -329,22 +428,40 @@ impl EnvBuilder {
     /// - if any specified `user` tries to use a user ID that already exists in the base image
     /// - if any specified `group` tries to use a group ID that already exists in the base image
     pub fn build(&self) -> Env {
-        static FAILED: AtomicBool = AtomicBool::new(false);
-        static ONCE: Once = Once::new();
-        ONCE.call_once(|| {
-            if std::panic::catch_unwind(docker::build_base_image).is_err() {
-                FAILED.store(true, Ordering::Relaxed);
+        self.build_for(SudoUnderTest::from_env())
+    }
+
+    /// builds the test environment twice: once with original sudo and once with sudo-rs
+    ///
+    /// # Panics
+    ///
+    /// see `build`
+    pub fn build_parity(&self) -> ParityEnv {
+        ParityEnv {
+            theirs: self.build_for(SudoUnderTest::Theirs),
+            ours: self.build_for(SudoUnderTest::Ours),
+        }
+    }
+
+    fn build_for(&self, under_test: SudoUnderTest) -> Env {
+        static FAILED: [AtomicBool; 2] = [const { AtomicBool::new(false) }; 2];
+        static ONCE: [Once; 2] = [const { Once::new() }; 2];
+        let index = under_test as usize;
+        ONCE[index].call_once(|| {
+            if std::panic::catch_unwind(|| docker::build_base_image(under_test)).is_err() {
+                FAILED[index].store(true, Ordering::Relaxed);
             }
         });
-        if FAILED.load(Ordering::Relaxed) {
+        if FAILED[index].load(Ordering::Relaxed) {
             // Fail the test, but do so silently to reduce the chance the actual error message
             // disappears from the terminal scrollback buffer.
             std::panic::resume_unwind(Box::new(()));
         }
 
         let container = Container::new_with_hostname(
-            base_image(),
+            under_test.base_image(),
             self.hostname.as_deref(),
+            self.network,
             #[cfg(feature = "apparmor")]
             self.apparmor_profile.as_deref(),
         );
@@ -441,7 +558,7 @@ impl EnvBuilder {
 
             let _ = Command::new("chmod").arg("755").arg("/home").output(&env);
 
-            if is_original_sudo() {
+            if under_test == SudoUnderTest::Theirs {
                 Command::new("chflags")
                     .arg("noschg")
                     .arg("/usr/bin/su")
@@ -1015,6 +1132,44 @@ mod tests {
         assert!(ls_l.contains(&format!("{USERNAME} {GROUPNAME}")));
     }
 
+    #[test]
+    fn stat_reports_ownership_and_mode() {
+        let path = "/root/file";
+        let link = "/root/link";
+        let env = EnvBuilder::default()
+            .user(User(USERNAME).id(1023))
+            .group(Group(GROUPNAME).id(1024))
+            .file(
+                path,
+                TextFile("hello")
+                    .chown(format!("{USERNAME}:{GROUPNAME}"))
+                    .chmod("640"),
+            )
+            .build();
+
+        Command::new("ln")
+            .args(["-s", path, link])
+            .output(&env)
+            .assert_success();
+
+        let stat = env.stat(path);
+        assert_eq!(
+            FileStat {
+                mode: 0o640,
+                uid: 1023,
+                gid: 1024,
+                is_symlink: false,
+                link_target: None,
+            },
+            stat
+        );
+
+        let stat = env.stat(link);
+        assert_eq!(0, stat.uid);
+        assert!(stat.is_symlink);
+        assert_eq!(Some(path), stat.link_target.as_deref());
+    }
+
     #[test]
     #[should_panic = "user root already exists in base image"]
     fn cannot_create_user_that_already_exists_in_base_image() {
@@ -1237,6 +1392,41 @@ mod tests {
         assert!(SudoVersion::Ours > SudoVersion::Theirs(99, 99, 99, 99));
     }
 
+    #[test]
+    fn parity_of_simple_command() {
+        let env = Env("ALL ALL=(ALL:ALL) NOPASSWD: ALL")
+            .user(USERNAME)
+            .build_parity();
+
+        Command::new("sudo")
+            .args([
+                "-u",
+                USERNAME,
+                "sh",
+                "-c",
+                "echo hello; echo world >&2; exit 3",
+            ])
+            .assert_parity(&env, &[]);
+
+        // `-V` prints a differently worded version banner
+        Command::new("sudo")
+            .arg("-V")
+            .assert_parity(&env, &[Divergence::Stdout]);
+    }
+
+    #[test]
+    fn output_pty_captures_raw_bytes() {
+        let env = EnvBuilder::default().build();
+
+        let output = Command::new("sh")
+            .args(["-c", "test -t 1 && printf '\\033[1mbold\\033[0m\\n'"])
+            .output_pty(&env);
+        output.assert_success();
+
+        // the terminal translates the newline into a carriage return and a line feed
+        assert_eq!(output.bytes(), b"\x1b[1mbold\x1b[0m\r\n");
+    }
+
     #[cfg(feature = "apparmor")]
     #[test]
     fn setting_apparmor_works() -> Result<()> {